@@ -0,0 +1,862 @@
+//! Prebuilt automata for matching the terms of a thesaurus
+//!
+//! Building an automata from a large thesaurus means normalizing and
+//! indexing every single term, which dominates startup time. An [`Automata`]
+//! is built once and can then be shared between all matches against the same
+//! thesaurus. It can also be written to disk as a compact, versioned artifact,
+//! which is memory mapped when loaded again, so no rebuild is needed at all.
+//!
+//! The normalized terms are stored in a finite state transducer (FST) which
+//! maps every term to the index of its entry in the thesaurus. The artifact
+//! layout is
+//!
+//! | bytes            | content                                          |
+//! |------------------|--------------------------------------------------|
+//! | 8                | magic bytes `TRPHAUTM`                           |
+//! | 4                | version of the format, little endian             |
+//! | 8                | length of the header, little endian              |
+//! | n                | JSON header with the name, the normalization,    |
+//! |                  | the number of entries and relations and the hash |
+//! | 16 × entries     | concept ID and entry index pairs, sorted by ID   |
+//! | 8 × (records + 1)| offsets of the records, relative to the first one|
+//! | records          | entries sorted by key, then relations, as JSON   |
+//! | rest             | the FST                                          |
+//!
+//! All integers of the tables are little endian. Entries are only decoded
+//! when they are first looked up, so loading an artifact doesn't depend on
+//! the size of the thesaurus.
+
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, OnceLock};
+
+use fst::automaton::{Automaton, Levenshtein, Str};
+use fst::raw::Output;
+use fst::{IntoStreamer, Map, MapBuilder, Streamer};
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use terraphim_types::hierarchy::narrower_transitive;
+use terraphim_types::{ConceptRelation, NormalizedTerm, NormalizedTermValue, Thesaurus};
+
+use crate::matcher::{is_on_word_boundary, is_word_char, MatchMode, Matched};
+use crate::normalize::{normalize, NormalizationOptions, NormalizedText};
+use crate::{Result, TerraphimAutomataError};
+
+/// Magic bytes at the start of every automata artifact
+const MAGIC: &[u8; 8] = b"TRPHAUTM";
+
+/// Current version of the artifact format
+///
+/// Bump it whenever the layout of the artifact changes, so that stale
+/// artifacts are rejected instead of matching garbage.
+pub const AUTOMATA_VERSION: u32 = 2;
+
+/// Length of the magic bytes, the version and the header length
+const PREAMBLE_LEN: usize = MAGIC.len() + 4 + 8;
+
+/// Length of a concept ID and entry index pair
+const CONCEPT_LEN: usize = 16;
+
+/// An entry of the thesaurus, a synonym and its concept
+type Entry = (NormalizedTermValue, NormalizedTerm);
+
+/// Everything of an artifact which isn't stored in a table
+#[derive(Serialize, Deserialize)]
+struct Header {
+    name: String,
+    normalization: NormalizationOptions,
+    /// Number of entries of the thesaurus
+    entries: u64,
+    /// Number of relations between the concepts of the thesaurus
+    relations: u64,
+    /// Content hash of the thesaurus, see [`Automata::thesaurus_hash`]
+    thesaurus_hash: u64,
+}
+
+/// Backing memory of an artifact
+enum Storage {
+    Owned(Vec<u8>),
+    Mapped(Mmap),
+}
+
+impl Storage {
+    fn bytes(&self) -> &[u8] {
+        match self {
+            Storage::Owned(bytes) => bytes.as_slice(),
+            Storage::Mapped(mmap) => &mmap[..],
+        }
+    }
+}
+
+/// The FST part of the backing memory
+pub(crate) struct FstBytes {
+    storage: Arc<Storage>,
+    offset: usize,
+}
+
+impl AsRef<[u8]> for FstBytes {
+    fn as_ref(&self) -> &[u8] {
+        &self.storage.bytes()[self.offset..]
+    }
+}
+
+/// Positions of the tables of an artifact
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    /// Start of the concept table
+    concepts: usize,
+    /// Start of the offset table
+    offsets: usize,
+    /// Start of the first record
+    records: usize,
+    /// Start of the FST
+    fst: usize,
+}
+
+/// A term of the thesaurus completing a prefix
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion<'a> {
+    /// The synonym in the thesaurus
+    pub key: &'a NormalizedTermValue,
+    /// The concept of the synonym
+    pub term: &'a NormalizedTerm,
+    /// Whether the prefix only matches with a typo
+    pub fuzzy: bool,
+}
+
+/// A prebuilt automata for the terms of a thesaurus
+///
+/// Matching is leftmost-longest: at every position of the text the longest
+/// term wins, and matches don't overlap.
+pub struct Automata {
+    name: String,
+    normalization: NormalizationOptions,
+    thesaurus_hash: u64,
+    relations: Vec<ConceptRelation>,
+    storage: Arc<Storage>,
+    layout: Layout,
+    /// Entries of the thesaurus, indexed by the values of the FST and
+    /// decoded on first use
+    entries: Box<[OnceLock<Option<Box<Entry>>>]>,
+    fst: Map<FstBytes>,
+    /// Number of words of the longest term, computed on first use
+    max_words: OnceLock<usize>,
+}
+
+impl fmt::Debug for Automata {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Automata")
+            .field("name", &self.name)
+            .field("normalization", &self.normalization)
+            .field("terms", &self.entries.len())
+            .field("patterns", &self.fst.len())
+            .finish()
+    }
+}
+
+impl Automata {
+    /// Build an automata for all terms of the thesaurus
+    ///
+    /// If several terms are the same after normalization, the
+    /// alphabetically first one is matched.
+    pub fn new(thesaurus: &Thesaurus, normalization: NormalizationOptions) -> Result<Self> {
+        let mut entries: Vec<(&NormalizedTermValue, &NormalizedTerm)> =
+            thesaurus.into_iter().collect();
+        entries.sort_unstable_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+
+        let mut patterns: Vec<(String, u64)> = entries
+            .iter()
+            .enumerate()
+            .map(|(index, (key, _))| (normalize(key.as_str(), normalization), index as u64))
+            // Empty patterns would match everywhere
+            .filter(|(pattern, _)| !pattern.is_empty())
+            .collect();
+        patterns.sort_unstable();
+        patterns.dedup_by(|a, b| a.0 == b.0);
+
+        let mut builder = MapBuilder::memory();
+        for (pattern, index) in patterns {
+            builder.insert(pattern, index)?;
+        }
+        let fst = builder.into_inner()?;
+
+        let mut concepts: Vec<(u64, u64)> = entries
+            .iter()
+            .enumerate()
+            .map(|(index, (_, term))| (term.id, index as u64))
+            .collect();
+        concepts.sort_unstable();
+
+        // The offset table is followed by the records
+        let mut records = Vec::new();
+        let mut offsets = vec![0];
+        for entry in &entries {
+            serde_json::to_writer(&mut records, entry)?;
+            offsets.push(records.len() as u64);
+        }
+        let relations: Vec<&ConceptRelation> = thesaurus.relations().collect();
+        for relation in &relations {
+            serde_json::to_writer(&mut records, relation)?;
+            offsets.push(records.len() as u64);
+        }
+        let mut tables =
+            Vec::with_capacity(concepts.len() * CONCEPT_LEN + offsets.len() * 8 + records.len());
+        for (id, index) in concepts {
+            tables.extend_from_slice(&id.to_le_bytes());
+            tables.extend_from_slice(&index.to_le_bytes());
+        }
+        for offset in offsets {
+            tables.extend_from_slice(&offset.to_le_bytes());
+        }
+        tables.extend_from_slice(&records);
+
+        let header = serde_json::to_vec(&Header {
+            name: thesaurus.name().to_string(),
+            normalization,
+            entries: entries.len() as u64,
+            relations: relations.len() as u64,
+            thesaurus_hash: content_hash(thesaurus.name(), &tables),
+        })?;
+        let mut bytes = Vec::with_capacity(PREAMBLE_LEN + header.len() + tables.len() + fst.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&AUTOMATA_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&(header.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&header);
+        bytes.extend_from_slice(&tables);
+        bytes.extend_from_slice(&fst);
+        Self::from_bytes(bytes)
+    }
+
+    /// Name of the thesaurus the automata was built from
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// How the terms were normalized
+    pub fn normalization(&self) -> NormalizationOptions {
+        self.normalization
+    }
+
+    /// Content hash of the thesaurus the automata was built from
+    ///
+    /// Automata built from equal thesauri have the same hash, regardless of
+    /// their normalization, so this is a cheap way to tell whether state
+    /// derived from a thesaurus is still valid. The hash is stable across
+    /// releases and platforms.
+    pub fn thesaurus_hash(&self) -> u64 {
+        self.thesaurus_hash
+    }
+
+    /// Number of terms in the thesaurus
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Check if the thesaurus is empty
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The thesaurus the automata was built from
+    ///
+    /// This decodes and copies every entry, so prefer looking up single
+    /// terms with [`Automata::get`] or [`Automata::concept`].
+    pub fn thesaurus(&self) -> Thesaurus {
+        let mut thesaurus = Thesaurus::new(self.name.clone());
+        for (key, term) in (0..self.len() as u64).filter_map(|index| self.entry(index)) {
+            thesaurus.insert(key.clone(), term.clone());
+        }
+        for relation in &self.relations {
+            thesaurus.add_relation(relation.from, relation.kind, relation.to);
+        }
+        thesaurus
+    }
+
+    /// The concept of a term of the thesaurus, like [`Thesaurus::get`]
+    pub fn get(&self, key: &NormalizedTermValue) -> Option<&NormalizedTerm> {
+        // Entries are sorted by their key
+        let (mut low, mut high) = (0, self.len() as u64);
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let (other, term) = self.entry(middle)?;
+            match other.as_str().cmp(key.as_str()) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => return Some(term),
+            }
+        }
+        None
+    }
+
+    /// The concept with the given ID
+    pub fn concept(&self, id: u64) -> Option<&NormalizedTerm> {
+        self.concept_entries(id).next().map(|(_, term)| term)
+    }
+
+    /// All terms of the thesaurus for the concept with the given ID
+    ///
+    /// The terms are looked up in the concept table, so this is cheap to
+    /// call repeatedly, e.g. for every concept of a query.
+    pub fn synonyms(&self, id: u64) -> Vec<&str> {
+        self.concept_entries(id)
+            .map(|(key, _)| key.as_str())
+            .collect()
+    }
+
+    /// Relations between the concepts of the thesaurus
+    pub fn relations(&self) -> &[ConceptRelation] {
+        &self.relations
+    }
+
+    /// IDs of all concepts below a concept, like
+    /// [`Thesaurus::narrower_transitive`]
+    pub fn narrower_transitive(&self, id: u64) -> BTreeSet<u64> {
+        narrower_transitive(&self.relations, id)
+    }
+
+    /// The entries of the concept with the given ID, sorted by their key
+    fn concept_entries(&self, id: u64) -> impl Iterator<Item = &Entry> {
+        let concept = |position: usize| {
+            let start = self.layout.concepts + position * CONCEPT_LEN;
+            (
+                read_u64(self.storage.bytes(), start),
+                read_u64(self.storage.bytes(), start + 8),
+            )
+        };
+        let first = partition_point(self.len(), |position| concept(position).0 < id);
+        (first..self.len())
+            .map(concept)
+            .take_while(move |(other, _)| *other == id)
+            .filter_map(|(_, index)| self.entry(index))
+    }
+
+    /// Find all terms in the text
+    ///
+    /// The text is normalized the same way as the terms. Positions point
+    /// into the original, not the normalized text.
+    pub fn find_matches(
+        &self,
+        text: &str,
+        return_positions: bool,
+        mode: MatchMode,
+    ) -> Vec<Matched> {
+        let text = NormalizedText::new(text, self.normalization);
+        self.find_iter(text.as_str(), mode)
+            .map(|(start, end, (key, term))| Matched {
+                term: key.to_string(),
+                normalized_term: term.clone(),
+                pos: return_positions.then(|| text.original_range(start, end)),
+                mode,
+            })
+            .collect()
+    }
+
+    /// Find the normalized terms of all terms in the text
+    ///
+    /// This is [`Automata::find_matches`] without positions and without
+    /// copying the terms.
+    pub fn find_terms(&self, text: &str, mode: MatchMode) -> Vec<&NormalizedTerm> {
+        let text = normalize(text, self.normalization);
+        self.find_iter(&text, mode)
+            .map(|(_, _, (_, term))| term)
+            .collect()
+    }
+
+    /// The FST mapping normalized terms to their index
+    pub(crate) fn fst(&self) -> &Map<FstBytes> {
+        &self.fst
+    }
+
+    /// The entry of the thesaurus with the given index
+    ///
+    /// Entries are decoded from the artifact on first use. Entries which
+    /// can't be decoded are skipped with a warning.
+    pub(crate) fn entry(&self, index: u64) -> Option<&(NormalizedTermValue, NormalizedTerm)> {
+        let index = usize::try_from(index).ok()?;
+        self.entries
+            .get(index)?
+            .get_or_init(|| match self.record(index).map(serde_json::from_slice) {
+                Some(Ok(entry)) => Some(Box::new(entry)),
+                Some(Err(e)) => {
+                    log::warn!("Skipping entry {index} of automata `{}`: {e}", self.name);
+                    None
+                }
+                None => {
+                    log::warn!(
+                        "Skipping entry {index} of automata `{}`: out of bounds",
+                        self.name
+                    );
+                    None
+                }
+            })
+            .as_deref()
+    }
+
+    /// The bytes of the record with the given index, if its offsets are
+    /// within the records
+    fn record(&self, index: usize) -> Option<&[u8]> {
+        let bytes = self.storage.bytes();
+        let offset =
+            |index: usize| usize::try_from(read_u64(bytes, self.layout.offsets + index * 8)).ok();
+        let start = self.layout.records.checked_add(offset(index)?)?;
+        let end = self.layout.records.checked_add(offset(index + 1)?)?;
+        (start <= end && end <= self.layout.fst).then(|| &bytes[start..end])
+    }
+
+    /// Number of words of the longest normalized term
+    pub(crate) fn max_words(&self) -> usize {
+        *self.max_words.get_or_init(|| {
+            let mut max_words = 0;
+            let mut stream = self.fst.keys();
+            while let Some(key) = stream.next() {
+                let words = String::from_utf8_lossy(key)
+                    .split(|c: char| !is_word_char(c))
+                    .filter(|word| !word.is_empty())
+                    .count();
+                max_words = max_words.max(words);
+            }
+            max_words
+        })
+    }
+
+    /// Complete a prefix to terms of the thesaurus
+    ///
+    /// The prefix is normalized the same way as the terms. Terms starting
+    /// with the prefix come first, followed by terms starting with the
+    /// prefix after fixing a typo. Prefixes of at least four characters
+    /// tolerate one typo, prefixes of at least eight characters two.
+    pub fn complete(&self, prefix: &str) -> Vec<Completion<'_>> {
+        let prefix = normalize(prefix, self.normalization);
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+        let mut found = vec![false; self.len()];
+        let mut completions = Vec::new();
+        self.collect_completions(
+            Str::new(&prefix).starts_with(),
+            false,
+            &mut found,
+            &mut completions,
+        );
+
+        let distance = match prefix.chars().count() {
+            0..=3 => return completions,
+            4..=7 => 1,
+            _ => 2,
+        };
+        match Levenshtein::new(&prefix, distance) {
+            Ok(levenshtein) => self.collect_completions(
+                levenshtein.starts_with(),
+                true,
+                &mut found,
+                &mut completions,
+            ),
+            Err(e) => log::debug!("Skipping typo tolerant completion of `{prefix}`: {e}"),
+        }
+        completions
+    }
+
+    /// Collect the terms accepted by the automaton, skipping the ones which
+    /// were already found
+    fn collect_completions<'a, A: Automaton>(
+        &'a self,
+        automaton: A,
+        fuzzy: bool,
+        found: &mut [bool],
+        completions: &mut Vec<Completion<'a>>,
+    ) {
+        let mut stream = self.fst.search(automaton).into_stream();
+        while let Some((_, index)) = stream.next() {
+            if let Some((key, term)) = self.entry(index) {
+                if !std::mem::replace(&mut found[index as usize], true) {
+                    completions.push(Completion { key, term, fuzzy });
+                }
+            }
+        }
+    }
+
+    /// Iterate over the matches in an already normalized text
+    ///
+    /// With [`MatchMode::WordBoundary`], the longest term on word boundaries
+    /// wins at every position.
+    fn find_iter<'a, 't>(
+        &'a self,
+        text: &'t str,
+        mode: MatchMode,
+    ) -> impl Iterator<Item = (usize, usize, &'a (NormalizedTermValue, NormalizedTerm))> + 't
+    where
+        'a: 't,
+    {
+        let mut position = 0;
+        std::iter::from_fn(move || {
+            while position < text.len() {
+                let start = position;
+                position += text[start..].chars().next().map_or(1, char::len_utf8);
+                if let Some((end, index)) = self.longest_match(text, start, mode) {
+                    position = end;
+                    if let Some(entry) = self.entry(index as u64) {
+                        return Some((start, end, entry));
+                    }
+                }
+            }
+            None
+        })
+    }
+
+    /// Longest term starting at the given position of the text, together
+    /// with its end
+    pub(crate) fn longest_match(
+        &self,
+        text: &str,
+        start: usize,
+        mode: MatchMode,
+    ) -> Option<(usize, usize)> {
+        let fst = self.fst.as_fst();
+        let mut node = fst.root();
+        let mut output = Output::zero();
+        let mut longest = None;
+        for (offset, byte) in text.as_bytes()[start..].iter().enumerate() {
+            let Some(index) = node.find_input(*byte) else {
+                break;
+            };
+            let transition = node.transition(index);
+            output = output.cat(transition.out);
+            node = fst.node(transition.addr);
+            let end = start + offset + 1;
+            if node.is_final()
+                && (mode == MatchMode::Substring || is_on_word_boundary(text, start, end))
+            {
+                let index = output.cat(node.final_output()).value() as usize;
+                // Indices of artifacts aren't validated on load
+                if index < self.len() {
+                    longest = Some((end, index));
+                }
+            }
+            if node.is_empty() {
+                break;
+            }
+        }
+        longest
+    }
+
+    /// Serialize the automata into an artifact
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        Ok(self.storage.bytes().to_vec())
+    }
+
+    /// Deserialize an automata from an artifact
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self> {
+        Self::from_storage(Storage::Owned(bytes))
+    }
+
+    /// Write the automata as artifact to a file
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let mut file = File::create(path)?;
+        file.write_all(self.storage.bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// Load an automata artifact from a file
+    ///
+    /// The file is memory mapped, so only the header and the relations get
+    /// parsed. Entries are decoded when they are first looked up.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: artifacts are written once and never modified in place.
+        // Truncating the file while it is mapped is undefined behaviour, the
+        // same as for every other memory mapped file.
+        let mmap = unsafe { Mmap::map(&file)? };
+        Self::from_storage(Storage::Mapped(mmap))
+    }
+
+    fn from_storage(storage: Storage) -> Result<Self> {
+        let storage = Arc::new(storage);
+        let bytes = storage.bytes();
+        if bytes.len() < PREAMBLE_LEN || &bytes[..MAGIC.len()] != MAGIC {
+            return Err(TerraphimAutomataError::Artifact(
+                "not an automata artifact".to_string(),
+            ));
+        }
+        let version = u32::from_le_bytes(bytes[8..12].try_into().unwrap());
+        if version != AUTOMATA_VERSION {
+            return Err(TerraphimAutomataError::Artifact(format!(
+                "unsupported version {version}, expected {AUTOMATA_VERSION}"
+            )));
+        }
+        let header_len = read_u64(bytes, 12);
+        let concepts = usize::try_from(header_len)
+            .ok()
+            .and_then(|len| PREAMBLE_LEN.checked_add(len))
+            .filter(|offset| *offset <= bytes.len())
+            .ok_or_else(|| TerraphimAutomataError::Artifact("truncated header".to_string()))?;
+        let header: Header = serde_json::from_slice(&bytes[PREAMBLE_LEN..concepts])?;
+
+        let truncated = || TerraphimAutomataError::Artifact("truncated tables".to_string());
+        let entries = usize::try_from(header.entries).map_err(|_| truncated())?;
+        let relations = usize::try_from(header.relations).map_err(|_| truncated())?;
+        let records = entries.checked_add(relations).ok_or_else(truncated)?;
+        let offsets = entries
+            .checked_mul(CONCEPT_LEN)
+            .and_then(|len| concepts.checked_add(len))
+            .ok_or_else(truncated)?;
+        let first_record = records
+            .checked_add(1)
+            .and_then(|count| count.checked_mul(8))
+            .and_then(|len| offsets.checked_add(len))
+            .filter(|offset| *offset <= bytes.len())
+            .ok_or_else(truncated)?;
+        let fst = usize::try_from(read_u64(bytes, first_record - 8))
+            .ok()
+            .and_then(|len| first_record.checked_add(len))
+            .filter(|offset| *offset <= bytes.len())
+            .ok_or_else(truncated)?;
+        let layout = Layout {
+            concepts,
+            offsets,
+            records: first_record,
+            fst,
+        };
+
+        let mut automata = Self {
+            name: header.name,
+            normalization: header.normalization,
+            thesaurus_hash: header.thesaurus_hash,
+            relations: Vec::with_capacity(relations),
+            entries: (0..entries).map(|_| OnceLock::new()).collect(),
+            fst: Map::new(FstBytes {
+                storage: Arc::clone(&storage),
+                offset: fst,
+            })?,
+            storage,
+            layout,
+            max_words: OnceLock::new(),
+        };
+        for index in entries..records {
+            let record = automata.record(index).ok_or_else(truncated)?;
+            automata.relations.push(serde_json::from_slice(record)?);
+        }
+        Ok(automata)
+    }
+}
+
+/// Reads a little endian `u64` at the given position, which has to be
+/// within the bytes
+fn read_u64(bytes: &[u8], position: usize) -> u64 {
+    u64::from_le_bytes(bytes[position..position + 8].try_into().unwrap())
+}
+
+/// Index of the first of `len` positions for which the predicate is false,
+/// like [`slice::partition_point`]
+fn partition_point(len: usize, mut pred: impl FnMut(usize) -> bool) -> usize {
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if pred(middle) {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
+    low
+}
+
+/// 64 bit FNV-1a hash of the name and the tables of a thesaurus
+///
+/// Unlike the hashers of the standard library, FNV-1a is stable across
+/// releases and platforms, so the hash can be persisted.
+fn content_hash(name: &str, tables: &[u8]) -> u64 {
+    let name_len = (name.len() as u64).to_le_bytes();
+    [&name_len[..], name.as_bytes(), tables]
+        .into_iter()
+        .flatten()
+        .fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+}
+
+/// Build an automata from the thesaurus at the given path
+///
+/// Artifacts are loaded as they are, unless they were built with different
+/// normalization options, in which case they get rebuilt. Remote thesauri
+/// are cached with the given options.
+pub async fn load_automata(
+    automata_path: &crate::AutomataPath,
+    normalization: NormalizationOptions,
+    remote: &crate::RemoteCacheOptions,
+) -> Result<Automata> {
+    if let crate::AutomataPath::Artifact(path) = automata_path {
+        let automata = Automata::load(path)?;
+        if automata.normalization() == normalization {
+            return Ok(automata);
+        }
+        log::info!("Rebuilding automata {path:?} with different normalization");
+        return Automata::new(&automata.thesaurus(), normalization);
+    }
+    let thesaurus = crate::load_thesaurus_with(automata_path, remote).await?;
+    Automata::new(&thesaurus, normalization)
+}
+
+/// Build an automata from a thesaurus file and write it as artifact
+pub fn build_artifact<P: AsRef<Path>, Q: AsRef<Path>>(
+    thesaurus_path: P,
+    artifact_path: Q,
+    normalization: NormalizationOptions,
+) -> Result<Automata> {
+    let thesaurus: Thesaurus = serde_json::from_str(&fs::read_to_string(thesaurus_path)?)?;
+    let automata = Automata::new(&thesaurus, normalization)?;
+    automata.save(artifact_path)?;
+    Ok(automata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use terraphim_types::RelationKind;
+
+    fn sample_thesaurus() -> Thesaurus {
+        let mut thesaurus = Thesaurus::new("streaming".to_string());
+        for (id, key, value) in [
+            (1, "kafka", "kafka"),
+            (2, "event streaming", "event streaming"),
+            (2, "event", "event streaming"),
+            (3, "straße", "straße"),
+        ] {
+            thesaurus.insert(key.into(), NormalizedTerm::new(id, value.into()));
+        }
+        thesaurus.add_relation(2, RelationKind::Broader, 1);
+        thesaurus
+    }
+
+    #[test]
+    fn test_synonyms() {
+        let automata = Automata::new(&sample_thesaurus(), NormalizationOptions::default()).unwrap();
+        assert_eq!(automata.synonyms(2), ["event", "event streaming"]);
+        assert_eq!(automata.synonyms(1), ["kafka"]);
+        assert!(automata.synonyms(42).is_empty());
+    }
+
+    #[test]
+    fn test_lookup() {
+        let automata = Automata::new(&sample_thesaurus(), NormalizationOptions::default()).unwrap();
+        assert_eq!(automata.get(&"event".into()).unwrap().id, 2);
+        assert_eq!(automata.get(&"straße".into()).unwrap().id, 3);
+        assert!(automata.get(&"zookeeper".into()).is_none());
+        assert_eq!(
+            automata.concept(2).unwrap().value.as_str(),
+            "event streaming"
+        );
+        assert!(automata.concept(42).is_none());
+        assert_eq!(automata.narrower_transitive(1), BTreeSet::from([2]));
+    }
+
+    #[test]
+    fn test_thesaurus_hash() {
+        let thesaurus = sample_thesaurus();
+        let hash = Automata::new(&thesaurus, NormalizationOptions::default())
+            .unwrap()
+            .thesaurus_hash();
+        let normalization = NormalizationOptions {
+            strip_diacritics: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            Automata::new(&thesaurus, normalization)
+                .unwrap()
+                .thesaurus_hash(),
+            hash
+        );
+
+        let mut changed = thesaurus.clone();
+        changed.insert(
+            "zookeeper".into(),
+            NormalizedTerm::new(4, "zookeeper".into()),
+        );
+        let mut related = thesaurus;
+        related.add_relation(3, RelationKind::Related, 1);
+        for thesaurus in [changed, related] {
+            let automata = Automata::new(&thesaurus, NormalizationOptions::default()).unwrap();
+            assert_ne!(automata.thesaurus_hash(), hash);
+        }
+    }
+
+    #[test]
+    fn test_find_matches() {
+        let automata = Automata::new(&sample_thesaurus(), NormalizationOptions::default()).unwrap();
+        let text = "Kafka does event streaming on the STRASSE, events too";
+        let matches = automata.find_matches(text, true, MatchMode::Substring);
+        let found: Vec<(&str, &str)> = matches
+            .iter()
+            .map(|matched| {
+                let (start, end) = matched.pos.unwrap();
+                (matched.term.as_str(), &text[start..end])
+            })
+            .collect();
+        assert_eq!(
+            found,
+            [
+                ("kafka", "Kafka"),
+                ("event streaming", "event streaming"),
+                ("straße", "STRASSE"),
+                ("event", "event"),
+            ]
+        );
+
+        let ids: Vec<u64> = automata
+            .find_terms(text, MatchMode::WordBoundary)
+            .iter()
+            .map(|term| term.id)
+            .collect();
+        assert_eq!(ids, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_complete() {
+        let automata = Automata::new(&sample_thesaurus(), NormalizationOptions::default()).unwrap();
+        let keys = |prefix| -> Vec<(&str, bool)> {
+            automata
+                .complete(prefix)
+                .into_iter()
+                .map(|completion| (completion.key.as_str(), completion.fuzzy))
+                .collect()
+        };
+        assert_eq!(keys("Ev"), [("event", false), ("event streaming", false)]);
+        assert_eq!(keys("stras"), [("straße", false)]);
+        assert_eq!(keys("kafak"), [("kafka", true)]);
+        assert_eq!(keys("evnt str"), [("event streaming", true)]);
+        assert!(keys("kfa").is_empty());
+        assert!(keys("").is_empty());
+    }
+
+    #[test]
+    fn test_artifact_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("streaming.automata");
+        let automata = Automata::new(&sample_thesaurus(), NormalizationOptions::default()).unwrap();
+        automata.save(&path).unwrap();
+
+        let loaded = Automata::load(&path).unwrap();
+        assert_eq!(loaded.name(), "streaming");
+        assert_eq!(loaded.normalization(), automata.normalization());
+        assert_eq!(loaded.thesaurus_hash(), automata.thesaurus_hash());
+        assert_eq!(loaded.thesaurus(), sample_thesaurus());
+        let text = "kafka and event streaming";
+        assert_eq!(
+            loaded.find_matches(text, true, MatchMode::Substring),
+            automata.find_matches(text, true, MatchMode::Substring)
+        );
+
+        let mut bytes = automata.to_bytes().unwrap();
+        bytes[8] = 99;
+        assert!(matches!(
+            Automata::from_bytes(bytes),
+            Err(TerraphimAutomataError::Artifact(_))
+        ));
+        assert!(Automata::from_bytes(b"{\"name\": \"json\"}".to_vec()).is_err());
+    }
+}
@@ -0,0 +1,160 @@
+//! Command line tool to validate, diff, merge and convert thesauri
+//!
+//! Thesauri are read from JSON files, CSV or TSV files with the default
+//! columns (optionally gzipped), SKOS vocabularies in Turtle or RDF/XML and
+//! automata artifacts, depending on their extension. They are written as
+//! JSON, or as SKOS vocabularies to files with a SKOS extension.
+//!
+//! The tool is built with the `cli` feature, e.g.
+//! `cargo run -p terraphim_automata --features cli -- validate thesaurus.json`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use terraphim_automata::{
+    load_thesaurus, save_skos_thesaurus, AutomataPath, CsvOptions, Result, SkosFormat, SkosOptions,
+};
+use terraphim_types::{MergePolicy, Thesaurus};
+
+/// Validate, diff, merge and convert thesauri
+#[derive(Debug, Parser)]
+#[command(version)]
+struct Args {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Check a thesaurus for empty synonyms, colliding IDs and shadowed
+    /// synonyms
+    ///
+    /// Fails if there are errors, shadowed synonyms are only warnings.
+    Validate {
+        thesaurus: PathBuf,
+        /// Also fail on warnings
+        #[arg(long)]
+        strict: bool,
+    },
+    /// Show the synonyms which were added, removed or remapped between two
+    /// versions of a thesaurus
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+        /// Print the diff as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Merge thesauri into the first one and write the result
+    Merge {
+        #[arg(required = true, num_args = 2..)]
+        thesauri: Vec<PathBuf>,
+        /// What to do with synonyms mapping to different concepts:
+        /// keep_ours, take_theirs or fail
+        #[arg(long, default_value = "keep_ours")]
+        policy: MergePolicy,
+        /// File to write the merged thesaurus to, defaults to stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+    /// Convert a thesaurus to the format given by the extension of the
+    /// output file
+    Convert { input: PathBuf, output: PathBuf },
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run(Args::parse().command).await {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Run the command, returning whether it succeeded
+async fn run(command: Command) -> Result<bool> {
+    match command {
+        Command::Validate { thesaurus, strict } => {
+            let issues = load(&thesaurus).await?.validate();
+            for issue in &issues {
+                let level = if issue.is_error() { "error" } else { "warning" };
+                println!("{level}: {issue}");
+            }
+            Ok(!issues.iter().any(|issue| strict || issue.is_error()))
+        }
+        Command::Diff { old, new, json } => {
+            let diff = load(&old).await?.diff(&load(&new).await?);
+            if json {
+                println!("{}", serde_json::to_string_pretty(&diff)?);
+            } else {
+                print!("{diff}");
+            }
+            Ok(true)
+        }
+        Command::Merge {
+            thesauri,
+            policy,
+            output,
+        } => {
+            let mut merged = load(&thesauri[0]).await?;
+            for path in &thesauri[1..] {
+                match merged.merge(&load(path).await?, policy) {
+                    Ok(conflicts) => {
+                        for conflict in conflicts {
+                            eprintln!("conflict in {}: {conflict}", path.display());
+                        }
+                    }
+                    Err(e) => {
+                        for conflict in &e.conflicts {
+                            eprintln!("conflict in {}: {conflict}", path.display());
+                        }
+                        eprintln!("Error: {e} in {}", path.display());
+                        return Ok(false);
+                    }
+                }
+            }
+            match output {
+                Some(output) => save(&merged, &output)?,
+                None => println!("{}", serde_json::to_string_pretty(&merged)?),
+            }
+            Ok(true)
+        }
+        Command::Convert { input, output } => {
+            save(&load(&input).await?, &output)?;
+            Ok(true)
+        }
+    }
+}
+
+/// Save a thesaurus as a SKOS vocabulary if the file has a SKOS extension,
+/// otherwise as JSON
+fn save(thesaurus: &Thesaurus, path: &Path) -> Result<()> {
+    if SkosFormat::from_path(path).is_some() {
+        save_skos_thesaurus(thesaurus, path, &SkosOptions::default())
+    } else {
+        Ok(fs::write(path, serde_json::to_string_pretty(thesaurus)?)?)
+    }
+}
+
+/// Load a thesaurus in the format given by the extension of the file
+async fn load(path: &Path) -> Result<Thesaurus> {
+    let name = path.to_string_lossy();
+    let name = name.strip_suffix(".gz").unwrap_or(&name);
+    let automata_path = if name.ends_with(".csv") {
+        AutomataPath::from_csv(path, CsvOptions::default())
+    } else if name.ends_with(".tsv") {
+        AutomataPath::from_csv(path, CsvOptions::tsv())
+    } else if name.ends_with(".automata") {
+        AutomataPath::from_artifact(path)
+    } else if SkosFormat::from_path(path).is_some() {
+        AutomataPath::from_skos(path, SkosOptions::default())
+    } else {
+        AutomataPath::from_local(path)
+    };
+    load_thesaurus(&automata_path).await
+}
@@ -0,0 +1,265 @@
+//! Fuzzy matching of misspelled text against the terms of a thesaurus
+//!
+//! Exact matching finds nothing for "kuberentes". This stage maps the words
+//! of a text, which don't start an exact match, to the closest term of the
+//! thesaurus within a configurable distance. Every correction is reported,
+//! so that callers can show "did you mean" hints, and
+//! [`apply_corrections`] turns the text into the corrected one.
+//!
+//! Spans of several words are tried before single words, so that
+//! "life cylce concepts" can be corrected to a single multi word term.
+
+use fst::automaton::Levenshtein;
+use fst::{IntoStreamer, Streamer};
+use serde::{Deserialize, Serialize};
+use terraphim_types::Correction;
+
+use crate::automata::Automata;
+use crate::matcher::{is_word_char, MatchMode};
+use crate::normalize::NormalizedText;
+
+/// Spans shorter than this number of characters are never corrected, there
+/// are too many terms close to them
+pub const MIN_FUZZY_LENGTH: usize = 4;
+
+/// How far a misspelled text may be from a term of the thesaurus
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "metric", rename_all = "snake_case")]
+pub enum FuzzyDistance {
+    /// At most `max_edits` insertions, deletions or substitutions
+    Levenshtein { max_edits: u32 },
+    /// A Jaro-Winkler similarity of at least `min_similarity`, between 0 and
+    /// 1
+    ///
+    /// This compares the text with every term of the thesaurus, so it is
+    /// meant for short texts like queries.
+    JaroWinkler { min_similarity: f64 },
+}
+
+impl Default for FuzzyDistance {
+    fn default() -> Self {
+        FuzzyDistance::Levenshtein { max_edits: 1 }
+    }
+}
+
+impl Automata {
+    /// Correct the misspelled words of a text to terms of the thesaurus
+    ///
+    /// Words which start an exact match are left as they are. Positions
+    /// point into the original text.
+    pub fn correct(&self, text: &str, distance: FuzzyDistance) -> Vec<Correction> {
+        let normalized = NormalizedText::new(text, self.normalization());
+        let normalized_text = normalized.as_str();
+        let words = words(normalized_text);
+        let max_words = self.max_words().max(1);
+
+        let mut corrections = Vec::new();
+        let mut i = 0;
+        while i < words.len() {
+            let start = words[i].0;
+            if let Some((end, _)) =
+                self.longest_match(normalized_text, start, MatchMode::WordBoundary)
+            {
+                i = words.partition_point(|word| word.0 < end);
+                continue;
+            }
+            let last = (i + max_words).min(words.len());
+            let closest = (i..last).rev().find_map(|j| {
+                let span = &normalized_text[start..words[j].1];
+                if span.chars().count() < MIN_FUZZY_LENGTH {
+                    return None;
+                }
+                Some((j, self.closest_term(span, distance)?))
+            });
+            let Some((j, (index, similarity))) = closest else {
+                i += 1;
+                continue;
+            };
+            if let Some((key, term)) = self.entry(index) {
+                let (from, to) = normalized.original_range(start, words[j].1);
+                corrections.push(Correction {
+                    original: text[from..to].to_string(),
+                    pos: (from, to),
+                    term: key.clone(),
+                    concept: term.clone(),
+                    similarity,
+                });
+            }
+            i = j + 1;
+        }
+        corrections
+    }
+
+    /// Index and similarity of the term closest to the text, if any is
+    /// within the distance
+    fn closest_term(&self, text: &str, distance: FuzzyDistance) -> Option<(u64, f64)> {
+        let mut closest: Option<(u64, f64)> = None;
+        // Terms come in alphabetical order, the first of equally close
+        // terms wins
+        let mut consider = |index: u64, similarity: f64| {
+            if closest.is_none_or(|(_, best)| similarity > best) {
+                closest = Some((index, similarity));
+            }
+        };
+        match distance {
+            FuzzyDistance::Levenshtein { max_edits } => {
+                let levenshtein = match Levenshtein::new(text, max_edits) {
+                    Ok(levenshtein) => levenshtein,
+                    Err(e) => {
+                        log::debug!("Skipping fuzzy matching of `{text}`: {e}");
+                        return None;
+                    }
+                };
+                let mut stream = self.fst().search(levenshtein).into_stream();
+                while let Some((key, index)) = stream.next() {
+                    let key = String::from_utf8_lossy(key);
+                    let edits = strsim::levenshtein(text, &key);
+                    let length = text.chars().count().max(key.chars().count());
+                    consider(index, 1.0 - edits as f64 / length as f64);
+                }
+            }
+            FuzzyDistance::JaroWinkler { min_similarity } => {
+                let mut stream = self.fst().stream();
+                while let Some((key, index)) = stream.next() {
+                    let similarity = strsim::jaro_winkler(text, &String::from_utf8_lossy(key));
+                    if similarity >= min_similarity {
+                        consider(index, similarity);
+                    }
+                }
+            }
+        }
+        closest
+    }
+}
+
+/// Byte ranges of the words of a text
+fn words(text: &str) -> Vec<(usize, usize)> {
+    let mut words = Vec::new();
+    let mut start = None;
+    for (offset, c) in text.char_indices() {
+        match (is_word_char(c), start) {
+            (true, None) => start = Some(offset),
+            (false, Some(from)) => {
+                words.push((from, offset));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(from) = start {
+        words.push((from, text.len()));
+    }
+    words
+}
+
+/// Replace the corrected parts of a text with the terms they were corrected
+/// to
+///
+/// The corrections have to be ordered by position and must not overlap, as
+/// returned by [`Automata::correct`].
+pub fn apply_corrections(text: &str, corrections: &[Correction]) -> String {
+    let mut corrected = String::with_capacity(text.len());
+    let mut last = 0;
+    for correction in corrections {
+        let (start, end) = correction.pos;
+        if start < last || end > text.len() {
+            continue;
+        }
+        corrected.push_str(&text[last..start]);
+        corrected.push_str(correction.term.as_str());
+        last = end;
+    }
+    corrected.push_str(&text[last..]);
+    corrected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::thesaurus;
+    use crate::NormalizationOptions;
+
+    fn sample_automata() -> Automata {
+        let thesaurus = thesaurus(&[
+            (1, "kubernetes"),
+            (2, "docker"),
+            (3, "life cycle concepts"),
+            (4, "helm"),
+        ]);
+        Automata::new(&thesaurus, NormalizationOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_correct_levenshtein() {
+        let automata = sample_automata();
+        let text = "Deploy Kuberentes with Dockr and hlm";
+        let corrections = automata.correct(text, FuzzyDistance::Levenshtein { max_edits: 2 });
+        let corrected: Vec<(&str, &str)> = corrections
+            .iter()
+            .map(|c| (c.original.as_str(), c.term.as_str()))
+            .collect();
+        // "hlm" is too short to be corrected
+        assert_eq!(
+            corrected,
+            [("Kuberentes", "kubernetes"), ("Dockr", "docker")]
+        );
+        assert_eq!(corrections[0].pos, (7, 17));
+        assert_eq!(corrections[0].concept.id, 1);
+        assert!(corrections[1].similarity > corrections[0].similarity);
+
+        assert_eq!(
+            apply_corrections(text, &corrections),
+            "Deploy kubernetes with docker and hlm"
+        );
+
+        let corrections = automata.correct(
+            "the life cylce concepts",
+            FuzzyDistance::Levenshtein { max_edits: 2 },
+        );
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].original, "life cylce concepts");
+        assert_eq!(corrections[0].concept.id, 3);
+
+        // Exact matches aren't corrected
+        assert!(automata
+            .correct("docker and kubernetes", FuzzyDistance::default())
+            .is_empty());
+    }
+
+    #[test]
+    fn test_correct_jaro_winkler() {
+        let automata = sample_automata();
+        let corrections = automata.correct(
+            "kubernets",
+            FuzzyDistance::JaroWinkler {
+                min_similarity: 0.9,
+            },
+        );
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].term.as_str(), "kubernetes");
+        assert!(automata
+            .correct(
+                "kafka",
+                FuzzyDistance::JaroWinkler {
+                    min_similarity: 0.9
+                }
+            )
+            .is_empty());
+    }
+
+    #[test]
+    fn test_fuzzy_distance_serde() {
+        let distance: FuzzyDistance =
+            serde_json::from_str(r#"{"metric": "levenshtein", "max_edits": 2}"#).unwrap();
+        assert_eq!(distance, FuzzyDistance::Levenshtein { max_edits: 2 });
+        let distance: FuzzyDistance =
+            serde_json::from_str(r#"{"metric": "jaro_winkler", "min_similarity": 0.8}"#).unwrap();
+        assert_eq!(
+            distance,
+            FuzzyDistance::JaroWinkler {
+                min_similarity: 0.8
+            }
+        );
+    }
+}
@@ -1,10 +1,31 @@
+pub mod automata;
+pub mod fuzzy;
+pub mod links;
 pub mod matcher;
+pub mod normalize;
+pub mod remote;
+pub mod skos;
+pub mod stream;
+pub mod tabular;
 
-pub use matcher::{find_matches, Matched};
+pub use automata::{build_artifact, load_automata, Automata, Completion, AUTOMATA_VERSION};
+pub use fuzzy::{apply_corrections, FuzzyDistance};
+pub use links::LinkFormat;
+pub use matcher::{
+    find_matches, find_matches_with, replace_matches, replace_matches_with, MatchMode, Matched,
+};
+pub use normalize::{normalize, NormalizationOptions, NormalizedText};
+pub use remote::{load_remote_thesaurus, CachedThesaurus, RemoteCacheOptions};
 use serde::{Deserialize, Serialize};
+pub use skos::{
+    load_skos_thesaurus, read_skos_thesaurus, save_skos_thesaurus, write_skos_thesaurus,
+    SkosFormat, SkosOptions,
+};
 use std::fmt::Display;
 use std::fs;
 use std::path::PathBuf;
+pub use stream::{AsyncStreamMatches, StreamMatcher, StreamMatches};
+pub use tabular::{load_csv_thesaurus, read_csv_thesaurus, CsvColumn, CsvOptions, RowError};
 
 use terraphim_types::Thesaurus;
 
@@ -24,17 +45,46 @@ pub enum TerraphimAutomataError {
 
     #[error("Aho-Corasick build error: {0}")]
     AhoCorasick(#[from] aho_corasick::BuildError),
+
+    #[error("FST error: {0}")]
+    Fst(#[from] fst::Error),
+
+    #[error("Invalid automata artifact: {0}")]
+    Artifact(String),
+
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+
+    #[error("Invalid thesaurus rows: {}", format_rows(.0))]
+    InvalidRows(Vec<RowError>),
+
+    #[error("Turtle error: {0}")]
+    Turtle(#[from] oxttl::TurtleParseError),
+
+    #[error("RDF/XML error: {0}")]
+    RdfXml(#[from] oxrdfxml::RdfXmlParseError),
 }
 
 pub type Result<T> = std::result::Result<T, TerraphimAutomataError>;
 
+fn format_rows(rows: &[RowError]) -> String {
+    let rows: Vec<String> = rows.iter().map(RowError::to_string).collect();
+    rows.join(", ")
+}
+
 /// AutomataPath is a path to the automata file
 ///
-/// It can either be a local file path or a URL.
+/// It can either be a local file path or a URL of a JSON thesaurus, or a
+/// local file path of a prebuilt automata artifact, see [`Automata::save`],
+/// or a local CSV or TSV file, see [`load_csv_thesaurus`], or a local SKOS
+/// vocabulary, see [`load_skos_thesaurus`].
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AutomataPath {
     Local(PathBuf),
     Remote(String),
+    Artifact(PathBuf),
+    Csv { path: PathBuf, options: CsvOptions },
+    Skos { path: PathBuf, options: SkosOptions },
 }
 
 impl Display for AutomataPath {
@@ -42,6 +92,9 @@ impl Display for AutomataPath {
         match self {
             AutomataPath::Local(path) => write!(f, "Local Path: {:?}", path),
             AutomataPath::Remote(url) => write!(f, "Remote URL: {:?}", url),
+            AutomataPath::Artifact(path) => write!(f, "Automata Artifact: {:?}", path),
+            AutomataPath::Csv { path, .. } => write!(f, "CSV Path: {:?}", path),
+            AutomataPath::Skos { path, .. } => write!(f, "SKOS Path: {:?}", path),
         }
     }
 }
@@ -64,6 +117,27 @@ impl AutomataPath {
         AutomataPath::Local(file.as_ref().to_path_buf())
     }
 
+    /// Create a new AutomataPath from a prebuilt automata artifact
+    pub fn from_artifact<P: AsRef<std::path::Path>>(file: P) -> Self {
+        AutomataPath::Artifact(file.as_ref().to_path_buf())
+    }
+
+    /// Create a new AutomataPath from a CSV or TSV file
+    pub fn from_csv<P: AsRef<std::path::Path>>(file: P, options: CsvOptions) -> Self {
+        AutomataPath::Csv {
+            path: file.as_ref().to_path_buf(),
+            options,
+        }
+    }
+
+    /// Create a new AutomataPath from a SKOS vocabulary
+    pub fn from_skos<P: AsRef<std::path::Path>>(file: P, options: SkosOptions) -> Self {
+        AutomataPath::Skos {
+            path: file.as_ref().to_path_buf(),
+            options,
+        }
+    }
+
     /// Local example for testing
     pub fn local_example() -> Self {
         log::debug!("Current folder {:?}", std::env::current_dir());
@@ -81,59 +155,45 @@ impl AutomataPath {
     }
 }
 
-/// `load_automata` loads output of the knowledge graph builder
-// pub async fn load_automata(url_or_file: &str) -> Result<matcher::Automata> {
-//     let thesaurus = load_thesaurus(url_or_file).await?;
-//     let automata = Automata::new(thesaurus);
-//     Ok(automata)
-// }
-
 /// Load a thesaurus from a file or URL
+///
+/// Remote thesauri are cached with the default [`RemoteCacheOptions`], see
+/// [`load_thesaurus_with`].
 pub async fn load_thesaurus(automata_path: &AutomataPath) -> Result<Thesaurus> {
-    async fn read_url(url: String) -> Result<String> {
-        log::debug!("Reading thesaurus from remote: {url}");
-        let response = reqwest::Client::new()
-            .get(url.clone())
-            .header("Accept", "application/json")
-            .send()
-            .await
-            .map_err(|e| {
-                TerraphimAutomataError::InvalidThesaurus(format!(
-                    "Failed to fetch thesaurus from remote {url}. Error: {e:#?}",
-                ))
-            })?;
-
-        let status = response.status();
-        let headers = response.headers().clone(); // Clone headers for error reporting
-        let body = response.text().await;
-
-        match body {
-            Ok(text) => Ok(text),
-            Err(e) => {
-                let error_info = format!(
-                    "Failed to read thesaurus from remote {url}. Status: {status}. Headers: {headers:#?}. Error: {e:#?}",
-                );
-                Err(TerraphimAutomataError::InvalidThesaurus(error_info))
-            }
-        }
-    }
+    load_thesaurus_with(automata_path, &RemoteCacheOptions::default()).await
+}
 
+/// Load a thesaurus from a file or URL, caching remote thesauri with the
+/// given options
+pub async fn load_thesaurus_with(
+    automata_path: &AutomataPath,
+    remote: &RemoteCacheOptions,
+) -> Result<Thesaurus> {
     log::debug!("Reading thesaurus from {automata_path}");
-    let contents = match automata_path {
-        AutomataPath::Local(path) => fs::read_to_string(path)?,
-        AutomataPath::Remote(url) => read_url(url.clone()).await?,
-    };
-
-    let thesaurus = serde_json::from_str(&contents)?;
-    Ok(thesaurus)
+    match automata_path {
+        AutomataPath::Local(path) => Ok(serde_json::from_str(&fs::read_to_string(path)?)?),
+        AutomataPath::Remote(url) => load_remote_thesaurus(url, remote).await,
+        AutomataPath::Artifact(path) => Ok(Automata::load(path)?.thesaurus()),
+        AutomataPath::Csv { path, options } => load_csv_thesaurus(path, options),
+        AutomataPath::Skos { path, options } => load_skos_thesaurus(path, options),
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use terraphim_types::NormalizedTermValue;
+    use terraphim_types::{NormalizedTerm, NormalizedTermValue};
 
     use super::*;
 
+    /// A thesaurus where every term is its own concept
+    pub(crate) fn thesaurus(entries: &[(u64, &str)]) -> Thesaurus {
+        let mut thesaurus = Thesaurus::new("test".to_string());
+        for &(id, term) in entries {
+            thesaurus.insert(term.into(), NormalizedTerm::new(id, term.into()));
+        }
+        thesaurus
+    }
+
     #[tokio::test]
     async fn test_load_thesaurus_from_file() {
         let automata_path = AutomataPath::from_local("data/term_to_id_simple.json");
@@ -153,6 +213,61 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn test_load_thesaurus_from_csv() {
+        let automata_path = AutomataPath::from_csv("data/output.csv", CsvOptions::default());
+        let thesaurus = load_thesaurus(&automata_path).await.unwrap();
+        assert_eq!(thesaurus.name(), "output");
+        assert!(thesaurus
+            .get(&NormalizedTermValue::from("corporate strategy"))
+            .is_some());
+    }
+
+    #[tokio::test]
+    async fn test_load_thesaurus_from_skos() {
+        let automata_path = AutomataPath::from_skos("data/devops.ttl", SkosOptions::default());
+        let thesaurus = load_thesaurus(&automata_path).await.unwrap();
+        assert_eq!(thesaurus.name(), "devops");
+        let k8s = thesaurus.get(&NormalizedTermValue::from("k8s")).unwrap();
+        assert_eq!(k8s.value, NormalizedTermValue::from("kubernetes"));
+        assert_eq!(k8s.id, 2);
+    }
+
+    #[tokio::test]
+    async fn test_load_automata_from_artifact() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("term_to_id_simple.automata");
+        let automata = build_artifact(
+            "data/term_to_id_simple.json",
+            &path,
+            NormalizationOptions::default(),
+        )
+        .unwrap();
+        let automata_path = AutomataPath::from_artifact(&path);
+
+        let thesaurus = load_thesaurus(&automata_path).await.unwrap();
+        assert_eq!(thesaurus, automata.thesaurus());
+        assert_eq!(thesaurus.len(), 3);
+
+        let loaded = load_automata(
+            &automata_path,
+            NormalizationOptions::default(),
+            &RemoteCacheOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(loaded.len(), 3);
+        let rebuilt = load_automata(
+            &automata_path,
+            NormalizationOptions::none(),
+            &RemoteCacheOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(rebuilt.normalization(), NormalizationOptions::none());
+        assert_eq!(rebuilt.thesaurus(), thesaurus);
+    }
+
     #[tokio::test]
     async fn test_load_thesaurus_from_url() {
         let automata_path = AutomataPath::remote_example();
@@ -0,0 +1,339 @@
+//! Replacing the terms of a thesaurus with links
+//!
+//! Documents are linked to the knowledge graph by replacing every term of
+//! the thesaurus with a link in one of the supported [`LinkFormat`]s.
+//! Existing links, code spans, code blocks, HTML tags and bare URLs are left
+//! alone, so that linking an already linked document changes nothing.
+
+use serde::{Deserialize, Serialize};
+
+use crate::automata::Automata;
+use crate::matcher::{MatchMode, Matched};
+
+/// How a matched term is turned into a link
+///
+/// Links point to the URL of the concept, or to its normalized term if the
+/// concept has no URL.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkFormat {
+    /// `[term](url)`
+    #[default]
+    Markdown,
+    /// `[[nterm]]`, as used by Logseq and Obsidian
+    WikiLink,
+    /// `<a href="url">term</a>`
+    Html,
+    /// A template with `{term}`, `{nterm}`, `{id}` and `{url}` placeholders
+    ///
+    /// `{term}` is the matched text, `{nterm}` the normalized term of its
+    /// concept. Other text, including unknown placeholders, is kept as is.
+    Template(String),
+}
+
+impl LinkFormat {
+    /// Render the link for a term matched in the text
+    fn render(&self, text: &str, matched: &Matched, out: &mut String) {
+        let term = &matched.normalized_term;
+        let url = term.url.as_deref().unwrap_or(term.value.as_str());
+        match self {
+            LinkFormat::Markdown => {
+                out.push('[');
+                out.push_str(text);
+                out.push_str("](");
+                if url.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+                    out.push('<');
+                    out.push_str(url);
+                    out.push('>');
+                } else {
+                    out.push_str(url);
+                }
+                out.push(')');
+            }
+            LinkFormat::WikiLink => {
+                out.push_str("[[");
+                out.push_str(term.value.as_str());
+                out.push_str("]]");
+            }
+            LinkFormat::Html => {
+                out.push_str("<a href=\"");
+                escape_html(url, out);
+                out.push_str("\">");
+                escape_html(text, out);
+                out.push_str("</a>");
+            }
+            LinkFormat::Template(template) => {
+                let mut rest = template.as_str();
+                while let Some(open) = rest.find('{') {
+                    out.push_str(&rest[..open]);
+                    rest = &rest[open..];
+                    let Some(close) = rest.find('}') else {
+                        break;
+                    };
+                    match &rest[1..close] {
+                        "term" => out.push_str(text),
+                        "nterm" => out.push_str(term.value.as_str()),
+                        "id" => out.push_str(&term.id.to_string()),
+                        "url" => out.push_str(url),
+                        _ => {
+                            out.push('{');
+                            rest = &rest[1..];
+                            continue;
+                        }
+                    }
+                    rest = &rest[close + 1..];
+                }
+                out.push_str(rest);
+            }
+        }
+    }
+}
+
+impl Automata {
+    /// Replace all terms in the text with links
+    ///
+    /// Terms inside of existing links, code spans, code blocks, HTML tags
+    /// and bare URLs aren't replaced.
+    pub fn replace_matches(&self, text: &str, format: &LinkFormat, mode: MatchMode) -> String {
+        let mut replaced = String::with_capacity(text.len());
+        let mut last = 0;
+        for (start, end) in unprotected(text) {
+            for matched in self.find_matches(&text[start..end], true, mode) {
+                let Some((from, to)) = matched.pos else {
+                    continue;
+                };
+                let (from, to) = (start + from, start + to);
+                // Matches within the same character, e.g. a ligature, can't
+                // be replaced separately
+                if from < last {
+                    continue;
+                }
+                replaced.push_str(&text[last..from]);
+                format.render(&text[from..to], &matched, &mut replaced);
+                last = to;
+            }
+        }
+        replaced.push_str(&text[last..]);
+        replaced
+    }
+}
+
+fn escape_html(text: &str, out: &mut String) {
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+}
+
+/// Byte ranges of the text outside of links, code and HTML tags
+fn unprotected(text: &str) -> Vec<(usize, usize)> {
+    let mut ranges = Vec::new();
+    let mut start = 0;
+    let mut position = 0;
+    let mut line_start = true;
+    while position < text.len() {
+        let rest = &text[position..];
+        let protected = match rest.as_bytes()[0] {
+            b'`' if line_start => code_block(rest).or_else(|| code_span(rest)),
+            b'~' if line_start => code_block(rest),
+            b'`' => code_span(rest),
+            b'[' => link(rest),
+            b'<' => html(rest),
+            b'h' if rest.starts_with("http://") || rest.starts_with("https://") => {
+                Some(rest.find(char::is_whitespace).unwrap_or(rest.len()))
+            }
+            _ => None,
+        };
+        match protected {
+            Some(len) if len > 0 => {
+                if start < position {
+                    ranges.push((start, position));
+                }
+                position += len;
+                start = position;
+                line_start = text[..position].ends_with('\n');
+            }
+            _ => {
+                let c = rest.chars().next().unwrap_or_default();
+                position += c.len_utf8();
+                line_start = c == '\n' || (line_start && (c == ' ' || c == '\t'));
+            }
+        }
+    }
+    if start < text.len() {
+        ranges.push((start, text.len()));
+    }
+    ranges
+}
+
+/// Length of a fenced code block at the start of the text, up to the end of
+/// its closing fence
+fn code_block(text: &str) -> Option<usize> {
+    let fence_char = text.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let fence = text.len() - text.trim_start_matches(fence_char).len();
+    if fence < 3 {
+        return None;
+    }
+    let mut position = text.find('\n').map_or(text.len(), |end| end + 1);
+    while position < text.len() {
+        let line_end = text[position..]
+            .find('\n')
+            .map_or(text.len(), |end| position + end + 1);
+        let line = text[position..line_end].trim();
+        if line.len() >= fence && line.chars().all(|c| c == fence_char) {
+            return Some(line_end);
+        }
+        position = line_end;
+    }
+    Some(text.len())
+}
+
+/// Length of a code span at the start of the text
+///
+/// A code span closes with a run of as many backticks as it was opened
+/// with. Without one, the backticks are just text.
+fn code_span(text: &str) -> Option<usize> {
+    let ticks = text.len() - text.trim_start_matches('`').len();
+    let mut position = ticks;
+    while let Some(offset) = text[position..].find('`') {
+        let start = position + offset;
+        let run = text[start..].len() - text[start..].trim_start_matches('`').len();
+        if run == ticks {
+            return Some(start + run);
+        }
+        position = start + run;
+    }
+    Some(ticks)
+}
+
+/// Length of a wiki link, a Markdown link or a reference link at the start
+/// of the text
+fn link(text: &str) -> Option<usize> {
+    if let Some(rest) = text.strip_prefix("[[") {
+        return rest.find("]]").map(|end| end + 4);
+    }
+    let label_end = text.find(']')?;
+    match text[label_end + 1..].chars().next() {
+        Some('(') => text[label_end..].find(')').map(|end| label_end + end + 1),
+        Some('[') => text[label_end + 1..]
+            .find(']')
+            .map(|end| label_end + end + 2),
+        // A link reference definition
+        Some(':') => Some(text.find('\n').unwrap_or(text.len())),
+        _ => None,
+    }
+}
+
+/// Length of an HTML tag at the start of the text
+///
+/// Anchors, code and preformatted text extend to their closing tag.
+fn html(text: &str) -> Option<usize> {
+    let second = text[1..].chars().next()?;
+    if !(second.is_ascii_alphabetic() || second == '/' || second == '!') {
+        return None;
+    }
+    let tag_end = text.find('>')? + 1;
+    let name: String = text[1..]
+        .chars()
+        .take_while(char::is_ascii_alphanumeric)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    if matches!(name.as_str(), "a" | "code" | "pre") && !text[..tag_end].ends_with("/>") {
+        let closing = format!("</{name}>");
+        let mut position = tag_end;
+        while let Some(offset) = text[position..].find("</") {
+            let start = position + offset;
+            let end = start + closing.len();
+            if text
+                .get(start..end)
+                .is_some_and(|tag| tag.eq_ignore_ascii_case(&closing))
+            {
+                return Some(end);
+            }
+            position = start + 2;
+        }
+    }
+    Some(tag_end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use terraphim_types::{NormalizedTerm, Thesaurus};
+
+    use crate::NormalizationOptions;
+
+    fn sample_automata() -> Automata {
+        let mut thesaurus = Thesaurus::new("devops".to_string());
+        thesaurus.insert(
+            "kubernetes".into(),
+            NormalizedTerm::new(1, "kubernetes".into()).with_url("https://kubernetes.io"),
+        );
+        thesaurus.insert("k8s".into(), NormalizedTerm::new(1, "kubernetes".into()));
+        thesaurus.insert("helm".into(), NormalizedTerm::new(2, "helm".into()));
+        Automata::new(&thesaurus, NormalizationOptions::default()).unwrap()
+    }
+
+    #[test]
+    fn test_link_formats() {
+        let automata = sample_automata();
+        let replace =
+            |format| automata.replace_matches("Helm & K8s", &format, MatchMode::WordBoundary);
+
+        assert_eq!(
+            replace(LinkFormat::Markdown),
+            "[Helm](helm) & [K8s](kubernetes)"
+        );
+        assert_eq!(replace(LinkFormat::WikiLink), "[[helm]] & [[kubernetes]]");
+        assert_eq!(
+            replace(LinkFormat::Html),
+            r#"<a href="helm">Helm</a> & <a href="kubernetes">K8s</a>"#
+        );
+        assert_eq!(
+            replace(LinkFormat::Template("{{term}} #{id} {nterm}".to_string())),
+            "{Helm} #2 helm & {K8s} #1 kubernetes"
+        );
+        assert_eq!(
+            automata.replace_matches("Kubernetes", &LinkFormat::Markdown, MatchMode::Substring),
+            "[Kubernetes](https://kubernetes.io)"
+        );
+    }
+
+    #[test]
+    fn test_skip_links_and_code() {
+        let automata = sample_automata();
+        let text = "\
+Deploy helm charts with [Helm](https://helm.sh), [[kubernetes]] and
+`helm install` or <a href=\"/k8s\">k8s</a>, see https://kubernetes.io/helm.
+
+```sh
+helm upgrade
+```
+[k8s]: https://kubernetes.io
+";
+        let expected = "\
+Deploy [[helm]] charts with [Helm](https://helm.sh), [[kubernetes]] and
+`helm install` or <a href=\"/k8s\">k8s</a>, see https://kubernetes.io/helm.
+
+```sh
+helm upgrade
+```
+[k8s]: https://kubernetes.io
+";
+        let replaced =
+            automata.replace_matches(text, &LinkFormat::WikiLink, MatchMode::WordBoundary);
+        assert_eq!(replaced, expected);
+        // Linking is idempotent
+        assert_eq!(
+            automata.replace_matches(&replaced, &LinkFormat::WikiLink, MatchMode::WordBoundary),
+            expected
+        );
+    }
+}
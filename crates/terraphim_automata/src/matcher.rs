@@ -1,62 +1,201 @@
-use aho_corasick::{AhoCorasick, MatchKind};
-use terraphim_types::{NormalizedTerm, NormalizedTermValue, Thesaurus};
+use serde::{Deserialize, Serialize};
+use terraphim_types::{NormalizedTerm, Thesaurus};
 
-use crate::{Result, TerraphimAutomataError};
+use crate::automata::Automata;
+use crate::links::LinkFormat;
+use crate::normalize::NormalizationOptions;
+use crate::Result;
+
+/// How terms of a thesaurus are matched in a text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MatchMode {
+    /// Terms match anywhere, also inside of words, e.g. "ai" in "maintain"
+    #[default]
+    Substring,
+    /// Terms only match on word boundaries
+    WordBoundary,
+}
 
 #[derive(Debug, PartialEq, Clone)]
 pub struct Matched {
     pub term: String,
     pub normalized_term: NormalizedTerm,
     pub pos: Option<(usize, usize)>,
+    /// The mode which produced the match
+    pub mode: MatchMode,
 }
 
+/// Find all terms of the thesaurus in the text
+///
+/// Both the terms and the text are normalized with the default
+/// [`NormalizationOptions`] and terms match anywhere in the text, see
+/// [`find_matches_with`].
 pub fn find_matches(
     text: &str,
     thesaurus: Thesaurus,
     return_positions: bool,
 ) -> Result<Vec<Matched>> {
-    let patterns: Vec<NormalizedTermValue> = thesaurus.keys().cloned().collect();
-
-    let ac = AhoCorasick::builder()
-        .match_kind(MatchKind::LeftmostLongest)
-        .ascii_case_insensitive(true)
-        .build(patterns.clone())?;
-
-    let mut matches: Vec<Matched> = Vec::new();
-    for mat in ac.find_iter(text) {
-        let term = &patterns[mat.pattern()];
-        let normalized_term = thesaurus
-            .get(term)
-            .ok_or_else(|| TerraphimAutomataError::Dict(format!("Unknown term {term}")))?;
-
-        matches.push(Matched {
-            term: term.to_string(),
-            normalized_term: normalized_term.clone(),
-            pos: if return_positions {
-                Some((mat.start(), mat.end()))
-            } else {
-                None
-            },
-        });
-    }
-    Ok(matches)
+    find_matches_with(
+        text,
+        thesaurus,
+        return_positions,
+        NormalizationOptions::default(),
+        MatchMode::default(),
+    )
+}
+
+/// Find all terms of the thesaurus in the text, after normalizing both with
+/// the given options
+///
+/// Positions point into the original, not the normalized text. This builds
+/// a new [`Automata`] on every call, build it once instead when matching
+/// many texts against the same thesaurus.
+pub fn find_matches_with(
+    text: &str,
+    thesaurus: Thesaurus,
+    return_positions: bool,
+    options: NormalizationOptions,
+    mode: MatchMode,
+) -> Result<Vec<Matched>> {
+    let automata = Automata::new(&thesaurus, options)?;
+    Ok(automata.find_matches(text, return_positions, mode))
+}
+
+/// Checks that a match neither starts nor ends inside of a word
+///
+/// A match starts inside of a word if both its first character and the
+/// character before it are word characters, likewise for the end. Terms
+/// starting or ending with punctuation, like "c++", can therefore be followed
+/// by more punctuation.
+pub(crate) fn is_on_word_boundary(text: &str, start: usize, end: usize) -> bool {
+    let is_word = |c: Option<char>| c.is_some_and(is_word_char);
+    let before = text[..start].chars().next_back();
+    let first = text[start..end].chars().next();
+    let last = text[start..end].chars().next_back();
+    let after = text[end..].chars().next();
+    !(is_word(before) && is_word(first) || is_word(last) && is_word(after))
+}
+
+/// Checks if a character is part of a word
+pub(crate) fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// Replace all terms of the thesaurus in the text with links in the given
+/// format
+///
+/// Terms match on word boundaries, and terms inside of existing links or
+/// code are left alone, see [`Automata::replace_matches`].
+pub fn replace_matches_with(
+    text: &str,
+    thesaurus: Thesaurus,
+    format: &LinkFormat,
+) -> Result<String> {
+    let automata = Automata::new(&thesaurus, NormalizationOptions::default())?;
+    Ok(automata.replace_matches(text, format, MatchMode::WordBoundary))
 }
 
 // // This function replacing instead of matching patterns
 pub fn replace_matches(text: &str, thesaurus: Thesaurus) -> Result<Vec<u8>> {
-    let mut patterns: Vec<String> = Vec::new();
-    let mut replace_with: Vec<String> = Vec::new();
-    for (key, value) in thesaurus.into_iter() {
-        patterns.push(key.to_string());
-        replace_with.push(value.clone().id.clone().to_string());
+    let mut result = Vec::with_capacity(text.len());
+    let mut last = 0;
+    for matched in find_matches(text, thesaurus, true)? {
+        let Some((start, end)) = matched.pos else {
+            continue;
+        };
+        // Matches within the same character, e.g. a ligature, can't be
+        // replaced separately
+        if start < last {
+            continue;
+        }
+        result.extend_from_slice(&text.as_bytes()[last..start]);
+        result.extend_from_slice(matched.normalized_term.id.to_string().as_bytes());
+        last = end;
     }
-    let ac = AhoCorasick::builder()
-        .match_kind(MatchKind::LeftmostLongest)
-        .ascii_case_insensitive(true)
-        .build(patterns)?;
-
-    let result = ac.replace_all_bytes(text.as_bytes(), &replace_with);
+    result.extend_from_slice(&text.as_bytes()[last..]);
     Ok(result)
 }
 
-// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::tests::thesaurus;
+
+    fn sample_thesaurus() -> Thesaurus {
+        thesaurus(&[(1, "ärzte"), (2, "straße"), (3, "café")])
+    }
+
+    #[test]
+    fn test_find_matches_unicode() {
+        let text = "ÄRZTE in der Strasse, im Cafe und im café";
+        let matches = find_matches(text, sample_thesaurus(), true).unwrap();
+        let found: Vec<&str> = matches
+            .iter()
+            .map(|matched| {
+                let (start, end) = matched.pos.unwrap();
+                &text[start..end]
+            })
+            .collect();
+        assert_eq!(found, ["ÄRZTE", "Strasse", "café"]);
+
+        let options = NormalizationOptions {
+            strip_diacritics: true,
+            ..Default::default()
+        };
+        let matches = find_matches_with(
+            text,
+            sample_thesaurus(),
+            false,
+            options,
+            MatchMode::Substring,
+        )
+        .unwrap();
+        assert_eq!(matches.len(), 4);
+    }
+
+    #[test]
+    fn test_word_boundary() {
+        let thesaurus = thesaurus(&[(1, "ai"), (2, "main"), (3, "c++")]);
+        let text = "Maintain the AI, mainly in C++.";
+        let options = NormalizationOptions::default();
+
+        let ids = |mode| -> Vec<u64> {
+            find_matches_with(text, thesaurus.clone(), false, options, mode)
+                .unwrap()
+                .into_iter()
+                .map(|matched| {
+                    assert_eq!(matched.mode, mode);
+                    matched.normalized_term.id
+                })
+                .collect()
+        };
+        assert_eq!(ids(MatchMode::Substring), [2, 1, 1, 2, 3]);
+        assert_eq!(ids(MatchMode::WordBoundary), [1, 3]);
+
+        let matches = find_matches_with(
+            "ai",
+            thesaurus.clone(),
+            true,
+            options,
+            MatchMode::WordBoundary,
+        )
+        .unwrap();
+        assert_eq!(matches[0].pos, Some((0, 2)));
+    }
+
+    #[test]
+    fn test_replace_matches() {
+        let replaced = replace_matches("Die Ärzte im Café", sample_thesaurus()).unwrap();
+        assert_eq!(String::from_utf8(replaced).unwrap(), "Die 1 im 3");
+
+        let replaced = replace_matches_with(
+            "Die Ärzte im Café",
+            sample_thesaurus(),
+            &LinkFormat::Markdown,
+        )
+        .unwrap();
+        assert_eq!(replaced, "Die [Ärzte](ärzte) im [Café](café)");
+    }
+}
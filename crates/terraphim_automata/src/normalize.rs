@@ -0,0 +1,210 @@
+//! Normalization of patterns and text before matching
+//!
+//! The automata only matches byte for byte, so "Ärzte" would never match
+//! "ärzte" and the ligature "ﬁ" would never match "fi". Both the patterns of
+//! a thesaurus and the text they are matched against are therefore run
+//! through the same pipeline:
+//!
+//! 1. compatibility normalization (NFKC), which also composes combining
+//!    characters,
+//! 2. optionally stripping diacritics, so that "café" matches "cafe",
+//! 3. Unicode case folding.
+//!
+//! Normalizing changes the length of the text, so [`NormalizedText`] keeps
+//! track of where every byte of the normalized text came from. That way
+//! match positions can be mapped back into the original text.
+
+use serde::{Deserialize, Serialize};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Steps of the normalization pipeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NormalizationOptions {
+    /// Fold the case of the text, so that "Straße" matches "STRASSE"
+    pub case_fold: bool,
+    /// Apply compatibility normalization (NFKC), so that "ﬁ" matches "fi"
+    pub nfkc: bool,
+    /// Remove diacritics, so that "café" matches "cafe"
+    pub strip_diacritics: bool,
+}
+
+impl Default for NormalizationOptions {
+    fn default() -> Self {
+        Self {
+            case_fold: true,
+            nfkc: true,
+            strip_diacritics: false,
+        }
+    }
+}
+
+impl NormalizationOptions {
+    /// Options which leave the text as it is
+    pub fn none() -> Self {
+        Self {
+            case_fold: false,
+            nfkc: false,
+            strip_diacritics: false,
+        }
+    }
+}
+
+/// Normalize a text with the given options
+pub fn normalize(text: &str, options: NormalizationOptions) -> String {
+    NormalizedText::new(text, options).text
+}
+
+/// A normalized text, which remembers the origin of each of its bytes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedText {
+    text: String,
+    /// For every byte of `text`, the byte range of the original text it was
+    /// produced from
+    origins: Vec<(usize, usize)>,
+    /// Length of the original text
+    original_len: usize,
+}
+
+impl NormalizedText {
+    /// Normalize a text with the given options
+    pub fn new(original: &str, options: NormalizationOptions) -> Self {
+        let mut text = String::with_capacity(original.len());
+        let mut origins = Vec::with_capacity(original.len());
+        for (start, cluster) in clusters(original) {
+            let end = start + cluster.len();
+            normalize_cluster(cluster, options, &mut text);
+            origins.resize(text.len(), (start, end));
+        }
+        Self {
+            text,
+            origins,
+            original_len: original.len(),
+        }
+    }
+
+    /// The normalized text
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Maps a byte range of the normalized text to the range of the original
+    /// text it was produced from
+    pub fn original_range(&self, start: usize, end: usize) -> (usize, usize) {
+        if start >= end || start >= self.origins.len() {
+            let offset = self
+                .origins
+                .get(start)
+                .map_or(self.original_len, |origin| origin.0);
+            return (offset, offset);
+        }
+        let end = end.min(self.origins.len());
+        (self.origins[start].0, self.origins[end - 1].1)
+    }
+}
+
+/// Splits a text into clusters of a character and the combining marks
+/// following it, together with their byte offset
+///
+/// Clusters are normalized independently, so that every byte of the
+/// normalized text can be traced back to its cluster.
+pub(crate) fn clusters(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut chars = text.char_indices().peekable();
+    std::iter::from_fn(move || {
+        let (start, _) = chars.next()?;
+        while chars.next_if(|(_, c)| is_combining_mark(*c)).is_some() {}
+        let end = chars.peek().map_or(text.len(), |(offset, _)| *offset);
+        Some((start, &text[start..end]))
+    })
+}
+
+pub(crate) fn normalize_cluster(cluster: &str, options: NormalizationOptions, out: &mut String) {
+    if cluster.is_ascii() {
+        if options.case_fold {
+            out.extend(cluster.chars().map(|c| c.to_ascii_lowercase()));
+        } else {
+            out.push_str(cluster);
+        }
+        return;
+    }
+    let mut cluster = if options.nfkc {
+        cluster.nfkc().collect()
+    } else {
+        cluster.to_string()
+    };
+    if options.strip_diacritics {
+        cluster = cluster
+            .nfd()
+            .filter(|c| !is_combining_mark(*c))
+            .nfc()
+            .collect();
+    }
+    if options.case_fold {
+        for c in cluster.chars() {
+            fold_case(c, out);
+        }
+    } else {
+        out.push_str(&cluster);
+    }
+}
+
+/// Folds the case of a character
+///
+/// This is lowercasing plus the special cases of full case folding, which
+/// lowercasing doesn't cover.
+fn fold_case(c: char, out: &mut String) {
+    match c {
+        'ß' | 'ẞ' => out.push_str("ss"),
+        'ς' => out.push('σ'),
+        _ => out.extend(c.to_lowercase()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        let options = NormalizationOptions::default();
+        assert_eq!(normalize("Ärzte", options), "ärzte");
+        assert_eq!(normalize("STRASSE", options), normalize("Straße", options));
+        assert_eq!(normalize("ﬁnance", options), "finance");
+        // Decomposed and precomposed characters are the same
+        assert_eq!(
+            normalize("Cafe\u{301}", options),
+            normalize("café", options)
+        );
+        assert_ne!(normalize("café", options), "cafe");
+
+        let options = NormalizationOptions {
+            strip_diacritics: true,
+            ..Default::default()
+        };
+        assert_eq!(normalize("Café Crème", options), "cafe creme");
+        assert_eq!(normalize("Ärzte", options), "arzte");
+
+        assert_eq!(normalize("Ärzte", NormalizationOptions::none()), "Ärzte");
+    }
+
+    #[test]
+    fn test_original_range() {
+        let original = "Die ﬁnale Straße";
+        let text = NormalizedText::new(original, NormalizationOptions::default());
+        assert_eq!(text.as_str(), "die finale strasse");
+
+        let start = text.as_str().find("finale").unwrap();
+        let (from, to) = text.original_range(start, start + "finale".len());
+        assert_eq!(&original[from..to], "ﬁnale");
+
+        let start = text.as_str().find("strasse").unwrap();
+        let (from, to) = text.original_range(start, start + "strasse".len());
+        assert_eq!(&original[from..to], "Straße");
+
+        assert_eq!(
+            text.original_range(100, 200),
+            (original.len(), original.len())
+        );
+    }
+}
@@ -0,0 +1,287 @@
+//! Cached loading of remote thesauri
+//!
+//! Remote thesauri are cached through `terraphim_persistence`. A cached
+//! thesaurus is used as it is for a configurable time to live, after that it
+//! is revalidated with its `ETag` and `Last-Modified` headers, so that an
+//! unchanged thesaurus isn't downloaded again. If the server can't be
+//! reached, the last cached copy is used instead.
+
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use async_trait::async_trait;
+use reqwest::header::{ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use terraphim_persistence::Persistable;
+use terraphim_types::Thesaurus;
+
+use crate::{Result, TerraphimAutomataError};
+
+/// How remote thesauri are cached
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteCacheOptions {
+    /// Seconds a cached thesaurus is used without asking the server
+    pub ttl_secs: u64,
+    /// Seconds to wait for the server before giving up
+    pub timeout_secs: u64,
+    /// Use the last cached copy if the server can't be reached
+    pub offline_fallback: bool,
+}
+
+impl Default for RemoteCacheOptions {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 60 * 60,
+            timeout_secs: 30,
+            offline_fallback: true,
+        }
+    }
+}
+
+/// A remote thesaurus together with the headers to revalidate it
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CachedThesaurus {
+    pub url: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    /// When the thesaurus was last fetched or revalidated, in seconds since
+    /// the Unix epoch
+    pub fetched_at: u64,
+    pub thesaurus: Thesaurus,
+}
+
+impl CachedThesaurus {
+    /// Whether the cached thesaurus can be used without asking the server
+    pub fn is_fresh(&self, options: &RemoteCacheOptions) -> bool {
+        now().saturating_sub(self.fetched_at) < options.ttl_secs
+    }
+}
+
+#[async_trait]
+impl Persistable for CachedThesaurus {
+    fn new(key: String) -> Self {
+        CachedThesaurus {
+            url: key.clone(),
+            etag: None,
+            last_modified: None,
+            fetched_at: 0,
+            thesaurus: Thesaurus::new(key),
+        }
+    }
+
+    /// returns key + .json
+    fn get_key(&self) -> String {
+        format!("remote_thesaurus_{}.json", self.normalize_key(&self.url))
+    }
+}
+
+/// Load a remote thesaurus, using the cached copy where possible
+pub async fn load_remote_thesaurus(url: &str, options: &RemoteCacheOptions) -> Result<Thesaurus> {
+    let cached = match CachedThesaurus::new(url.to_string()).load().await {
+        Ok(cached) => Some(cached),
+        Err(e) => {
+            log::debug!("No cached copy of thesaurus {url}: {e}");
+            None
+        }
+    };
+    let (cached, changed) = refresh(url, cached, options).await?;
+    if changed {
+        if let Err(e) = cached.save().await {
+            log::warn!("Failed to cache thesaurus {url}: {e}");
+        }
+    }
+    Ok(cached.thesaurus)
+}
+
+/// Bring a cached thesaurus up to date
+///
+/// Returns the current thesaurus and whether it differs from the cached
+/// one, so it has to be cached again.
+pub async fn refresh(
+    url: &str,
+    cached: Option<CachedThesaurus>,
+    options: &RemoteCacheOptions,
+) -> Result<(CachedThesaurus, bool)> {
+    let cached = cached.filter(|cached| cached.url == url);
+    if let Some(cached) = cached.as_ref().filter(|cached| cached.is_fresh(options)) {
+        log::debug!("Using cached thesaurus {url}");
+        return Ok((cached.clone(), false));
+    }
+    match fetch(url, cached.as_ref(), options).await {
+        Ok(Some(fetched)) => Ok((fetched, true)),
+        Ok(None) => {
+            log::debug!("Cached thesaurus {url} is still up to date");
+            let mut cached = cached.expect("only cached thesauri are revalidated");
+            cached.fetched_at = now();
+            Ok((cached, true))
+        }
+        Err(e) => match cached {
+            Some(cached) if options.offline_fallback => {
+                log::warn!("Using cached copy of thesaurus {url}: {e}");
+                Ok((cached, false))
+            }
+            _ => Err(e),
+        },
+    }
+}
+
+/// Fetch a thesaurus, or `None` if the cached copy is still up to date
+async fn fetch(
+    url: &str,
+    cached: Option<&CachedThesaurus>,
+    options: &RemoteCacheOptions,
+) -> Result<Option<CachedThesaurus>> {
+    log::debug!("Reading thesaurus from remote: {url}");
+    let error = |e: &dyn std::fmt::Display| {
+        TerraphimAutomataError::InvalidThesaurus(format!(
+            "Failed to fetch thesaurus from remote {url}. Error: {e}",
+        ))
+    };
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(options.timeout_secs))
+        .build()
+        .map_err(|e| error(&e))?;
+    let mut request = client.get(url).header("Accept", "application/json");
+    if let Some(cached) = cached {
+        if let Some(etag) = &cached.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &cached.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+    }
+    let response = request.send().await.map_err(|e| error(&e))?;
+
+    let status = response.status();
+    if status == StatusCode::NOT_MODIFIED && cached.is_some() {
+        return Ok(None);
+    }
+    if !status.is_success() {
+        return Err(error(&format!("Status: {status}")));
+    }
+    let header = |name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let etag = header(ETAG);
+    let last_modified = header(LAST_MODIFIED);
+    let body = response.text().await.map_err(|e| error(&e))?;
+    let thesaurus = serde_json::from_str(&body)?;
+    Ok(Some(CachedThesaurus {
+        url: url.to_string(),
+        etag,
+        last_modified,
+        fetched_at: now(),
+        thesaurus,
+    }))
+}
+
+/// Seconds since the Unix epoch
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    const ETAG_VALUE: &str = "\"v1\"";
+    const BODY: &str = r#"{"name": "remote", "data": {"foo": {"id": 1, "nterm": "foo"}}}"#;
+
+    /// Serve the thesaurus over HTTP, answering revalidations with the right
+    /// ETag with `304 Not Modified`
+    ///
+    /// Returns the URL of the thesaurus and the number of requests served.
+    async fn serve() -> (String, Arc<AtomicUsize>, tokio::task::JoinHandle<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/thesaurus.json", listener.local_addr().unwrap());
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let server = tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut request = Vec::new();
+                let mut buffer = [0; 1024];
+                while !request.ends_with(b"\r\n\r\n") {
+                    let read = stream.read(&mut buffer).await.unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    request.extend_from_slice(&buffer[..read]);
+                }
+                counter.fetch_add(1, Ordering::SeqCst);
+                let request = String::from_utf8_lossy(&request).to_lowercase();
+                let response = if request.contains(&format!("if-none-match: {ETAG_VALUE}")) {
+                    "HTTP/1.1 304 Not Modified\r\nConnection: close\r\n\r\n".to_string()
+                } else {
+                    format!(
+                        "HTTP/1.1 200 OK\r\nETag: {ETAG_VALUE}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{BODY}",
+                        BODY.len()
+                    )
+                };
+                stream.write_all(response.as_bytes()).await.unwrap();
+                stream.shutdown().await.ok();
+            }
+        });
+        (url, requests, server)
+    }
+
+    #[tokio::test]
+    async fn test_refresh() {
+        let (url, requests, server) = serve().await;
+        let options = RemoteCacheOptions::default();
+
+        let (cached, changed) = refresh(&url, None, &options).await.unwrap();
+        assert!(changed);
+        assert_eq!(cached.etag.as_deref(), Some(ETAG_VALUE));
+        assert_eq!(cached.thesaurus.name(), "remote");
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // Fresh copies are used without asking the server
+        let (fresh, changed) = refresh(&url, Some(cached.clone()), &options).await.unwrap();
+        assert!(!changed);
+        assert_eq!(fresh, cached);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        // Stale copies are revalidated
+        let expired = RemoteCacheOptions {
+            ttl_secs: 0,
+            ..options
+        };
+        let stale = CachedThesaurus {
+            fetched_at: 0,
+            ..cached.clone()
+        };
+        let (revalidated, changed) = refresh(&url, Some(stale.clone()), &expired).await.unwrap();
+        assert!(changed);
+        assert!(revalidated.fetched_at > 0);
+        assert_eq!(revalidated.thesaurus, cached.thesaurus);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+
+        // Offline, the cached copy is used
+        server.abort();
+        let _ = server.await;
+        let (offline, changed) = refresh(&url, Some(stale.clone()), &expired).await.unwrap();
+        assert!(!changed);
+        assert_eq!(offline, stale);
+
+        let no_fallback = RemoteCacheOptions {
+            offline_fallback: false,
+            ..expired
+        };
+        assert!(refresh(&url, Some(stale), &no_fallback).await.is_err());
+        assert!(refresh(&url, None, &expired).await.is_err());
+    }
+}
@@ -0,0 +1,538 @@
+//! Thesauri in SKOS vocabularies
+//!
+//! Concepts of [SKOS](https://www.w3.org/TR/skos-reference/) vocabularies in
+//! Turtle or RDF/XML become concepts of the thesaurus:
+//!
+//! * the `skos:prefLabel` is the normalized term, `skos:altLabel` and
+//!   `skos:hiddenLabel` are its synonyms
+//! * the URI of the concept is its URL
+//! * its ID is its numeric `skos:notation`, or else the number its URI ends
+//!   with, e.g. 42 for `http://example.org/concept/42`. Concepts without
+//!   either are numbered after the highest ID
+//! * `skos:broader`, `skos:narrower` and `skos:related` become relations
+//!   between the concepts
+//!
+//! Labels can be restricted to one language, labels without a language are
+//! always used. When a thesaurus is written, concepts without a URL get a URI
+//! made of a base IRI and their ID, and their ID is kept as `skos:notation`.
+
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use oxrdf::vocab::rdf;
+use oxrdf::{Literal, NamedNode, NamedOrBlankNode, Term, Triple};
+use oxrdfxml::{RdfXmlParser, RdfXmlSerializer};
+use oxttl::{TurtleParser, TurtleSerializer};
+use serde::{Deserialize, Serialize};
+use terraphim_types::{NormalizedTerm, NormalizedTermValue, RelationKind, Thesaurus};
+
+use crate::tabular::open_thesaurus_file;
+use crate::{Result, TerraphimAutomataError};
+
+/// Namespace of the SKOS vocabulary
+pub const SKOS_NAMESPACE: &str = "http://www.w3.org/2004/02/skos/core#";
+
+/// Serialization of a SKOS vocabulary
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SkosFormat {
+    Turtle,
+    RdfXml,
+}
+
+impl SkosFormat {
+    /// The format given by the extension of a file, ignoring `.gz`
+    ///
+    /// `.ttl` is Turtle, `.rdf`, `.owl` and `.xml` are RDF/XML.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let path = match path.extension() {
+            Some(ext) if ext == "gz" => Path::new(path.file_stem()?),
+            _ => path,
+        };
+        match path.extension()?.to_str()? {
+            "ttl" => Some(SkosFormat::Turtle),
+            "rdf" | "owl" | "xml" => Some(SkosFormat::RdfXml),
+            _ => None,
+        }
+    }
+}
+
+/// How a SKOS vocabulary is read and written
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SkosOptions {
+    /// Format of the vocabulary, by default given by the file extension
+    pub format: Option<SkosFormat>,
+    /// Language of the labels, e.g. `en`
+    ///
+    /// When reading, labels in other languages are ignored. When writing,
+    /// labels are tagged with it.
+    pub language: Option<String>,
+    /// Prefix of the URIs of concepts without a URL, followed by their ID
+    pub base_iri: String,
+}
+
+impl Default for SkosOptions {
+    fn default() -> Self {
+        Self {
+            format: None,
+            language: None,
+            base_iri: "urn:terraphim:concept:".to_string(),
+        }
+    }
+}
+
+impl SkosOptions {
+    fn format(&self, path: &Path) -> Result<SkosFormat> {
+        self.format
+            .or_else(|| SkosFormat::from_path(path))
+            .ok_or_else(|| {
+                TerraphimAutomataError::InvalidThesaurus(format!(
+                    "Unknown SKOS format of {path:?}, expected .ttl, .rdf, .owl or .xml"
+                ))
+            })
+    }
+
+    /// How much a label is preferred, or `None` if it is in another language
+    fn rank(&self, label: &Literal) -> Option<u8> {
+        match (&self.language, label.language()) {
+            (_, None) => Some(1),
+            (None, Some(_)) => Some(2),
+            (Some(wanted), Some(language)) => {
+                let wanted = wanted.to_lowercase();
+                let language = language.to_lowercase();
+                let matches = language == wanted
+                    || language
+                        .strip_prefix(&wanted)
+                        .is_some_and(|rest| rest.starts_with('-'));
+                matches.then_some(0)
+            }
+        }
+    }
+
+    fn label(&self, value: &str) -> Result<Literal> {
+        match &self.language {
+            Some(language) => Literal::new_language_tagged_literal(value, language)
+                .map_err(|e| TerraphimAutomataError::InvalidThesaurus(e.to_string())),
+            None => Ok(Literal::new_simple_literal(value)),
+        }
+    }
+}
+
+/// Load a thesaurus from a SKOS vocabulary
+///
+/// Files ending with `.gz` are decompressed on the fly. The thesaurus is
+/// named after the file.
+pub fn load_skos_thesaurus<P: AsRef<Path>>(path: P, options: &SkosOptions) -> Result<Thesaurus> {
+    let path = path.as_ref();
+    let format = options.format(path)?;
+    let (name, reader) = open_thesaurus_file(path)?;
+    read_skos_thesaurus(name, reader, format, options)
+}
+
+/// Save a thesaurus as a SKOS vocabulary
+///
+/// Files ending with `.gz` are compressed.
+pub fn save_skos_thesaurus<P: AsRef<Path>>(
+    thesaurus: &Thesaurus,
+    path: P,
+    options: &SkosOptions,
+) -> Result<()> {
+    let path = path.as_ref();
+    let format = options.format(path)?;
+    let file = File::create(path)?;
+    if path.extension().is_some_and(|ext| ext == "gz") {
+        let encoder = GzEncoder::new(file, Compression::default());
+        write_skos_thesaurus(thesaurus, encoder, format, options)?.finish()?;
+    } else {
+        write_skos_thesaurus(thesaurus, file, format, options)?;
+    }
+    Ok(())
+}
+
+/// The labels and relations of a subject of the vocabulary
+#[derive(Debug, Default)]
+struct SkosConcept {
+    pref_labels: Vec<Literal>,
+    labels: Vec<Literal>,
+    notations: Vec<String>,
+    relations: Vec<(RelationKind, String)>,
+}
+
+/// Read a thesaurus from a SKOS vocabulary
+pub fn read_skos_thesaurus<R: Read>(
+    name: String,
+    reader: R,
+    format: SkosFormat,
+    options: &SkosOptions,
+) -> Result<Thesaurus> {
+    let triples: Vec<Triple> = match format {
+        SkosFormat::Turtle => TurtleParser::new()
+            .for_reader(reader)
+            .collect::<std::result::Result<_, _>>()?,
+        SkosFormat::RdfXml => RdfXmlParser::new()
+            .for_reader(reader)
+            .collect::<std::result::Result<_, _>>()?,
+    };
+
+    let mut subjects: BTreeMap<String, SkosConcept> = BTreeMap::new();
+    for triple in triples {
+        let NamedOrBlankNode::NamedNode(subject) = triple.subject else {
+            continue;
+        };
+        let Some(predicate) = triple.predicate.as_str().strip_prefix(SKOS_NAMESPACE) else {
+            continue;
+        };
+        let concept = subjects.entry(subject.into_string()).or_default();
+        match (predicate, triple.object) {
+            ("prefLabel", Term::Literal(label)) => concept.pref_labels.push(label),
+            ("altLabel" | "hiddenLabel", Term::Literal(label)) => concept.labels.push(label),
+            ("notation", Term::Literal(notation)) => {
+                concept.notations.push(notation.value().to_string())
+            }
+            (relation, Term::NamedNode(object)) => {
+                if let Ok(kind) = relation.parse::<RelationKind>() {
+                    concept.relations.push((kind, object.into_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Only subjects with a preferred label in the language are concepts
+    let mut concepts: BTreeMap<&str, (NormalizedTermValue, Vec<NormalizedTermValue>)> =
+        BTreeMap::new();
+    for (uri, concept) in &subjects {
+        let mut labels: Vec<(u8, &Literal)> = concept
+            .pref_labels
+            .iter()
+            .filter_map(|label| Some((options.rank(label)?, label)))
+            .collect();
+        labels.sort_by(|(a_rank, a), (b_rank, b)| {
+            (a_rank, a.language(), a.value()).cmp(&(b_rank, b.language(), b.value()))
+        });
+        let Some(((_, pref_label), other_labels)) = labels.split_first() else {
+            if !concept.labels.is_empty() {
+                log::warn!("Skipping concept {uri} without a preferred label");
+            }
+            continue;
+        };
+        let term = NormalizedTermValue::new(pref_label.value().to_string());
+        if term.as_str().is_empty() {
+            log::warn!("Skipping concept {uri} with an empty preferred label");
+            continue;
+        }
+        let synonyms = other_labels
+            .iter()
+            .map(|(_, label)| *label)
+            .chain(
+                concept
+                    .labels
+                    .iter()
+                    .filter(|label| options.rank(label).is_some()),
+            )
+            .map(|label| NormalizedTermValue::new(label.value().to_string()))
+            .filter(|synonym| !synonym.as_str().is_empty())
+            .collect();
+        concepts.insert(uri, (term, synonyms));
+    }
+
+    // Explicit IDs take precedence, the other concepts are numbered after
+    // the highest of them
+    let mut ids: BTreeMap<&str, u64> = BTreeMap::new();
+    let mut used = HashSet::new();
+    for &uri in concepts.keys() {
+        let notation = subjects[uri]
+            .notations
+            .iter()
+            .find_map(|notation| notation.trim().parse().ok());
+        match notation.or_else(|| trailing_number(uri)) {
+            Some(id) if used.insert(id) => {
+                ids.insert(uri, id);
+            }
+            Some(id) => log::warn!("ID {id} of concept {uri} is already taken. Renumbering"),
+            None => {}
+        }
+    }
+    let mut next_id = used.iter().max().map_or(1, |max| max + 1);
+    for &uri in concepts.keys() {
+        ids.entry(uri).or_insert_with(|| {
+            next_id += 1;
+            next_id - 1
+        });
+    }
+
+    let mut thesaurus = Thesaurus::new(name);
+    for (&uri, (term, _)) in &concepts {
+        thesaurus.insert(
+            term.clone(),
+            NormalizedTerm::new(ids[uri], term.clone()).with_url(uri),
+        );
+    }
+    for (&uri, (term, synonyms)) in &concepts {
+        for synonym in synonyms {
+            match thesaurus.get(synonym) {
+                Some(existing) if existing.value != *term => log::warn!(
+                    "Synonym `{synonym}` of `{term}` already maps to `{}`. Skipping",
+                    existing.value
+                ),
+                Some(_) => {}
+                None => thesaurus.insert(
+                    synonym.clone(),
+                    NormalizedTerm::new(ids[uri], term.clone()).with_url(uri),
+                ),
+            }
+        }
+    }
+    for (&uri, &from) in &ids {
+        for (kind, object) in &subjects[uri].relations {
+            match ids.get(object.as_str()) {
+                Some(&to) => thesaurus.add_relation(from, *kind, to),
+                None => log::debug!("Skipping {kind} concept {object} of {uri}, it has no label"),
+            }
+        }
+    }
+    Ok(thesaurus)
+}
+
+/// The number a URI ends with, if any
+fn trailing_number(uri: &str) -> Option<u64> {
+    let prefix = uri.trim_end_matches(|c: char| c.is_ascii_digit());
+    uri[prefix.len()..].parse().ok()
+}
+
+/// Write a thesaurus as a SKOS vocabulary
+///
+/// Every concept is written with its normalized term as `skos:prefLabel`,
+/// its other synonyms as `skos:altLabel`, its ID as `skos:notation` and its
+/// relations. Returns the writer.
+pub fn write_skos_thesaurus<W: Write>(
+    thesaurus: &Thesaurus,
+    writer: W,
+    format: SkosFormat,
+    options: &SkosOptions,
+) -> Result<W> {
+    // The concepts by ID, with the synonyms which aren't their own term
+    let entries: BTreeMap<&NormalizedTermValue, &NormalizedTerm> = thesaurus.into_iter().collect();
+    let mut concepts: BTreeMap<u64, (&NormalizedTerm, BTreeSet<&NormalizedTermValue>)> =
+        BTreeMap::new();
+    for (key, nterm) in entries {
+        let (concept, synonyms) = concepts.entry(nterm.id).or_insert((nterm, BTreeSet::new()));
+        if *key == nterm.value {
+            *concept = nterm;
+        }
+        synonyms.insert(key);
+    }
+
+    let mut uris = BTreeMap::new();
+    for (&id, (nterm, _)) in &concepts {
+        let uri = nterm
+            .url
+            .as_deref()
+            .and_then(|url| NamedNode::new(url).ok())
+            .map_or_else(|| NamedNode::new(format!("{}{id}", options.base_iri)), Ok)
+            .map_err(|e| {
+                TerraphimAutomataError::InvalidThesaurus(format!(
+                    "Invalid base IRI `{}`: {e}",
+                    options.base_iri
+                ))
+            })?;
+        uris.insert(id, uri);
+    }
+
+    let skos = |name: &str| NamedNode::new_unchecked(format!("{SKOS_NAMESPACE}{name}"));
+    let mut triples = Vec::new();
+    for (id, (nterm, synonyms)) in &concepts {
+        let uri = &uris[id];
+        let triple =
+            |predicate: NamedNode, object: Term| Triple::new(uri.clone(), predicate, object);
+        triples.push(triple(rdf::TYPE.into_owned(), skos("Concept").into()));
+        triples.push(triple(
+            skos("prefLabel"),
+            options.label(nterm.value.as_str())?.into(),
+        ));
+        for synonym in synonyms.iter().filter(|synonym| ***synonym != nterm.value) {
+            triples.push(triple(
+                skos("altLabel"),
+                options.label(synonym.as_str())?.into(),
+            ));
+        }
+        triples.push(triple(
+            skos("notation"),
+            Literal::new_simple_literal(id.to_string()).into(),
+        ));
+    }
+    for relation in thesaurus.relations() {
+        if let (Some(from), Some(to)) = (uris.get(&relation.from), uris.get(&relation.to)) {
+            triples.push(Triple::new(
+                from.clone(),
+                skos(&relation.kind.to_string()),
+                to.clone(),
+            ));
+        }
+    }
+
+    let writer = match format {
+        SkosFormat::Turtle => {
+            let mut serializer = TurtleSerializer::new()
+                .with_prefix("skos", SKOS_NAMESPACE)
+                .expect("the SKOS namespace is a valid IRI")
+                .for_writer(writer);
+            for triple in &triples {
+                serializer.serialize_triple(triple)?;
+            }
+            serializer.finish()?
+        }
+        SkosFormat::RdfXml => {
+            let mut serializer = RdfXmlSerializer::new()
+                .with_prefix("skos", SKOS_NAMESPACE)
+                .expect("the SKOS namespace is a valid IRI")
+                .for_writer(writer);
+            for triple in &triples {
+                serializer.serialize_triple(triple)?;
+            }
+            serializer.finish()?
+        }
+    };
+    Ok(writer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VOCABULARY: &str = r#"
+@prefix skos: <http://www.w3.org/2004/02/skos/core#> .
+@prefix ex: <http://example.org/concept/> .
+
+ex:10 a skos:Concept ;
+    skos:prefLabel "Container orchestration"@en, "Orchestrierung"@de ;
+    skos:narrower ex:kubernetes .
+
+ex:kubernetes a skos:Concept ;
+    skos:prefLabel "Kubernetes" ;
+    skos:altLabel "k8s", "Kubernetes-Cluster"@de ;
+    skos:hiddenLabel "kube"@en-GB ;
+    skos:notation "42" ;
+    skos:related ex:helm .
+
+ex:helm a skos:Concept ;
+    skos:prefLabel "Helm"@en ;
+    skos:broader ex:missing .
+
+ex:german a skos:Concept ;
+    skos:prefLabel "Nur Deutsch"@de .
+"#;
+
+    fn english() -> SkosOptions {
+        SkosOptions {
+            language: Some("en".to_string()),
+            ..SkosOptions::default()
+        }
+    }
+
+    fn get<'a>(thesaurus: &'a Thesaurus, key: &str) -> Option<&'a NormalizedTerm> {
+        thesaurus.get(&NormalizedTermValue::from(key))
+    }
+
+    #[test]
+    fn test_read_skos_thesaurus() {
+        let thesaurus = read_skos_thesaurus(
+            "devops".to_string(),
+            VOCABULARY.as_bytes(),
+            SkosFormat::Turtle,
+            &english(),
+        )
+        .unwrap();
+        assert_eq!(thesaurus.name(), "devops");
+        assert_eq!(thesaurus.len(), 5);
+
+        let orchestration = get(&thesaurus, "container orchestration").unwrap();
+        assert_eq!(orchestration.id, 10);
+        assert_eq!(
+            orchestration.url.as_deref(),
+            Some("http://example.org/concept/10")
+        );
+        assert!(get(&thesaurus, "orchestrierung").is_none());
+
+        let kubernetes = get(&thesaurus, "k8s").unwrap();
+        assert_eq!(kubernetes.id, 42);
+        assert_eq!(kubernetes.value.as_str(), "kubernetes");
+        assert_eq!(get(&thesaurus, "kube"), Some(kubernetes));
+        assert!(get(&thesaurus, "kubernetes-cluster").is_none());
+
+        // Concepts without an ID are numbered after the highest one
+        assert_eq!(get(&thesaurus, "helm").unwrap().id, 43);
+        assert!(get(&thesaurus, "nur deutsch").is_none());
+
+        assert_eq!(
+            thesaurus.related_concepts(42, RelationKind::Broader),
+            BTreeSet::from([10])
+        );
+        assert_eq!(
+            thesaurus.related_concepts(43, RelationKind::Related),
+            BTreeSet::from([42])
+        );
+        assert_eq!(thesaurus.relations().count(), 2);
+
+        // Without a language, all labels are used
+        let thesaurus = read_skos_thesaurus(
+            "devops".to_string(),
+            VOCABULARY.as_bytes(),
+            SkosFormat::Turtle,
+            &SkosOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(get(&thesaurus, "orchestrierung").unwrap().id, 10);
+        assert_eq!(get(&thesaurus, "kubernetes-cluster").unwrap().id, 42);
+        assert!(get(&thesaurus, "nur deutsch").is_some());
+    }
+
+    #[test]
+    fn test_skos_roundtrip() {
+        let thesaurus = read_skos_thesaurus(
+            "devops".to_string(),
+            VOCABULARY.as_bytes(),
+            SkosFormat::Turtle,
+            &english(),
+        )
+        .unwrap();
+        for format in [SkosFormat::Turtle, SkosFormat::RdfXml] {
+            let written = write_skos_thesaurus(&thesaurus, Vec::new(), format, &english()).unwrap();
+            let restored =
+                read_skos_thesaurus("devops".to_string(), &written[..], format, &english())
+                    .unwrap();
+            assert_eq!(restored, thesaurus, "{format:?}");
+        }
+
+        // Concepts without a URL keep their ID through the base IRI
+        let mut thesaurus = Thesaurus::new("plain".to_string());
+        let foo = NormalizedTerm::new(7, NormalizedTermValue::from("foo"));
+        thesaurus.insert(NormalizedTermValue::from("foo"), foo.clone());
+        thesaurus.insert(NormalizedTermValue::from("bar"), foo);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("plain.ttl.gz");
+        save_skos_thesaurus(&thesaurus, &path, &SkosOptions::default()).unwrap();
+        let restored = load_skos_thesaurus(&path, &SkosOptions::default()).unwrap();
+        assert_eq!(restored.name(), "plain");
+        assert_eq!(restored.len(), 2);
+        let bar = get(&restored, "bar").unwrap();
+        assert_eq!(bar.id, 7);
+        assert_eq!(bar.value.as_str(), "foo");
+        assert_eq!(bar.url.as_deref(), Some("urn:terraphim:concept:7"));
+    }
+
+    #[test]
+    fn test_skos_format_from_path() {
+        let format = |path: &str| SkosFormat::from_path(Path::new(path));
+        assert_eq!(format("vocabulary.ttl"), Some(SkosFormat::Turtle));
+        assert_eq!(format("vocabulary.ttl.gz"), Some(SkosFormat::Turtle));
+        assert_eq!(format("vocabulary.rdf"), Some(SkosFormat::RdfXml));
+        assert_eq!(format("vocabulary.json"), None);
+        assert_eq!(format("vocabulary.gz"), None);
+    }
+}
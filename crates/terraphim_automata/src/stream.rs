@@ -0,0 +1,424 @@
+//! Matching the terms of a thesaurus in streams
+//!
+//! [`Automata::find_matches`] needs the whole text in memory, which doesn't
+//! work for multi-gigabyte logs or corpora. A [`StreamMatcher`] consumes a
+//! [`std::io::Read`] or [`tokio::io::AsyncRead`] chunk by chunk and yields
+//! the matches as they are found, with absolute byte offsets into the
+//! stream.
+//!
+//! The stream is normalized and matched like a text, so the matches are the
+//! same as the ones [`Automata::find_matches`] finds in the whole stream.
+//! Only the normalized text which can still be part of a match is buffered,
+//! so memory stays bounded by the length of the longest term. Invalid UTF-8
+//! is matched as U+FFFD REPLACEMENT CHARACTER.
+
+use std::collections::VecDeque;
+use std::io::{self, Read};
+use std::sync::Arc;
+
+use fst::Streamer;
+use terraphim_types::Thesaurus;
+use tokio::io::{AsyncRead, AsyncReadExt};
+
+use crate::automata::Automata;
+use crate::matcher::{MatchMode, Matched};
+use crate::normalize::{clusters, normalize_cluster, NormalizationOptions};
+use crate::Result;
+
+/// Number of bytes read from a stream at once
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Matches the terms of a thesaurus in streams
+#[derive(Debug, Clone)]
+pub struct StreamMatcher {
+    automata: Arc<Automata>,
+    mode: MatchMode,
+    /// Length of the longest normalized term in bytes
+    max_len: usize,
+}
+
+impl StreamMatcher {
+    /// Build a stream matcher for all terms of an automata, which finds the
+    /// same matches as [`Automata::find_matches`] with the given mode
+    pub fn new(automata: Arc<Automata>, mode: MatchMode) -> Self {
+        let mut max_len = 0;
+        let mut stream = automata.fst().stream();
+        while let Some((pattern, _)) = stream.next() {
+            max_len = max_len.max(pattern.len());
+        }
+        Self {
+            automata,
+            mode,
+            max_len,
+        }
+    }
+
+    /// Build a stream matcher for all terms of the thesaurus
+    pub fn from_thesaurus(
+        thesaurus: &Thesaurus,
+        normalization: NormalizationOptions,
+        mode: MatchMode,
+    ) -> Result<Self> {
+        let automata = Automata::new(thesaurus, normalization)?;
+        Ok(Self::new(Arc::new(automata), mode))
+    }
+
+    /// Iterate over all terms in a reader
+    ///
+    /// The reader is read in chunks, wrapping it in a
+    /// [`std::io::BufReader`] isn't necessary.
+    pub fn find_iter<R: Read>(&self, reader: R) -> StreamMatches<'_, R> {
+        StreamMatches {
+            reader,
+            buffer: vec![0; CHUNK_SIZE],
+            search: Search::new(self),
+            done: false,
+        }
+    }
+
+    /// Find all terms in an asynchronous reader
+    ///
+    /// Call [`AsyncStreamMatches::next_match`] to get the matches one by
+    /// one.
+    pub fn find_iter_async<R: AsyncRead + Unpin>(&self, reader: R) -> AsyncStreamMatches<'_, R> {
+        AsyncStreamMatches {
+            reader,
+            buffer: vec![0; CHUNK_SIZE],
+            search: Search::new(self),
+            done: false,
+        }
+    }
+}
+
+/// Iterator over the matches in a [`Read`], see [`StreamMatcher::find_iter`]
+pub struct StreamMatches<'m, R> {
+    reader: R,
+    buffer: Vec<u8>,
+    search: Search<'m>,
+    done: bool,
+}
+
+impl<R: Read> Iterator for StreamMatches<'_, R> {
+    type Item = io::Result<Matched>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(matched) = self.search.matches.pop_front() {
+                return Some(Ok(matched));
+            }
+            if self.done {
+                return None;
+            }
+            match self.reader.read(&mut self.buffer) {
+                Ok(0) => {
+                    self.search.finish();
+                    self.done = true;
+                }
+                Ok(read) => self.search.feed(&self.buffer[..read]),
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+/// Matches in an [`AsyncRead`], see [`StreamMatcher::find_iter_async`]
+pub struct AsyncStreamMatches<'m, R> {
+    reader: R,
+    buffer: Vec<u8>,
+    search: Search<'m>,
+    done: bool,
+}
+
+impl<R: AsyncRead + Unpin> AsyncStreamMatches<'_, R> {
+    /// The next match in the stream, or `None` at its end
+    pub async fn next_match(&mut self) -> io::Result<Option<Matched>> {
+        loop {
+            if let Some(matched) = self.search.matches.pop_front() {
+                return Ok(Some(matched));
+            }
+            if self.done {
+                return Ok(None);
+            }
+            match self.reader.read(&mut self.buffer).await {
+                Ok(0) => {
+                    self.search.finish();
+                    self.done = true;
+                }
+                Ok(read) => self.search.feed(&self.buffer[..read]),
+                Err(e) => {
+                    self.done = true;
+                    return Err(e);
+                }
+            }
+        }
+    }
+}
+
+/// State of a leftmost-longest search across the chunks of a stream
+struct Search<'m> {
+    matcher: &'m StreamMatcher,
+    /// Bytes of the stream which weren't normalized yet, as they end with an
+    /// incomplete character or a cluster which may still continue
+    raw: Vec<u8>,
+    /// Offset of the first byte of `raw` in the stream
+    raw_offset: usize,
+    /// Normalized text which wasn't searched yet, preceded by the last
+    /// searched character for word boundary checks
+    text: String,
+    /// For every byte of `text`, the byte range of the stream it was
+    /// produced from
+    origins: Vec<(usize, usize)>,
+    /// Position in `text` where the search continues
+    position: usize,
+    matches: VecDeque<Matched>,
+}
+
+impl<'m> Search<'m> {
+    fn new(matcher: &'m StreamMatcher) -> Self {
+        Self {
+            matcher,
+            raw: Vec::new(),
+            raw_offset: 0,
+            text: String::new(),
+            origins: Vec::new(),
+            position: 0,
+            matches: VecDeque::new(),
+        }
+    }
+
+    /// Search the next chunk of the stream
+    fn feed(&mut self, chunk: &[u8]) {
+        self.raw.extend_from_slice(chunk);
+        self.normalize(false);
+        self.search(false);
+    }
+
+    /// Search the rest of the stream at its end
+    fn finish(&mut self) {
+        self.normalize(true);
+        self.search(true);
+    }
+
+    /// Normalize the complete clusters of the raw bytes, see
+    /// [`crate::normalize::NormalizedText`]
+    ///
+    /// The last cluster is held back until the next chunk shows whether
+    /// more combining marks belong to it, unless the stream is at its end.
+    fn normalize(&mut self, at_end: bool) {
+        let options = self.matcher.automata.normalization();
+        let mut consumed = 0;
+        while consumed < self.raw.len() {
+            let rest = &self.raw[consumed..];
+            let offset = self.raw_offset + consumed;
+            let (valid, invalid) = match std::str::from_utf8(rest) {
+                Ok(valid) => (valid, None),
+                Err(e) => {
+                    let valid = std::str::from_utf8(&rest[..e.valid_up_to()])
+                        .expect("bytes up to the error are valid");
+                    // An incomplete character can still be completed by the
+                    // next chunk
+                    let invalid = e
+                        .error_len()
+                        .or_else(|| at_end.then_some(rest.len() - e.valid_up_to()));
+                    (valid, invalid)
+                }
+            };
+
+            let complete = if at_end || invalid.is_some() {
+                valid.len()
+            } else {
+                clusters(valid).last().map_or(0, |(start, _)| start)
+            };
+            for (start, cluster) in clusters(&valid[..complete]) {
+                let origin = (offset + start, offset + start + cluster.len());
+                normalize_cluster(cluster, options, &mut self.text);
+                self.origins.resize(self.text.len(), origin);
+            }
+            consumed += complete;
+
+            let Some(len) = invalid else {
+                break;
+            };
+            let origin = (offset + complete, offset + complete + len);
+            normalize_cluster(
+                &char::REPLACEMENT_CHARACTER.to_string(),
+                options,
+                &mut self.text,
+            );
+            self.origins.resize(self.text.len(), origin);
+            consumed += len;
+        }
+        self.raw.drain(..consumed);
+        self.raw_offset += consumed;
+    }
+
+    /// Search the normalized text, see [`Automata::find_matches`]
+    ///
+    /// The match at a position is only known once the longest term and the
+    /// character after it fit into the text, unless the stream is at its
+    /// end.
+    fn search(&mut self, at_end: bool) {
+        let automata = &self.matcher.automata;
+        let lookahead = self.matcher.max_len + char::MAX.len_utf8();
+        while self.position < self.text.len()
+            && (at_end || self.position + lookahead <= self.text.len())
+        {
+            let start = self.position;
+            self.position += self.text[start..].chars().next().map_or(1, char::len_utf8);
+            let Some((end, index)) = automata.longest_match(&self.text, start, self.matcher.mode)
+            else {
+                continue;
+            };
+            self.position = end;
+            if let Some((key, term)) = automata.entry(index as u64) {
+                self.matches.push_back(Matched {
+                    term: key.to_string(),
+                    normalized_term: term.clone(),
+                    pos: Some((self.origins[start].0, self.origins[end - 1].1)),
+                    mode: self.matcher.mode,
+                });
+            }
+        }
+
+        // Only keep the character before the position
+        let searched = self.text[..self.position]
+            .chars()
+            .next_back()
+            .map_or(0, |c| self.position - c.len_utf8());
+        self.text.drain(..searched);
+        self.origins.drain(..searched);
+        self.position -= searched;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Cursor;
+
+    use crate::tests::thesaurus;
+
+    fn sample_automata(
+        entries: &[(u64, &str)],
+        normalization: NormalizationOptions,
+    ) -> Arc<Automata> {
+        Arc::new(Automata::new(&thesaurus(entries), normalization).unwrap())
+    }
+
+    /// A reader which returns at most one byte per read
+    struct Trickle<R>(R);
+
+    impl<R: Read> Read for Trickle<R> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(1);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    /// Checks that the stream matcher finds the same matches as
+    /// [`Automata::find_matches`], also if characters are split across reads
+    fn assert_parity(automata: &Arc<Automata>, text: &str, mode: MatchMode) -> Vec<Matched> {
+        let matcher = StreamMatcher::new(automata.clone(), mode);
+        let expected = automata.find_matches(text, true, mode);
+
+        let matches: Vec<Matched> = matcher
+            .find_iter(Cursor::new(text))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(matches, expected);
+
+        let matches: Vec<Matched> = matcher
+            .find_iter(Trickle(Cursor::new(text)))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(matches, expected);
+        expected
+    }
+
+    #[test]
+    fn test_stream_matches() {
+        let text = "Helm charts for KUBERNETES operators, kube and kubernetes op";
+        let automata = sample_automata(
+            &[
+                (1, "kube"),
+                (2, "kubernetes"),
+                (3, "kubernetes operator"),
+                (4, "helm"),
+            ],
+            NormalizationOptions::default(),
+        );
+        let matches = assert_parity(&automata, text, MatchMode::Substring);
+        let ids: Vec<u64> = matches.iter().map(|m| m.normalized_term.id).collect();
+        assert_eq!(ids, [4, 3, 1, 2]);
+
+        let matches = assert_parity(&automata, text, MatchMode::WordBoundary);
+        let ids: Vec<u64> = matches.iter().map(|m| m.normalized_term.id).collect();
+        assert_eq!(ids, [4, 2, 1, 2]);
+    }
+
+    #[test]
+    fn test_stream_matches_normalized() {
+        let text = "Die STRASSE zum Cafe\u{301}, die Straße zur cafeteria: ﬁnance.";
+        let terms = [(1, "straße"), (2, "café"), (3, "finance")];
+        let options = NormalizationOptions {
+            strip_diacritics: true,
+            ..Default::default()
+        };
+        let automata = sample_automata(&terms, options);
+        let matches = assert_parity(&automata, text, MatchMode::Substring);
+        let found: Vec<&str> = matches
+            .iter()
+            .map(|m| {
+                let (start, end) = m.pos.unwrap();
+                &text[start..end]
+            })
+            .collect();
+        assert_eq!(
+            found,
+            ["STRASSE", "Cafe\u{301}", "Straße", "cafe", "ﬁnance"]
+        );
+        assert_eq!(
+            assert_parity(&automata, text, MatchMode::WordBoundary).len(),
+            4
+        );
+
+        // Without normalization, none of the terms are in the text as is
+        let automata = sample_automata(&terms, NormalizationOptions::none());
+        assert!(assert_parity(&automata, text, MatchMode::Substring).is_empty());
+    }
+
+    #[test]
+    fn test_stream_invalid_utf8() {
+        let automata = sample_automata(&[(1, "helm")], NormalizationOptions::default());
+        let matcher = StreamMatcher::new(automata, MatchMode::Substring);
+        let matches: Vec<Matched> = matcher
+            .find_iter(Cursor::new(b"\xffhelm\xe2\x82"))
+            .collect::<io::Result<_>>()
+            .unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].pos, Some((1, 5)));
+    }
+
+    #[tokio::test]
+    async fn test_stream_matches_async() {
+        let automata = sample_automata(
+            &[(1, "kube"), (2, "kubernetes")],
+            NormalizationOptions::default(),
+        );
+        let matcher = StreamMatcher::new(automata, MatchMode::Substring);
+        let text = "kubernetes ".repeat(10_000);
+        let mut matches = matcher.find_iter_async(text.as_bytes());
+        let mut count = 0;
+        while let Some(matched) = matches.next_match().await.unwrap() {
+            assert_eq!(matched.normalized_term.id, 2);
+            assert_eq!(matched.pos, Some((count * 11, count * 11 + 10)));
+            count += 1;
+        }
+        assert_eq!(count, 10_000);
+    }
+}
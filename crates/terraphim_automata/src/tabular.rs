@@ -0,0 +1,420 @@
+//! Thesauri in CSV and TSV files
+//!
+//! Every row of the file describes a concept: its normalized term, and
+//! optionally its synonyms, its ID and its URL, each in a configurable
+//! column. Synonyms within a single cell are separated by a configurable
+//! character. The normalized term is a synonym of itself.
+//!
+//! Rows are validated one by one, and all invalid rows are reported
+//! together, see [`TerraphimAutomataError::InvalidRows`].
+
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use ahash::AHashMap;
+use flate2::read::GzDecoder;
+use serde::{Deserialize, Serialize};
+use terraphim_types::{NormalizedTerm, NormalizedTermValue, Thesaurus};
+
+use crate::{Result, TerraphimAutomataError};
+
+/// A column of a CSV file, either by position or by header name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CsvColumn {
+    /// Zero-based position of the column
+    Index(usize),
+    /// Name of the column in the header row
+    Name(String),
+}
+
+impl From<usize> for CsvColumn {
+    fn from(index: usize) -> Self {
+        CsvColumn::Index(index)
+    }
+}
+
+impl From<&str> for CsvColumn {
+    fn from(name: &str) -> Self {
+        CsvColumn::Name(name.to_string())
+    }
+}
+
+impl fmt::Display for CsvColumn {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvColumn::Index(index) => write!(f, "column {index}"),
+            CsvColumn::Name(name) => write!(f, "column `{name}`"),
+        }
+    }
+}
+
+/// Layout of a CSV or TSV thesaurus
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CsvOptions {
+    /// Separator of the columns
+    pub delimiter: char,
+    /// Whether the first row holds the names of the columns
+    pub has_headers: bool,
+    /// Column of the normalized term of the concept
+    pub term_column: CsvColumn,
+    /// Column of the synonyms of the concept, if any
+    pub synonyms_column: Option<CsvColumn>,
+    /// Separator of the synonyms within their column
+    pub synonym_separator: char,
+    /// Column of the ID of the concept
+    ///
+    /// Without it, concepts are numbered in the order they appear.
+    pub id_column: Option<CsvColumn>,
+    /// Column of the URL of the concept, if any
+    pub url_column: Option<CsvColumn>,
+    /// Skip invalid rows with a warning instead of failing
+    pub skip_invalid_rows: bool,
+}
+
+impl Default for CsvOptions {
+    fn default() -> Self {
+        Self {
+            delimiter: ',',
+            has_headers: true,
+            term_column: CsvColumn::Name("term".to_string()),
+            synonyms_column: None,
+            synonym_separator: '|',
+            id_column: None,
+            url_column: None,
+            skip_invalid_rows: false,
+        }
+    }
+}
+
+impl CsvOptions {
+    /// Options for tab separated files, with the default columns
+    pub fn tsv() -> Self {
+        Self {
+            delimiter: '\t',
+            ..Default::default()
+        }
+    }
+}
+
+/// An invalid row of a CSV thesaurus
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RowError {
+    /// One-based line of the row in the file
+    pub line: u64,
+    /// What is wrong with the row
+    pub message: String,
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Load a thesaurus from a CSV or TSV file
+///
+/// Files ending with `.gz` are decompressed on the fly. The thesaurus is
+/// named after the file.
+pub fn load_csv_thesaurus<P: AsRef<Path>>(path: P, options: &CsvOptions) -> Result<Thesaurus> {
+    let (name, reader) = open_thesaurus_file(path.as_ref())?;
+    read_csv_thesaurus(name, reader, options)
+}
+
+/// Opens a thesaurus file, decompressing it if it ends with `.gz`
+///
+/// Returns the name of the thesaurus, which is the name of the file without
+/// its extensions, and the reader of its contents.
+pub(crate) fn open_thesaurus_file(path: &Path) -> Result<(String, Box<dyn Read>)> {
+    let file = File::open(path)?;
+    let reader: Box<dyn Read> = if path.extension().is_some_and(|ext| ext == "gz") {
+        Box::new(GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    let stem = match path.extension() {
+        Some(ext) if ext == "gz" => path.file_stem().map(Path::new).and_then(Path::file_stem),
+        _ => path.file_stem(),
+    };
+    let name = stem.unwrap_or_default().to_string_lossy().into_owned();
+    Ok((name, reader))
+}
+
+/// Read a thesaurus from CSV or TSV data
+pub fn read_csv_thesaurus<R: Read>(
+    name: String,
+    reader: R,
+    options: &CsvOptions,
+) -> Result<Thesaurus> {
+    let delimiter = u8::try_from(options.delimiter).map_err(|_| {
+        TerraphimAutomataError::InvalidThesaurus(format!(
+            "CSV delimiter `{}` isn't an ASCII character",
+            options.delimiter
+        ))
+    })?;
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(options.has_headers)
+        .flexible(true)
+        .from_reader(reader);
+
+    let headers = if options.has_headers {
+        Some(reader.headers()?.clone())
+    } else {
+        None
+    };
+    let index = |column: &CsvColumn| -> Result<usize> {
+        match (column, &headers) {
+            (CsvColumn::Index(index), _) => Ok(*index),
+            (CsvColumn::Name(name), Some(headers)) => headers
+                .iter()
+                .position(|header| header.trim() == name)
+                .ok_or_else(|| {
+                    TerraphimAutomataError::InvalidThesaurus(format!("CSV file has no {column}"))
+                }),
+            (CsvColumn::Name(_), None) => Err(TerraphimAutomataError::InvalidThesaurus(format!(
+                "CSV file has no header row to find {column}"
+            ))),
+        }
+    };
+    let columns = Columns {
+        term: index(&options.term_column)?,
+        synonyms: options.synonyms_column.as_ref().map(index).transpose()?,
+        id: options.id_column.as_ref().map(index).transpose()?,
+        url: options.url_column.as_ref().map(index).transpose()?,
+    };
+
+    let mut builder = Builder {
+        thesaurus: Thesaurus::new(name),
+        ids: AHashMap::new(),
+        next_id: 1,
+    };
+    let mut errors = Vec::new();
+    for record in reader.records() {
+        let (line, result) = match record {
+            Ok(record) => {
+                let line = record.position().map_or(0, |position| position.line());
+                (line, builder.add(&record, &columns, options))
+            }
+            Err(e) => {
+                let line = e.position().map_or(0, |position| position.line());
+                (line, Err(e.to_string()))
+            }
+        };
+        if let Err(message) = result {
+            let error = RowError { line, message };
+            if options.skip_invalid_rows {
+                log::warn!("Skipping invalid row of CSV thesaurus: {error}");
+            } else {
+                errors.push(error);
+            }
+        }
+    }
+    if !errors.is_empty() {
+        return Err(TerraphimAutomataError::InvalidRows(errors));
+    }
+    Ok(builder.thesaurus)
+}
+
+/// Positions of the configured columns
+struct Columns {
+    term: usize,
+    synonyms: Option<usize>,
+    id: Option<usize>,
+    url: Option<usize>,
+}
+
+struct Builder {
+    thesaurus: Thesaurus,
+    /// IDs of the concepts seen so far
+    ids: AHashMap<NormalizedTermValue, u64>,
+    next_id: u64,
+}
+
+impl Builder {
+    /// Add the concept of a row and its synonyms
+    ///
+    /// Nothing is added if the row is invalid.
+    fn add(
+        &mut self,
+        record: &csv::StringRecord,
+        columns: &Columns,
+        options: &CsvOptions,
+    ) -> std::result::Result<(), String> {
+        let cell = |index: usize| {
+            record
+                .get(index)
+                .map(str::trim)
+                .ok_or_else(|| format!("missing column {index}"))
+        };
+
+        let term = cell(columns.term)?;
+        if term.is_empty() {
+            return Err("empty term".to_string());
+        }
+        let value = NormalizedTermValue::from(term);
+        let id = match columns.id {
+            Some(index) => {
+                let id = cell(index)?;
+                id.parse::<u64>()
+                    .map_err(|_| format!("invalid concept ID `{id}`"))?
+            }
+            None => match self.ids.get(&value) {
+                Some(id) => *id,
+                None => self.next_id,
+            },
+        };
+        if let Some(existing) = self.ids.get(&value) {
+            if *existing != id {
+                return Err(format!(
+                    "concept `{value}` has ID {existing} in an earlier row, not {id}"
+                ));
+            }
+        }
+        let url = columns
+            .url
+            .map(cell)
+            .transpose()?
+            .filter(|url| !url.is_empty());
+
+        let mut synonyms = vec![value.clone()];
+        if let Some(index) = columns.synonyms {
+            synonyms.extend(
+                cell(index)?
+                    .split(options.synonym_separator)
+                    .map(str::trim)
+                    .filter(|synonym| !synonym.is_empty())
+                    .map(NormalizedTermValue::from),
+            );
+        }
+        for synonym in &synonyms {
+            if let Some(existing) = self.thesaurus.get(synonym) {
+                if existing.value != value {
+                    return Err(format!(
+                        "synonym `{synonym}` already belongs to concept `{}`",
+                        existing.value
+                    ));
+                }
+            }
+        }
+
+        let mut nterm = NormalizedTerm::new(id, value.clone());
+        nterm.url = url.map(str::to_string);
+        for synonym in synonyms {
+            self.thesaurus.insert(synonym, nterm.clone());
+        }
+        if self.ids.insert(value, id).is_none() && columns.id.is_none() {
+            self.next_id += 1;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_csv_thesaurus() {
+        let data = "\
+id,term,synonyms,url
+1,Kubernetes,k8s | kube,https://kubernetes.io
+2,Helm,,
+1,kubernetes,kubernetes cluster,
+";
+        let options = CsvOptions {
+            synonyms_column: Some("synonyms".into()),
+            id_column: Some("id".into()),
+            url_column: Some(3.into()),
+            ..Default::default()
+        };
+        let thesaurus =
+            read_csv_thesaurus("devops".to_string(), data.as_bytes(), &options).unwrap();
+        assert_eq!(thesaurus.name(), "devops");
+        assert_eq!(thesaurus.len(), 5);
+        let k8s = thesaurus.get(&"k8s".into()).unwrap();
+        assert_eq!(k8s.id, 1);
+        assert_eq!(k8s.value.as_str(), "kubernetes");
+        assert_eq!(k8s.url.as_deref(), Some("https://kubernetes.io"));
+        assert_eq!(thesaurus.get(&"kubernetes cluster".into()).unwrap().id, 1);
+        assert_eq!(thesaurus.get(&"helm".into()).unwrap().url, None);
+    }
+
+    #[test]
+    fn test_read_tsv_thesaurus_without_ids() {
+        let data = "helm\tcharts\nkafka\tevent streaming\thelm\n";
+        let options = CsvOptions {
+            has_headers: false,
+            term_column: 0.into(),
+            synonyms_column: Some(1.into()),
+            ..CsvOptions::tsv()
+        };
+        let thesaurus = read_csv_thesaurus("tsv".to_string(), data.as_bytes(), &options).unwrap();
+        assert_eq!(thesaurus.get(&"charts".into()).unwrap().id, 1);
+        assert_eq!(thesaurus.get(&"event streaming".into()).unwrap().id, 2);
+    }
+
+    #[test]
+    fn test_invalid_rows() {
+        let data = "\
+id,term,synonyms
+1,kubernetes,k8s
+x,helm,
+2,,
+3,kafka,k8s
+";
+        let options = CsvOptions {
+            synonyms_column: Some("synonyms".into()),
+            id_column: Some("id".into()),
+            ..Default::default()
+        };
+        let Err(TerraphimAutomataError::InvalidRows(errors)) =
+            read_csv_thesaurus("invalid".to_string(), data.as_bytes(), &options)
+        else {
+            panic!("invalid rows weren't reported");
+        };
+        let lines: Vec<u64> = errors.iter().map(|error| error.line).collect();
+        assert_eq!(lines, [3, 4, 5]);
+        assert_eq!(errors[0].message, "invalid concept ID `x`");
+        assert_eq!(
+            errors[2].message,
+            "synonym `k8s` already belongs to concept `kubernetes`"
+        );
+
+        let options = CsvOptions {
+            skip_invalid_rows: true,
+            ..options
+        };
+        let thesaurus =
+            read_csv_thesaurus("invalid".to_string(), data.as_bytes(), &options).unwrap();
+        assert_eq!(thesaurus.len(), 2);
+
+        let options = CsvOptions {
+            term_column: "concept".into(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            read_csv_thesaurus("invalid".to_string(), data.as_bytes(), &options),
+            Err(TerraphimAutomataError::InvalidThesaurus(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_csv_thesaurus() {
+        let thesaurus = load_csv_thesaurus("data/output.csv", &CsvOptions::default()).unwrap();
+        assert_eq!(thesaurus.name(), "output");
+        assert!(thesaurus.get(&"project blueprint".into()).is_some());
+
+        let options = CsvOptions {
+            has_headers: false,
+            term_column: 0.into(),
+            ..Default::default()
+        };
+        let thesaurus = load_csv_thesaurus("data/output.csv.gz", &options).unwrap();
+        assert_eq!(thesaurus.name(), "output");
+        assert!(thesaurus.get(&"project outline".into()).is_some());
+    }
+}
@@ -1,18 +1,25 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{path::PathBuf, sync::Arc, time::Duration};
 
-use terraphim_automata::{load_thesaurus, AutomataPath};
+use terraphim_automata::{
+    load_automata, Automata, AutomataPath, FuzzyDistance, MatchMode, NormalizationOptions,
+    RemoteCacheOptions,
+};
 use terraphim_persistence::Persistable;
-use terraphim_rolegraph::{RoleGraph, RoleGraphSync};
+use terraphim_rolegraph::{CoOccurrenceWindow, RoleGraph, RoleGraphSnapshot, RoleGraphSync};
 use terraphim_types::{
-    Document, IndexedDocument, KnowledgeGraphInputType, RelevanceFunction, RoleName, SearchQuery,
+    Correction, Document, Explanation, IndexedDocument, KnowledgeGraphInputType, RelevanceFunction,
+    RoleName, SearchQuery,
 };
 
 use ahash::AHashMap;
 use async_trait::async_trait;
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use thiserror::Error;
 use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
 
 pub type Result<T> = std::result::Result<T, TerraphimConfigError>;
 
@@ -71,6 +78,138 @@ pub struct Role {
     pub extra: AHashMap<String, Value>,
 }
 
+/// Key in `Role.extra` holding the weights of the hybrid relevance function
+pub const HYBRID_WEIGHTS_KEY: &str = "hybrid_weights";
+
+/// Key in `Role.extra` holding the normalization of the knowledge graph
+///
+/// e.g. `"normalization": { "strip_diacritics": true }`
+pub const NORMALIZATION_KEY: &str = "normalization";
+
+/// Key in `Role.extra` holding the match mode of the knowledge graph
+///
+/// e.g. `"match_mode": "word_boundary"`
+pub const MATCH_MODE_KEY: &str = "match_mode";
+
+/// Key in `Role.extra` holding the distance for correcting misspelled queries
+///
+/// e.g. `"fuzzy": { "metric": "levenshtein", "max_edits": 2 }` or
+/// `"fuzzy": { "metric": "jaro_winkler", "min_similarity": 0.9 }`
+pub const FUZZY_KEY: &str = "fuzzy";
+
+/// Key in `Role.extra` holding whether queries are expanded to narrower
+/// concepts of the knowledge graph
+///
+/// e.g. `"query_expansion": true`
+pub const QUERY_EXPANSION_KEY: &str = "query_expansion";
+
+/// Key in `Role.extra` holding where concepts of a document co-occur
+///
+/// e.g. `"co_occurrence_window": "sentence"` or
+/// `"co_occurrence_window": { "tokens": 20 }`
+pub const CO_OCCURRENCE_WINDOW_KEY: &str = "co_occurrence_window";
+
+/// Key in `Role.extra` holding how a remote knowledge graph is cached
+///
+/// e.g. `"remote_cache": { "ttl_secs": 600, "offline_fallback": true }`
+pub const REMOTE_CACHE_KEY: &str = "remote_cache";
+
+/// Weights of the components of the hybrid relevance function
+///
+/// They are configured per role in `Role.extra`, e.g.
+/// `"hybrid_weights": { "graph": 0.7, "lexical": 0.3 }`
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(default)]
+pub struct HybridWeights {
+    /// Weight of the rank in the knowledge graph
+    pub graph: f64,
+    /// Weight of the lexical similarity of the title
+    pub lexical: f64,
+}
+
+impl Default for HybridWeights {
+    fn default() -> Self {
+        Self {
+            graph: 0.5,
+            lexical: 0.5,
+        }
+    }
+}
+
+impl Role {
+    /// Returns the setting stored under the key in `Role.extra`
+    ///
+    /// Returns `None` if the setting isn't configured or is invalid, in
+    /// which case a warning is logged.
+    pub fn extra_setting<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let value = self.extra.get(key)?;
+        match serde_json::from_value(value.clone()) {
+            Ok(value) => Some(value),
+            Err(e) => {
+                log::warn!("Invalid `{key}` setting for role `{}`: {e}", self.name);
+                None
+            }
+        }
+    }
+
+    /// Returns the weights for `RelevanceFunction::Hybrid`
+    ///
+    /// Falls back to the default weights if none or invalid weights are
+    /// configured.
+    pub fn hybrid_weights(&self) -> HybridWeights {
+        self.extra_setting(HYBRID_WEIGHTS_KEY).unwrap_or_default()
+    }
+
+    /// Normalization of the knowledge graph and of the text matched against it
+    ///
+    /// Falls back to the default normalization if none or an invalid one is
+    /// configured.
+    pub fn normalization(&self) -> NormalizationOptions {
+        self.extra_setting(NORMALIZATION_KEY).unwrap_or_default()
+    }
+
+    /// Which hits of the knowledge graph terms are accepted as matches
+    ///
+    /// Falls back to substring matching if none or an invalid mode is
+    /// configured.
+    pub fn match_mode(&self) -> MatchMode {
+        self.extra_setting(MATCH_MODE_KEY).unwrap_or_default()
+    }
+
+    /// Distance for correcting misspelled queries to terms of the knowledge
+    /// graph
+    ///
+    /// Correcting queries is disabled if no or an invalid distance is
+    /// configured.
+    pub fn fuzzy(&self) -> Option<FuzzyDistance> {
+        self.extra_setting(FUZZY_KEY)
+    }
+
+    /// Whether queries also match the narrower concepts of their concepts
+    ///
+    /// Expansion is disabled if it isn't or is invalidly configured.
+    pub fn query_expansion(&self) -> bool {
+        self.extra_setting(QUERY_EXPANSION_KEY).unwrap_or_default()
+    }
+
+    /// Where consecutive concepts of a document are connected in the
+    /// knowledge graph
+    ///
+    /// Falls back to the whole document if no or an invalid window is
+    /// configured.
+    pub fn co_occurrence_window(&self) -> CoOccurrenceWindow {
+        self.extra_setting(CO_OCCURRENCE_WINDOW_KEY).unwrap_or_default()
+    }
+
+    /// How the thesaurus of a remote knowledge graph is cached
+    ///
+    /// Falls back to the default caching if none or invalid options are
+    /// configured.
+    pub fn remote_cache(&self) -> RemoteCacheOptions {
+        self.extra_setting(REMOTE_CACHE_KEY).unwrap_or_default()
+    }
+}
+
 use anyhow::Context;
 /// The service used for indexing documents
 ///
@@ -80,6 +219,9 @@ use anyhow::Context;
 pub enum ServiceType {
     /// Use ripgrep as the indexing service
     Ripgrep,
+    /// Use the built-in indexer, which searches haystacks in-process and
+    /// doesn't require ripgrep to be installed
+    Native,
 }
 
 /// A haystack is a collection of documents that can be indexed and searched
@@ -116,6 +258,39 @@ impl KnowledgeGraph {
 pub struct KnowledgeGraphLocal {
     pub input_type: KnowledgeGraphInputType,
     pub path: PathBuf,
+    /// How synonyms are read from Markdown files
+    #[serde(default)]
+    pub synonyms: SynonymProperties,
+}
+
+/// The properties of a Markdown file which list synonyms of its concept
+///
+/// Logseq writes properties as `synonyms:: foo, bar`, Obsidian keeps
+/// `aliases` in the YAML frontmatter of a file.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(default)]
+pub struct SynonymProperties {
+    /// Separates the name of a property from its value
+    pub delimiter: String,
+    /// Properties with synonyms, e.g. `synonyms`, `alias` or `aka`
+    ///
+    /// Names are matched case-insensitively.
+    pub keys: Vec<String>,
+    /// Whether `aliases` in the YAML frontmatter are synonyms, too
+    ///
+    /// The `keys` and the relations to other concepts are read from the
+    /// frontmatter as well.
+    pub frontmatter_aliases: bool,
+}
+
+impl Default for SynonymProperties {
+    fn default() -> Self {
+        Self {
+            delimiter: "::".to_string(),
+            keys: vec!["synonyms".to_string()],
+            frontmatter_aliases: true,
+        }
+    }
 }
 /// Builder, which allows to create a new `Config`
 ///
@@ -206,6 +381,7 @@ impl ConfigBuilder {
                     knowledge_graph_local: Some(KnowledgeGraphLocal {
                         input_type: KnowledgeGraphInputType::Markdown,
                         path: system_operator_haystack.clone(),
+                        synonyms: SynonymProperties::default(),
                     }),
                     public: true,
                     publish: true,
@@ -229,6 +405,7 @@ impl ConfigBuilder {
                     knowledge_graph_local: Some(KnowledgeGraphLocal {
                         input_type: KnowledgeGraphInputType::Markdown,
                         path: system_operator_haystack.clone(),
+                        synonyms: SynonymProperties::default(),
                     }),
                     public: true,
                     publish: true,
@@ -300,6 +477,7 @@ impl ConfigBuilder {
                     knowledge_graph_local: Some(KnowledgeGraphLocal {
                         input_type: KnowledgeGraphInputType::Markdown,
                         path: docs_path.join("kg"),
+                        synonyms: SynonymProperties::default(),
                     }),
                     public: true,
                     publish: true,
@@ -323,6 +501,7 @@ impl ConfigBuilder {
                     knowledge_graph_local: Some(KnowledgeGraphLocal {
                         input_type: KnowledgeGraphInputType::Markdown,
                         path: PathBuf::from("/tmp/system_operator/pages/"),
+                        synonyms: SynonymProperties::default(),
                     }),
                     public: true,
                     publish: true,
@@ -476,6 +655,10 @@ impl Persistable for Config {
     }
 }
 
+/// How often changed rolegraphs are saved, see
+/// [`ConfigState::save_rolegraphs_periodically`]
+pub const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(30);
+
 /// ConfigState for the Terraphim (Actor)
 /// Config state can be updated using the API or Atomic Server
 ///
@@ -498,10 +681,14 @@ impl ConfigState {
         for (name, role) in &config.roles {
             let role_name = name.clone();
             log::info!("Creating role {}", role_name);
-            // FIXME: this looks like local KG is never re-build
+            // The local KG isn't re-built here, changes to it are picked up
+            // at runtime by `terraphim_middleware::watcher`
             // check if role have configured local KG or automata_path
             // skip role if incorrectly configured
-            if role.relevance_function == RelevanceFunction::TerraphimGraph {
+            if matches!(
+                role.relevance_function,
+                RelevanceFunction::TerraphimGraph | RelevanceFunction::Hybrid
+            ) {
                 if role.kg.as_ref().is_some_and(|kg| kg.is_set()) {
                     //FIXME: turn into errors
                     log::info!("Role {} is configured correctly", role_name);
@@ -514,8 +701,10 @@ impl ConfigState {
                         .unwrap()
                         .clone();
                     log::info!("Loading Role `{}` - URL: {:?}", role_name, automata_url);
-                    let thesaurus = load_thesaurus(&automata_url).await?;
-                    let rolegraph = RoleGraph::new(role_name.clone(), thesaurus).await?;
+                    let automata =
+                        load_automata(&automata_url, role.normalization(), &role.remote_cache())
+                            .await?;
+                    let rolegraph = load_rolegraph(role_name.clone(), automata, role).await?;
                     roles.insert(role_name.clone(), RoleGraphSync::from(rolegraph));
                 } else {
                     log::info!("Role {} is configured to use KG ranking but is missing remote url or local configuration", role_name );
@@ -546,12 +735,84 @@ impl ConfigState {
         let id = document.id.clone();
 
         for rolegraph_state in self.roles.values() {
-            let mut rolegraph = rolegraph_state.lock().await;
+            let mut rolegraph = rolegraph_state.lock_mut().await;
             rolegraph.insert_document(&id, document.clone());
         }
         Ok(())
     }
 
+    /// Replace a document in all rolegraphs
+    ///
+    /// In contrast to `add_to_roles`, the edges of the previous version of
+    /// the document are removed first.
+    pub async fn update_in_roles(&mut self, document: &Document) -> OpendalResult<()> {
+        let id = document.id.clone();
+
+        for rolegraph_state in self.roles.values() {
+            let mut rolegraph = rolegraph_state.lock_mut().await;
+            rolegraph.update_document(&id, document.clone());
+        }
+        Ok(())
+    }
+
+    /// Remove a document from all rolegraphs
+    ///
+    /// Returns `true` if the document was part of any rolegraph.
+    pub async fn remove_from_roles(&mut self, document_id: &str) -> bool {
+        let mut removed = false;
+        for rolegraph_state in self.roles.values() {
+            if rolegraph_state.lock().await.remove_document(document_id) {
+                rolegraph_state.mark_dirty();
+                removed = true;
+            }
+        }
+        removed
+    }
+
+    /// Save a snapshot of every rolegraph which changed since its last
+    /// snapshot to all persistence profiles
+    ///
+    /// The snapshots are picked up again by `ConfigState::new`, so that
+    /// indexed documents survive a restart.
+    pub async fn save_rolegraphs(&self) -> Result<()> {
+        for (role_name, rolegraph_state) in &self.roles {
+            let snapshot = {
+                let rolegraph = rolegraph_state.lock().await;
+                if !rolegraph_state.take_dirty() {
+                    continue;
+                }
+                rolegraph.to_snapshot()
+            };
+            log::debug!("Saving rolegraph snapshot for role `{}`", role_name);
+            if let Err(e) = snapshot.save().await {
+                rolegraph_state.mark_dirty();
+                return Err(e.into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Save the snapshots of changed rolegraphs in the background, see
+    /// [`ConfigState::save_rolegraphs`]
+    ///
+    /// Rolegraphs change with every search and document update, so instead
+    /// of saving them right away, they are saved at most once per
+    /// `interval`. The task runs until it is aborted, changes made since
+    /// the last save should be saved on shutdown.
+    pub fn save_rolegraphs_periodically(&self, interval: Duration) -> JoinHandle<()> {
+        let config_state = self.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                if let Err(e) = config_state.save_rolegraphs().await {
+                    log::warn!("Failed to save rolegraph snapshots: {e:?}");
+                }
+            }
+        })
+    }
+
     /// Search documents in rolegraph index using matching Knowledge Graph
     /// If knowledge graph isn't defined for the role, RoleGraph isn't build for the role
     pub async fn search_indexed_documents(
@@ -591,6 +852,96 @@ impl ConfigState {
 
         documents.into_iter().map(|(_id, doc)| doc).collect()
     }
+
+    /// Correct the misspelled words of the search term to concepts of the
+    /// rolegraph of the role
+    ///
+    /// Returns no corrections if the role has no rolegraph or fuzzy
+    /// matching is disabled for it.
+    pub async fn correct_search_term(
+        &self,
+        search_query: &SearchQuery,
+        role: &Role,
+    ) -> Vec<Correction> {
+        let Some(rolegraph) = self.roles.get(&role.name) else {
+            return Vec::new();
+        };
+        let rolegraph = rolegraph.lock().await;
+        rolegraph.correct(search_query.search_term.as_str())
+    }
+
+    /// Explain the ranking of the documents in the rolegraph of the role
+    ///
+    /// All matching documents are explained, regardless of `skip` and
+    /// `limit` of the search query.
+    pub async fn explain_indexed_documents(
+        &self,
+        search_query: &SearchQuery,
+        role: &Role,
+    ) -> Vec<Explanation> {
+        let Some(rolegraph) = self.roles.get(&role.name) else {
+            log::error!(
+                "Role `{}` does not exist or RoleGraph isn't populated",
+                role.name
+            );
+            return Vec::new();
+        };
+        let rolegraph = rolegraph.lock().await;
+        rolegraph
+            .explain(search_query.search_term.as_str(), None, None)
+            .unwrap_or_else(|e| {
+                log::error!("Error while explaining graph search: {:?}", e);
+                vec![]
+            })
+    }
+}
+
+/// Create the rolegraph for a role, restoring it from a persisted snapshot
+/// if one exists.
+///
+/// A snapshot is only used if it was built with the same thesaurus, as told
+/// by its content hash, and the same normalization, match mode and
+/// co-occurrence window, otherwise its nodes and edges can't be trusted and
+/// a fresh rolegraph is built. Either way the rolegraph shares the given
+/// automata.
+async fn load_rolegraph(role_name: RoleName, automata: Automata, role: &Role) -> Result<RoleGraph> {
+    let automata = Arc::new(automata);
+    let match_mode = role.match_mode();
+    let co_occurrence_window = role.co_occurrence_window();
+    let mut snapshot = RoleGraphSnapshot::new(role_name.clone());
+    match snapshot.load().await {
+        Ok(snapshot)
+            if snapshot.thesaurus_hash == automata.thesaurus_hash()
+                && snapshot.normalization == automata.normalization()
+                && snapshot.match_mode == match_mode
+                && snapshot.co_occurrence_window == co_occurrence_window =>
+        {
+            match RoleGraph::from_snapshot(snapshot, automata.clone()) {
+                Ok(mut rolegraph) => {
+                    log::info!("Restored rolegraph for role `{}` from snapshot", role_name);
+                    rolegraph.set_fuzzy(role.fuzzy());
+                    rolegraph.set_query_expansion(role.query_expansion());
+                    return Ok(rolegraph);
+                }
+                Err(e) => log::warn!(
+                    "Failed to restore rolegraph snapshot for role `{}`: {:?}",
+                    role_name,
+                    e
+                ),
+            }
+        }
+        Ok(_) => log::info!(
+            "Knowledge graph settings changed for role `{}`, discarding rolegraph snapshot",
+            role_name
+        ),
+        Err(e) => log::debug!("No rolegraph snapshot for role `{}`: {:?}", role_name, e),
+    }
+    let mut rolegraph = RoleGraph::from_automata(role_name, automata);
+    rolegraph.set_match_mode(match_mode);
+    rolegraph.set_co_occurrence_window(co_occurrence_window);
+    rolegraph.set_fuzzy(role.fuzzy());
+    rolegraph.set_query_expansion(role.query_expansion());
+    Ok(rolegraph)
 }
 
 #[cfg(test)]
@@ -693,6 +1044,7 @@ mod tests {
                         knowledge_graph_local: Some(KnowledgeGraphLocal {
                             input_type: KnowledgeGraphInputType::Markdown,
                             path: PathBuf::from("~/pkm"),
+                            synonyms: SynonymProperties::default(),
                         }),
                         public: true,
                         publish: true,
@@ -763,6 +1115,115 @@ mod tests {
         assert_eq!(config.roles[&RoleName::new("Father")], dummy_role());
     }
 
+    #[test]
+    async fn test_role_relevance_function() {
+        let mut role = serde_json::to_value(dummy_role()).unwrap();
+        role["relevance_function"] = "bm25".into();
+        let role: Role = serde_json::from_value(role).unwrap();
+        assert_eq!(role.relevance_function, RelevanceFunction::BM25);
+    }
+
+    /// A setting in `Role.extra` with a valid and an invalid value
+    struct ExtraSetting {
+        key: &'static str,
+        valid: Value,
+        invalid: Value,
+        /// Whether the typed accessor returns its fallback
+        is_fallback: fn(&Role) -> bool,
+        /// Whether the typed accessor returns the valid value
+        is_valid: fn(&Role) -> bool,
+    }
+
+    #[test]
+    async fn test_extra_settings() {
+        let settings = [
+            ExtraSetting {
+                key: HYBRID_WEIGHTS_KEY,
+                valid: serde_json::json!({ "graph": 0.8 }),
+                invalid: serde_json::json!("invalid"),
+                is_fallback: |role| role.hybrid_weights() == HybridWeights::default(),
+                is_valid: |role| {
+                    role.hybrid_weights()
+                        == HybridWeights {
+                            graph: 0.8,
+                            lexical: 0.5,
+                        }
+                },
+            },
+            ExtraSetting {
+                key: NORMALIZATION_KEY,
+                valid: serde_json::json!({ "strip_diacritics": true }),
+                invalid: serde_json::json!("nfkc"),
+                is_fallback: |role| role.normalization() == NormalizationOptions::default(),
+                is_valid: |role| {
+                    role.normalization()
+                        == NormalizationOptions {
+                            strip_diacritics: true,
+                            ..Default::default()
+                        }
+                },
+            },
+            ExtraSetting {
+                key: MATCH_MODE_KEY,
+                valid: serde_json::json!("word_boundary"),
+                invalid: serde_json::json!("fuzzy"),
+                is_fallback: |role| role.match_mode() == MatchMode::Substring,
+                is_valid: |role| role.match_mode() == MatchMode::WordBoundary,
+            },
+            ExtraSetting {
+                key: FUZZY_KEY,
+                valid: serde_json::json!({ "metric": "levenshtein", "max_edits": 2 }),
+                invalid: serde_json::json!({ "metric": "soundex" }),
+                is_fallback: |role| role.fuzzy().is_none(),
+                is_valid: |role| role.fuzzy() == Some(FuzzyDistance::Levenshtein { max_edits: 2 }),
+            },
+            ExtraSetting {
+                key: QUERY_EXPANSION_KEY,
+                valid: serde_json::json!(true),
+                invalid: serde_json::json!("narrower"),
+                is_fallback: |role| !role.query_expansion(),
+                is_valid: |role| role.query_expansion(),
+            },
+            ExtraSetting {
+                key: CO_OCCURRENCE_WINDOW_KEY,
+                valid: serde_json::json!({ "tokens": 20 }),
+                invalid: serde_json::json!("page"),
+                is_fallback: |role| role.co_occurrence_window() == CoOccurrenceWindow::Document,
+                is_valid: |role| role.co_occurrence_window() == CoOccurrenceWindow::Tokens(20),
+            },
+            ExtraSetting {
+                key: REMOTE_CACHE_KEY,
+                valid: serde_json::json!({ "ttl_secs": 600, "offline_fallback": false }),
+                invalid: serde_json::json!("forever"),
+                is_fallback: |role| role.remote_cache() == RemoteCacheOptions::default(),
+                is_valid: |role| {
+                    role.remote_cache()
+                        == RemoteCacheOptions {
+                            ttl_secs: 600,
+                            offline_fallback: false,
+                            ..Default::default()
+                        }
+                },
+            },
+        ];
+
+        for setting in settings {
+            let key = setting.key;
+            let mut role = dummy_role();
+            assert!((setting.is_fallback)(&role), "missing `{key}`");
+
+            role.extra.insert(key.to_string(), setting.valid);
+            assert!((setting.is_valid)(&role), "valid `{key}`");
+
+            role.extra.insert(key.to_string(), setting.invalid);
+            assert!((setting.is_fallback)(&role), "invalid `{key}`");
+            assert_eq!(
+                role.extra_setting::<Value>(key),
+                role.extra.get(key).cloned()
+            );
+        }
+    }
+
     ///test to create config with different id - server, desktop, embedded
     #[tokio::test]
     async fn test_config_with_id_desktop() {
@@ -1,13 +1,17 @@
+use ahash::AHashSet;
 use std::collections::hash_map::DefaultHasher;
 use std::hash::{Hash, Hasher};
 use std::path::Path;
 use terraphim_config::{ConfigState, ServiceType};
-use terraphim_types::{Index, SearchQuery};
+use terraphim_types::{Document, Index, Query, QueryError, SearchQuery};
 
 use crate::{Error, Result};
 
+mod native;
 mod ripgrep;
 
+pub(crate) use native::search_files;
+pub use native::NativeIndexer;
 pub use ripgrep::RipgrepIndexer;
 
 fn hash_as_string<T: Hash>(t: &T) -> String {
@@ -16,6 +20,25 @@ fn hash_as_string<T: Hash>(t: &T) -> String {
     format!("{:x}", s.finish())
 }
 
+/// Builds a `Document` for a haystack file
+///
+/// This uses the same scheme as the ripgrep indexer, so that document IDs
+/// are stable across all indexers.
+pub(crate) fn document_from_path(path: &Path, body: String) -> Document {
+    let url = path.to_string_lossy().to_string();
+    let title = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Document {
+        id: hash_as_string(&url),
+        url,
+        title,
+        body,
+        ..Default::default()
+    }
+}
+
 /// A Middleware is a service that creates an index of documents from
 /// a haystack.
 ///
@@ -42,10 +65,38 @@ pub async fn search_haystacks(
     search_query: SearchQuery,
 ) -> Result<Index> {
     let config = config_state.config.lock().await.clone();
-    let search_query_role = search_query.role.unwrap_or(config.default_role);
-    let needle = search_query.search_term.as_str();
+    let search_query_role = search_query.role.clone().unwrap_or(config.default_role);
+    // An empty search term matches every document
+    let query = match search_query.query() {
+        Ok(query) => Some(query),
+        Err(QueryError::Empty) => None,
+        Err(e) => return Err(e.into()),
+    };
+    // The synonyms of the concepts are looked up in the automata of the role
+    let automata = match config_state.roles.get(&search_query_role) {
+        Some(rolegraph) => Some(rolegraph.lock().await.automata.clone()),
+        None => None,
+    };
+    let synonyms = |id: u64| {
+        automata
+            .as_ref()
+            .map(|automata| {
+                automata
+                    .synonyms(id)
+                    .into_iter()
+                    .map(str::to_string)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let needle = query
+        .as_ref()
+        .map(|query| query_needle(query, &synonyms))
+        .unwrap_or_default();
+    let needle = needle.as_str();
 
     let ripgrep = RipgrepIndexer::default();
+    let native = NativeIndexer::default();
     let mut full_index = Index::new();
 
     let role = config
@@ -62,7 +113,18 @@ pub async fn search_haystacks(
                 // This indexes the haystack using the ripgrep middleware
                 ripgrep.index(needle, &haystack.path).await?
             }
+            ServiceType::Native => {
+                // Search through documents in-process, without shelling out
+                // to ripgrep
+                native.index(needle, &haystack.path).await?
+            }
         };
+        let mut index = index;
+        if let Some(query) = &query {
+            // The haystack search only finds documents containing any of the
+            // terms, so `AND` and `NOT` are applied here
+            index.retain(|_, document| query.matches_text(&document.body, &synonyms));
+        }
 
         for indexed_doc in index.values() {
             if let Err(e) = config_state.add_to_roles(indexed_doc).await {
@@ -76,5 +138,45 @@ pub async fn search_haystacks(
 
         full_index.extend(index);
     }
+
     Ok(full_index)
 }
+
+/// Builds the needle for searching a haystack
+///
+/// Haystacks are searched for any of the terms, phrases and concepts of the
+/// query which are not negated. The documents found have to be filtered by
+/// the whole query afterwards.
+fn query_needle<F>(query: &Query, synonyms: &F) -> String
+where
+    F: Fn(u64) -> Vec<String>,
+{
+    let mut seen = AHashSet::new();
+    let patterns: Vec<String> = query
+        .positive_leaves()
+        .into_iter()
+        .flat_map(|leaf| match leaf {
+            Query::Term(text) | Query::Phrase(text) => vec![text.clone()],
+            Query::Concept(id) => synonyms(*id),
+            _ => Vec::new(),
+        })
+        .filter(|text| seen.insert(text.clone()))
+        .map(|text| regex::escape(&text))
+        .collect();
+    patterns.join("|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_query_needle() {
+        let synonyms = |id| match id {
+            3 => vec!["zk".to_string(), "zoo keeper".to_string()],
+            _ => Vec::new(),
+        };
+        let query = Query::parse("c++ AND NOT zookeeper OR #3").unwrap();
+        assert_eq!(query_needle(&query, &synonyms), r"c\+\+|zk|zoo keeper");
+    }
+}
@@ -0,0 +1,179 @@
+use grep_regex::RegexMatcherBuilder;
+use grep_searcher::{BinaryDetection, Searcher, SearcherBuilder, Sink, SinkContext, SinkMatch};
+use ignore::types::TypesBuilder;
+use ignore::WalkBuilder;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use terraphim_types::Index;
+
+use super::{document_from_path, IndexMiddleware};
+use crate::{Error, Result};
+
+/// Number of lines of context before and after each match
+/// (the equivalent of `rg -C3`)
+const CONTEXT_LINES: usize = 3;
+
+/// Middleware that indexes haystacks in-process, using the same building
+/// blocks as ripgrep (the `grep` and `ignore` crates).
+///
+/// In contrast to the [`RipgrepIndexer`](super::RipgrepIndexer), it doesn't
+/// need the `rg` binary to be installed. It produces the same `Index` of
+/// `Document`s, respects `.gitignore` (and `.ignore`) files and only
+/// searches files of the configured file types.
+#[derive(Debug, Clone)]
+pub struct NativeIndexer {
+    /// File types to search, as defined by ripgrep's default type
+    /// definitions (e.g. `markdown`)
+    file_types: Vec<String>,
+}
+
+impl Default for NativeIndexer {
+    fn default() -> Self {
+        Self {
+            file_types: vec!["markdown".to_string()],
+        }
+    }
+}
+
+impl NativeIndexer {
+    /// Only search files of the given types (e.g. `markdown`, `txt`)
+    pub fn with_file_types(mut self, file_types: Vec<String>) -> Self {
+        self.file_types = file_types;
+        self
+    }
+}
+
+impl IndexMiddleware for NativeIndexer {
+    /// Index the haystack in-process and return an index of documents
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the needle is not a valid regex, a file type is
+    /// unknown or the haystack can't be walked
+    async fn index(&self, needle: &str, haystack: &Path) -> Result<Index> {
+        let needle = needle.to_string();
+        let haystack = haystack.to_path_buf();
+        let file_types = self.file_types.clone();
+        tokio::task::spawn_blocking(move || index_haystack(&needle, &haystack, &file_types))
+            .await
+            .map_err(|e| Error::Indexation(format!("Native indexer task failed: {e}")))?
+    }
+}
+
+/// Walks the haystack and searches every file for the needle
+fn index_haystack(needle: &str, haystack: &Path, file_types: &[String]) -> Result<Index> {
+    let mut index = Index::new();
+    for (path, description) in search_files(needle, haystack, file_types)? {
+        let body = match fs::read_to_string(&path) {
+            Ok(body) => body,
+            Err(e) => {
+                log::warn!("Failed to read file {path:?}: {e}");
+                continue;
+            }
+        };
+        let mut document = document_from_path(&path, body);
+        document.description = Some(description);
+        index.insert(document.id.clone(), document);
+    }
+    Ok(index)
+}
+
+/// Walks the haystack and returns the files of the given types which match
+/// the needle, ignoring case
+///
+/// Every file comes with its matched lines and their context. Files are
+/// returned in the order of their paths.
+///
+/// # Errors
+///
+/// Returns an error if the needle is not a valid regex or a file type is
+/// unknown
+pub(crate) fn search_files(
+    needle: &str,
+    haystack: &Path,
+    file_types: &[String],
+) -> Result<Vec<(PathBuf, String)>> {
+    log::debug!("Searching haystack {haystack:?} for needle `{needle}`");
+    let matcher = RegexMatcherBuilder::new()
+        .case_insensitive(true)
+        .build(needle)
+        .map_err(|e| Error::Indexation(format!("Invalid needle `{needle}`: {e}")))?;
+
+    let mut types = TypesBuilder::new();
+    types.add_defaults();
+    for file_type in file_types {
+        types.select(file_type);
+    }
+    let types = types
+        .build()
+        .map_err(|e| Error::Indexation(format!("Invalid file type filter: {e}")))?;
+
+    let mut searcher = SearcherBuilder::new()
+        .before_context(CONTEXT_LINES)
+        .after_context(CONTEXT_LINES)
+        .binary_detection(BinaryDetection::quit(b'\x00'))
+        .build();
+
+    let mut files = Vec::new();
+    let walker = WalkBuilder::new(haystack)
+        .types(types)
+        .sort_by_file_path(Path::cmp)
+        .build();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                log::warn!("Failed to walk haystack {haystack:?}: {e}");
+                continue;
+            }
+        };
+        if !entry.file_type().is_some_and(|t| t.is_file()) {
+            continue;
+        }
+
+        let path = entry.into_path();
+        let mut sink = DocumentSink::default();
+        if let Err(e) = searcher.search_path(&matcher, &path, &mut sink) {
+            log::warn!("Failed to search {path:?}: {e}");
+            continue;
+        }
+        // Files without a match don't have a description
+        if let Some(description) = sink.description {
+            files.push((path, description));
+        }
+    }
+    Ok(files)
+}
+
+/// Collects the matched lines and their context into a description,
+/// like `rg --trim` does
+#[derive(Default)]
+struct DocumentSink {
+    description: Option<String>,
+}
+
+impl DocumentSink {
+    fn push_lines(&mut self, bytes: &[u8]) {
+        let lines = String::from_utf8_lossy(bytes);
+        let lines = lines.trim_start();
+        self.description = Some(match self.description.take() {
+            Some(description) => description + " " + lines,
+            None => lines.to_string(),
+        });
+    }
+}
+
+impl Sink for DocumentSink {
+    type Error = io::Error;
+
+    fn matched(&mut self, _searcher: &Searcher, mat: &SinkMatch<'_>) -> io::Result<bool> {
+        self.push_lines(mat.bytes());
+        Ok(true)
+    }
+
+    fn context(&mut self, _searcher: &Searcher, context: &SinkContext<'_>) -> io::Result<bool> {
+        self.push_lines(context.bytes());
+        Ok(true)
+    }
+}
@@ -4,6 +4,7 @@ use terraphim_config::TerraphimConfigError;
 mod command;
 pub mod indexer;
 pub mod thesaurus;
+pub mod watcher;
 
 pub use indexer::search_haystacks;
 
@@ -23,6 +24,15 @@ pub enum Error {
 
     #[error("Config error: {0}")]
     Config(#[from] TerraphimConfigError),
+
+    #[error("RoleGraph error: {0}")]
+    RoleGraph(#[from] terraphim_rolegraph::Error),
+
+    #[error("Watcher error: {0}")]
+    Watcher(#[from] notify::Error),
+
+    #[error("Invalid query: {0}")]
+    Query(#[from] terraphim_types::QueryError),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
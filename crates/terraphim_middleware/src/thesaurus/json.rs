@@ -0,0 +1,183 @@
+//! Knowledge graphs which were exported to JSON by other systems
+//!
+//! A JSON knowledge graph is a file, or a directory of `*.json` files, with a
+//! list of concepts and their synonyms:
+//!
+//! ```json
+//! {
+//!   "concepts": [
+//!     {
+//!       "term": "kubernetes",
+//!       "synonyms": ["k8s", "kube"],
+//!       "id": 42,
+//!       "url": "https://kubernetes.io"
+//!     },
+//!     { "term": "helm" }
+//!   ]
+//! }
+//! ```
+//!
+//! Only `term` is required. Concepts without an `id` get the next free ID
+//! after all explicit IDs, in the order of the files and of the concepts in
+//! them. A term listed in several files is the same concept, its synonyms
+//! are combined.
+//!
+//! JSON files without a top-level `concepts` key, such as thesauri written
+//! next to the knowledge graph, are skipped.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use ignore::WalkBuilder;
+use serde::Deserialize;
+use terraphim_types::{NormalizedTerm, NormalizedTermValue, Thesaurus};
+
+use super::ThesaurusBuilder;
+use crate::{Error, Result};
+
+/// A JSON knowledge graph, see the [module documentation](self) for the
+/// schema
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct JsonKnowledgeGraph {
+    pub concepts: Vec<JsonConcept>,
+}
+
+/// A concept of a [`JsonKnowledgeGraph`]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct JsonConcept {
+    pub term: String,
+    #[serde(default)]
+    pub synonyms: Vec<String>,
+    pub id: Option<u64>,
+    pub url: Option<String>,
+}
+
+/// A builder for a knowledge graph, which knows how to handle JSON input.
+#[derive(Debug, Default)]
+pub struct JsonKg;
+
+impl ThesaurusBuilder for JsonKg {
+    async fn build<P: Into<PathBuf> + Send>(&self, name: String, haystack: P) -> Result<Thesaurus> {
+        let haystack = haystack.into();
+        let mut graphs = Vec::new();
+        for path in json_files(&haystack)? {
+            let contents = tokio::fs::read_to_string(&path).await?;
+            if let Some(graph) = parse_knowledge_graph(&path, &contents)? {
+                graphs.push(graph);
+            }
+        }
+        Ok(index_concepts(name, graphs))
+    }
+}
+
+/// Parses a JSON knowledge graph, or returns `None` if the file isn't one
+fn parse_knowledge_graph(path: &Path, contents: &str) -> Result<Option<JsonKnowledgeGraph>> {
+    let invalid = |e: &dyn std::fmt::Display| {
+        Error::Indexation(format!("Invalid JSON knowledge graph {path:?}: {e}"))
+    };
+    let value: serde_json::Value = serde_json::from_str(contents).map_err(|e| invalid(&e))?;
+    if value.get("concepts").is_none() {
+        log::debug!("Skipping {path:?}, it has no concepts");
+        return Ok(None);
+    }
+    let graph = serde_json::from_value(value).map_err(|e| invalid(&e))?;
+    Ok(Some(graph))
+}
+
+/// The haystack itself if it's a file, otherwise all `*.json` files below
+/// it, sorted by path
+fn json_files(haystack: &Path) -> Result<Vec<PathBuf>> {
+    if haystack.is_file() {
+        return Ok(vec![haystack.to_path_buf()]);
+    }
+    if !haystack.is_dir() {
+        return Err(Error::Indexation(format!(
+            "JSON knowledge graph {haystack:?} doesn't exist"
+        )));
+    }
+    let mut files = Vec::new();
+    for entry in WalkBuilder::new(haystack).build() {
+        let entry = entry.map_err(|e| Error::Indexation(e.to_string()))?;
+        let path = entry.path();
+        if path.is_file() && path.extension().is_some_and(|ext| ext == "json") {
+            files.push(path.to_path_buf());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Creates the thesaurus from the concepts of all knowledge graphs
+///
+/// Terms always map to their own concept. If a synonym is listed for several
+/// concepts, the first concept wins.
+fn index_concepts(name: String, graphs: Vec<JsonKnowledgeGraph>) -> Thesaurus {
+    // Concepts with the same term are merged, in order of first mention
+    let mut merged: Vec<JsonConcept> = Vec::new();
+    let mut positions: HashMap<NormalizedTermValue, usize> = HashMap::new();
+    for concept in graphs.into_iter().flat_map(|graph| graph.concepts) {
+        let value = NormalizedTermValue::new(concept.term.clone());
+        if value.as_str().is_empty() {
+            log::warn!("Skipping concept without a term: {concept:?}");
+            continue;
+        }
+        match positions.get(&value) {
+            Some(&position) => {
+                let existing = &mut merged[position];
+                existing.synonyms.extend(concept.synonyms);
+                existing.id = existing.id.or(concept.id);
+                existing.url = existing.url.take().or(concept.url);
+            }
+            None => {
+                positions.insert(value, merged.len());
+                merged.push(concept);
+            }
+        }
+    }
+
+    // Explicit IDs take precedence, the other concepts are numbered after
+    // the highest of them
+    let mut next_id = merged
+        .iter()
+        .filter_map(|concept| concept.id)
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let nterms: Vec<NormalizedTerm> = merged
+        .iter()
+        .map(|concept| {
+            let id = concept.id.unwrap_or_else(|| {
+                next_id += 1;
+                next_id - 1
+            });
+            let nterm = NormalizedTerm::new(id, NormalizedTermValue::new(concept.term.clone()));
+            match &concept.url {
+                Some(url) => nterm.with_url(url.clone()),
+                None => nterm,
+            }
+        })
+        .collect();
+
+    let mut thesaurus = Thesaurus::new(name);
+    for nterm in &nterms {
+        thesaurus.insert(nterm.value.clone(), nterm.clone());
+    }
+    for (concept, nterm) in merged.iter().zip(&nterms) {
+        for synonym in &concept.synonyms {
+            let key = NormalizedTermValue::new(synonym.clone());
+            if key.as_str().is_empty() {
+                continue;
+            }
+            match thesaurus.get(&key) {
+                Some(existing) if existing.value != nterm.value => log::warn!(
+                    "Synonym `{key}` of `{}` already maps to `{}`. Skipping",
+                    nterm.value,
+                    existing.value
+                ),
+                Some(_) => {}
+                None => thesaurus.insert(key, nterm.clone()),
+            }
+        }
+    }
+    thesaurus
+}
@@ -24,27 +24,32 @@
 //! ```
 //! The logic as follows: if you ask for concept by name you get concept, if you ask (get) for any of the synonyms you will get concept with id,
 //! its pre-computed reverse tree traversal - any of the synonyms (leaf) maps into the concepts (root)
+//!
+//! Knowledge graphs exported to JSON are handled by [`JsonKg`], which of the
+//! builders is used depends on the `input_type` of the local knowledge graph
+//! of the role.
 
 use terraphim_automata::AutomataPath;
 use terraphim_config::ConfigState;
 use terraphim_config::Role;
+use terraphim_config::{KnowledgeGraphLocal, SynonymProperties};
 use terraphim_persistence::Persistable;
 use terraphim_rolegraph::{Error as RoleGraphError, RoleGraph, RoleGraphSync};
-use terraphim_types::SearchQuery;
-use terraphim_types::{Concept, NormalizedTerm, RoleName, Thesaurus};
+use terraphim_types::{Concept, NormalizedTerm, NormalizedTermValue, RoleName, Thesaurus};
+use terraphim_types::{KnowledgeGraphInputType, RelationKind, SearchQuery};
 
 use crate::Result;
 use cached::proc_macro::cached;
-use std::collections::HashSet;
 use std::path::Path;
 use std::path::PathBuf;
-use std::process::Stdio;
-use tokio::io::AsyncReadExt;
-use tokio::process::Command;
 
-use crate::command::ripgrep::{json_decode, Data, Message};
+use crate::indexer::search_files;
 use crate::Error;
 
+mod json;
+
+pub use json::{JsonConcept, JsonKg, JsonKnowledgeGraph};
+
 pub async fn build_thesaurus_from_haystack(
     config_state: &mut ConfigState,
     search_query: &SearchQuery,
@@ -62,13 +67,30 @@ pub async fn build_thesaurus_from_haystack(
         .unwrap_or(&roles[&default_role])
         .to_owned();
     println!("Role: {:?}", role);
+    let kg_local: Option<KnowledgeGraphLocal> = role
+        .kg
+        .as_ref()
+        .and_then(|kg| kg.knowledge_graph_local.clone());
+    let input_type = kg_local
+        .as_ref()
+        .map_or(KnowledgeGraphInputType::Markdown, |kg_local| {
+            kg_local.input_type.clone()
+        });
+    let synonyms = kg_local
+        .map(|kg_local| kg_local.synonyms)
+        .unwrap_or_default();
     for haystack in &role.haystacks {
         log::debug!("Updating thesaurus for haystack: {:?}", haystack);
 
-        let logseq = Logseq::default();
-        let thesaurus: Thesaurus = logseq
-            .build(role_name.as_lowercase().to_string(), &haystack.path)
-            .await?;
+        let name = role_name.as_lowercase().to_string();
+        let thesaurus: Thesaurus = match input_type {
+            KnowledgeGraphInputType::Markdown => {
+                Logseq::new(synonyms.clone())
+                    .build(name, &haystack.path)
+                    .await?
+            }
+            KnowledgeGraphInputType::Json => JsonKg.build(name, &haystack.path).await?,
+        };
         match thesaurus.save().await {
             Ok(_) => {
                 log::debug!("Thesaurus saved");
@@ -89,7 +111,8 @@ pub async fn build_thesaurus_from_haystack(
         role.kg.as_mut().unwrap().automata_path = Some(AutomataPath::Local(thesaurus_path));
         log::info!("Make sure thesaurus updated in a role {}", role_name);
         println!("Make sure thesaurus updated in a role {}", role_name);
-        // TODO: may be re-building all thesaurus on change using inotify is easier
+        // Subsequent changes to the files are picked up incrementally by
+        // `crate::watcher`
 
         update_thesaurus(config_state, &role_name, thesaurus).await?;
     }
@@ -134,87 +157,73 @@ pub trait ThesaurusBuilder {
     ) -> impl std::future::Future<Output = Result<Thesaurus>> + Send;
 }
 
-/// In Logseq, `::` serves as a delimiter between the property name and its
-/// value, e.g.
+/// The key of the YAML frontmatter with synonyms, as used by Obsidian
+const FRONTMATTER_ALIASES_KEY: &str = "aliases";
+
+/// Properties relating the concept of a file to other concepts, like the
+/// relations of SKOS, e.g. `broader:: [[database]]`
+const RELATION_KEYS: [(&str, RelationKind); 4] = [
+    ("broader", RelationKind::Broader),
+    ("parent", RelationKind::Broader),
+    ("narrower", RelationKind::Narrower),
+    ("related", RelationKind::Related),
+];
+
+/// A builder for a knowledge graph, which knows how to handle Logseq input.
+///
+/// Which properties hold the synonyms of a concept, and how properties are
+/// written, is configured with [`SynonymProperties`]. In Logseq, `::` serves
+/// as a delimiter between the property name and its value, e.g.
 ///
 /// ```markdown
 /// title:: My Note
 /// tags:: #idea #project
 /// ```
-// FIXME: move to config item per role
-const LOGSEQ_KEY_VALUE_DELIMITER: &str = "::";
-
-/// The synonyms keyword used in Logseq documents
-// FIXME: move to config item per role
-const LOGSEQ_SYNONYMS_KEYWORD: &str = "synonyms";
-
-/// A builder for a knowledge graph, which knows how to handle Logseq input.
 #[derive(Default)]
 pub struct Logseq {
-    service: LogseqService,
+    synonyms: SynonymProperties,
 }
 
-impl ThesaurusBuilder for Logseq {
-    /// Build the knowledge graph from the data source
-    /// and store it in each rolegraph.
-    async fn build<P: Into<PathBuf> + Send>(&self, name: String, haystack: P) -> Result<Thesaurus> {
-        let haystack = haystack.into();
-        let messages = self
-            .service
-            .get_raw_messages(LOGSEQ_KEY_VALUE_DELIMITER, &haystack)
-            .await?;
-
-        let thesaurus = index_inner(name, messages);
-        Ok(thesaurus)
+impl Logseq {
+    /// Returns a builder reading the synonyms from the given properties
+    pub fn new(synonyms: SynonymProperties) -> Self {
+        Self { synonyms }
     }
-}
-
-pub struct LogseqService {
-    command: String,
-    default_args: Vec<String>,
-}
 
-/// Returns a new ripgrep service with default arguments
-impl Default for LogseqService {
-    fn default() -> Self {
-        Self {
-            command: "rg".to_string(),
-            default_args: ["--json", "--trim", "--ignore-case", "-tmarkdown"]
-                .into_iter()
-                .map(String::from)
-                .collect(),
+    /// The pattern to find the files which may define synonyms
+    fn needle(&self) -> String {
+        let needle = regex::escape(&self.synonyms.delimiter);
+        if self.synonyms.frontmatter_aliases {
+            format!("{needle}|^---\\s*$")
+        } else {
+            needle
         }
     }
 }
 
-impl LogseqService {
-    /// Run ripgrep with the given needle and haystack
+impl ThesaurusBuilder for Logseq {
+    /// Build the knowledge graph from the data source
+    /// and store it in each rolegraph.
     ///
-    /// Returns a Vec of Messages, which correspond to ripgrep's internal
-    /// JSON output. Learn more about ripgrep's JSON output here:
-    /// https://docs.rs/grep-printer/0.2.1/grep_printer/struct.JSON.html
-    pub async fn get_raw_messages(&self, needle: &str, haystack: &Path) -> Result<Vec<Message>> {
-        let haystack = haystack.to_string_lossy().to_string();
-        log::debug!("Running logseq with needle `{needle}` and haystack `{haystack}`");
-
-        // Merge the default arguments with the needle and haystack
-        let args: Vec<String> = vec![needle.to_string(), haystack]
-            .into_iter()
-            .chain(self.default_args.clone())
-            .collect();
-
-        let mut child = Command::new(&self.command)
-            .args(args)
-            .stdout(Stdio::piped())
-            .spawn()?;
-
-        let mut stdout = child.stdout.take().expect("Stdout is not available");
-        let read = async move {
-            let mut data = String::new();
-            stdout.read_to_string(&mut data).await.map(|_| data)
-        };
-        let output = read.await?;
-        json_decode(&output)
+    /// The Markdown files of the haystack are searched in-process, the
+    /// same way as by the [`NativeIndexer`](crate::indexer::NativeIndexer).
+    async fn build<P: Into<PathBuf> + Send>(&self, name: String, haystack: P) -> Result<Thesaurus> {
+        let haystack = haystack.into();
+        let needle = self.needle();
+        let file_types = vec!["markdown".to_string()];
+        let files =
+            tokio::task::spawn_blocking(move || search_files(&needle, &haystack, &file_types))
+                .await
+                .map_err(|e| Error::Indexation(format!("Logseq search task failed: {e}")))??;
+
+        let mut documents = Vec::new();
+        for (path, _) in files {
+            let contents = tokio::fs::read_to_string(&path).await?;
+            documents.push((path, contents));
+        }
+
+        let thesaurus = index_inner(name, documents, self.synonyms.clone());
+        Ok(thesaurus)
     }
 }
 
@@ -246,98 +255,293 @@ impl LogseqService {
 /// }
 /// ```
 ///
+/// Relations like `broader:: database` are resolved once all documents are
+/// indexed. Related concepts without a document of their own are added to
+/// the thesaurus, so that they can be searched for.
+///
 // This is a free-standing function because it's a requirement for caching the
 // results
 #[cached]
-fn index_inner(name: String, messages: Vec<Message>) -> Thesaurus {
+fn index_inner(
+    name: String,
+    documents: Vec<(PathBuf, String)>,
+    synonyms: SynonymProperties,
+) -> Thesaurus {
     let mut thesaurus = Thesaurus::new(name);
-    let mut current_concept: Option<Concept> = None;
-
-    let mut existing_paths: HashSet<PathBuf> = HashSet::new();
-
-    for message in messages {
-        match message {
-            Message::Begin(message) => {
-                let Some(path_str) = message.path() else {
-                    continue;
-                };
-                let path = PathBuf::from(&path_str);
-
-                if existing_paths.contains(&path) {
-                    // Already processed this input
-                    continue;
-                }
-                existing_paths.insert(path.clone());
-
-                // Use the path as the concept
-                let concept = match concept_from_path(path) {
-                    Ok(concept) => concept,
-                    Err(e) => {
-                        log::info!("Failed to get concept from path: {:?}. Skipping", e);
-                        continue;
-                    }
-                };
-                log::trace!("Found concept: {concept}");
-                current_concept = Some(concept);
+    let mut relations: Vec<(u64, Vec<(RelationKind, String)>)> = Vec::new();
+
+    for (path, contents) in documents {
+        // Use the path as the concept
+        let concept = match concept_from_path(path) {
+            Ok(concept) => concept,
+            Err(e) => {
+                log::info!("Failed to get concept from path: {:?}. Skipping", e);
+                continue;
             }
-            Message::Match(message) => {
-                if message.path().is_none() {
-                    continue;
-                };
-
-                // let body = match fs::read_to_string(path) {
-                //     Ok(body) => body,
-                //     Err(e) => {
-                //         println!("Error: Failed to read file: {:?}. Skipping", e);
-                //         continue;
-                //     }
-                // };
-
-                let lines = match &message.lines {
-                    Data::Text { text } => text,
-                    _ => {
-                        println!("Error: lines is not text: {:?}", message.lines);
-                        continue;
-                    }
-                };
-
-                // Split text by delimiter (`::`)
-                // If the key is `synonyms`, then the value is a comma-separated
-                // list of synonyms, which we can use to build the thesaurus
-                let Some((synonym_keyword, synonym)) = lines.split_once(LOGSEQ_KEY_VALUE_DELIMITER)
-                else {
-                    println!("Error: Expected key-value pair, got {}. Skipping", lines);
-                    continue;
-                };
-
-                if synonym_keyword != LOGSEQ_SYNONYMS_KEYWORD {
-                    // Not a synonym, skip
-                    continue;
-                }
-
-                let synonyms: Vec<String> =
-                    synonym.split(',').map(|s| s.trim().to_string()).collect();
-
-                let concept = match current_concept {
-                    Some(ref concept) => {
-                        let nterm = NormalizedTerm::new(concept.id, concept.value.clone());
-                        thesaurus.insert(concept.value.clone(), nterm);
-                        concept
-                    }
-                    None => {
-                        println!("Error: No concept found. Skipping");
-                        continue;
-                    }
-                };
-                for synonym in synonyms {
-                    let nterm = NormalizedTerm::new(concept.id, concept.value.clone());
-                    thesaurus.insert(synonym.into(), nterm);
-                }
+        };
+        log::trace!("Found concept: {concept}");
+
+        let concept_relations = parse_relations(&contents, &synonyms);
+        let concept_synonyms = match parse_synonyms(&contents, &synonyms) {
+            Some(concept_synonyms) => concept_synonyms,
+            None if !concept_relations.is_empty() => Vec::new(),
+            // Not a concept with synonyms or relations, skip
+            None => continue,
+        };
+        let nterm = NormalizedTerm::new(concept.id, concept.value.clone());
+        thesaurus.insert(concept.value.clone(), nterm.clone());
+        for synonym in concept_synonyms {
+            thesaurus.insert(synonym.into(), nterm.clone());
+        }
+        relations.push((concept.id, concept_relations));
+    }
+
+    for (id, concept_relations) in relations {
+        add_relations(&mut thesaurus, id, concept_relations);
+    }
+    thesaurus
+}
+
+/// Declares the relations of a concept to the concepts with the given names
+///
+/// Concepts which aren't part of the thesaurus yet are added.
+fn add_relations(thesaurus: &mut Thesaurus, id: u64, relations: Vec<(RelationKind, String)>) {
+    for (kind, name) in relations {
+        let value = NormalizedTermValue::new(name);
+        let related_id = match concept_id(thesaurus, &value) {
+            Some(related_id) => related_id,
+            None => {
+                let related_id = next_concept_id(thesaurus);
+                log::debug!("Adding concept `{value}` related to concept {id}");
+                thesaurus.insert(value.clone(), NormalizedTerm::new(related_id, value));
+                related_id
             }
-            _ => {}
         };
+        thesaurus.add_relation(id, kind, related_id);
     }
+}
+
+/// Updates the entries of a single concept from a Logseq file
+///
+/// Only the concept defined by the file at `path` is touched: its existing
+/// entries and the relations declared by the file are dropped and rebuilt
+/// from the properties of the file, keeping the ID of the concept stable. If
+/// the file was deleted, the concept is removed from the thesaurus.
+pub fn update_concept_from_file(
+    thesaurus: &mut Thesaurus,
+    path: &Path,
+    synonyms: &SynonymProperties,
+) -> Result<()> {
+    let stem = path
+        .file_stem()
+        .ok_or(Error::Indexation(format!("No file stem in path {path:?}")))?;
+    let concept_value = NormalizedTermValue::new(stem.to_string_lossy().to_string());
+
+    let existing_id = concept_id(thesaurus, &concept_value);
+    thesaurus.retain(|_, nterm| nterm.value != concept_value);
+    if let Some(id) = existing_id {
+        thesaurus.retain_relations(|relation| relation.from != id);
+    }
+
+    if !path.exists() {
+        log::debug!("Removed concept `{concept_value}` from thesaurus");
+        return Ok(());
+    }
+
+    let contents = std::fs::read_to_string(path)?;
+    let relations = parse_relations(&contents, synonyms);
+    let synonyms = match parse_synonyms(&contents, synonyms) {
+        Some(synonyms) => synonyms,
+        None if !relations.is_empty() => Vec::new(),
+        // Same as for a full build: concepts without synonyms or relations
+        // are skipped
+        None => return Ok(()),
+    };
+    // Avoid clashing with the IDs of the existing concepts
+    let id = existing_id.unwrap_or_else(|| next_concept_id(thesaurus));
+    let nterm = NormalizedTerm::new(id, concept_value.clone());
+    thesaurus.insert(concept_value.clone(), nterm.clone());
+    for synonym in synonyms {
+        thesaurus.insert(synonym.into(), nterm.clone());
+    }
+    add_relations(thesaurus, id, relations);
+    log::debug!("Updated concept `{concept_value}` in thesaurus");
+    Ok(())
+}
+
+/// Looks up the ID of a concept by its normalized value
+fn concept_id(thesaurus: &Thesaurus, concept_value: &NormalizedTermValue) -> Option<u64> {
+    thesaurus
+        .get(concept_value)
+        .or_else(|| {
+            thesaurus
+                .into_iter()
+                .map(|(_, nterm)| nterm)
+                .find(|nterm| &nterm.value == concept_value)
+        })
+        .map(|nterm| nterm.id)
+}
+
+/// Returns an ID which isn't used by any concept in the thesaurus yet
+fn next_concept_id(thesaurus: &Thesaurus) -> u64 {
     thesaurus
+        .into_iter()
+        .map(|(_, nterm)| nterm.id)
+        .max()
+        .unwrap_or(0)
+        + 1
+}
+
+/// Collects the synonyms from all synonym properties of a document
+///
+/// Properties are read from the lines of the document, e.g.
+/// `synonyms:: foo, bar`, and from its YAML frontmatter, e.g.
+///
+/// ```markdown
+/// ---
+/// aliases: [foo, bar]
+/// synonyms:
+///   - baz
+/// ---
+/// ```
+///
+/// Returns `None` if the document has no synonym property.
+fn parse_synonyms(contents: &str, properties: &SynonymProperties) -> Option<Vec<String>> {
+    property_values(contents, properties, |key, in_frontmatter| {
+        properties
+            .keys
+            .iter()
+            .any(|synonym_key| synonym_key.eq_ignore_ascii_case(key))
+            || (in_frontmatter && key.eq_ignore_ascii_case(FRONTMATTER_ALIASES_KEY))
+    })
+}
+
+/// Collects the names of the concepts a document relates its concept to,
+/// see [`RELATION_KEYS`]
+fn parse_relations(contents: &str, properties: &SynonymProperties) -> Vec<(RelationKind, String)> {
+    RELATION_KEYS
+        .iter()
+        .flat_map(|(relation_key, kind)| {
+            property_values(contents, properties, |key, _| {
+                key.eq_ignore_ascii_case(relation_key)
+            })
+            .unwrap_or_default()
+            .into_iter()
+            .map(|name| (*kind, name))
+        })
+        .collect()
+}
+
+/// Collects the values of all matching properties of a document
+///
+/// `is_key` gets the trimmed name of a property and whether it is part of
+/// the YAML frontmatter, which is only read if enabled. Returns `None` if no
+/// property matches.
+fn property_values(
+    contents: &str,
+    properties: &SynonymProperties,
+    is_key: impl Fn(&str, bool) -> bool,
+) -> Option<Vec<String>> {
+    let mut found = false;
+    let mut values = Vec::new();
+    let mut body = contents;
+    if properties.frontmatter_aliases {
+        if let Some((frontmatter, rest)) = split_frontmatter(contents) {
+            body = rest;
+            if let Some(frontmatter_values) =
+                parse_frontmatter(frontmatter, |key| is_key(key.trim(), true))
+            {
+                found = true;
+                values.extend(frontmatter_values);
+            }
+        }
+    }
+    for line in body.lines() {
+        let Some((key, value)) = line.trim_start().split_once(properties.delimiter.as_str()) else {
+            continue;
+        };
+        if !is_key(key.trim(), false) {
+            continue;
+        }
+        found = true;
+        values.extend(split_values(value));
+    }
+    found.then_some(values)
+}
+
+/// Splits a document into its YAML frontmatter and the rest of it
+///
+/// Returns `None` if the document doesn't start with a frontmatter.
+fn split_frontmatter(contents: &str) -> Option<(&str, &str)> {
+    let rest = contents.strip_prefix("---")?;
+    let rest = rest
+        .strip_prefix("\r\n")
+        .or_else(|| rest.strip_prefix('\n'))?;
+    let mut position = 0;
+    for line in rest.split_inclusive('\n') {
+        let end = position + line.len();
+        if matches!(line.trim_end(), "---" | "...") {
+            return Some((&rest[..position], &rest[end..]));
+        }
+        position = end;
+    }
+    None
+}
+
+/// Collects the values of the matching keys of a YAML frontmatter
+///
+/// Only flat keys are supported, with a list (`[foo, bar]`), a
+/// comma-separated string or a block sequence (`- foo` on the following
+/// lines) as value. Returns `None` if no key matches.
+fn parse_frontmatter(frontmatter: &str, is_key: impl Fn(&str) -> bool) -> Option<Vec<String>> {
+    let mut found = false;
+    let mut values = Vec::new();
+    let mut in_sequence = false;
+    for line in frontmatter.lines() {
+        if in_sequence {
+            if let Some(item) = line.trim_start().strip_prefix('-') {
+                values.extend(split_values(item));
+                continue;
+            }
+            if line.starts_with([' ', '\t']) || line.trim().is_empty() {
+                continue;
+            }
+            in_sequence = false;
+        }
+        if line.starts_with([' ', '\t']) {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        if !is_key(key) {
+            continue;
+        }
+        found = true;
+        let value = value.trim();
+        match value.strip_prefix('[').and_then(|v| v.strip_suffix(']')) {
+            Some(list) => values.extend(split_values(list)),
+            None if value.is_empty() => in_sequence = true,
+            None => values.extend(split_values(value)),
+        }
+    }
+    found.then_some(values)
+}
+
+/// Splits a comma-separated list of values, dropping quotes and the
+/// brackets of wiki links
+fn split_values(values: &str) -> impl Iterator<Item = String> + '_ {
+    values
+        .split(',')
+        .map(|value| {
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            let value = value
+                .strip_prefix("[[")
+                .and_then(|v| v.strip_suffix("]]"))
+                .unwrap_or(value);
+            value.trim().to_string()
+        })
+        .filter(|value| !value.is_empty())
 }
 
 /// Uses the file stem as the concept name
@@ -348,3 +552,133 @@ fn concept_from_path(path: PathBuf) -> Result<Concept> {
     let concept_str = stem.to_string_lossy().to_string();
     Ok(Concept::from(concept_str))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn properties(keys: &[&str]) -> SynonymProperties {
+        SynonymProperties {
+            keys: keys.iter().map(|key| key.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_synonym_properties() {
+        let contents = "title:: Terraform\nAlias:: [[tf]], [[Terraform CLI]]\naka:: hcl,\n";
+        assert_eq!(
+            parse_synonyms(contents, &properties(&["alias", "aka"])),
+            Some(vec![
+                "tf".to_string(),
+                "Terraform CLI".to_string(),
+                "hcl".to_string()
+            ])
+        );
+        assert_eq!(parse_synonyms(contents, &properties(&["synonyms"])), None);
+
+        let custom = SynonymProperties {
+            delimiter: "=".to_string(),
+            ..properties(&["synonyms"])
+        };
+        assert_eq!(
+            parse_synonyms("synonyms = foo, bar", &custom),
+            Some(vec!["foo".to_string(), "bar".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_parse_frontmatter_aliases() {
+        let contents = "\
+---
+title: Kubernetes
+aliases: [k8s, \"kube\"]
+tags:
+  - containers
+synonyms:
+  - container orchestration
+---
+synonyms:: kubectl
+";
+        assert_eq!(
+            parse_synonyms(contents, &properties(&["synonyms"])),
+            Some(vec![
+                "k8s".to_string(),
+                "kube".to_string(),
+                "container orchestration".to_string(),
+                "kubectl".to_string()
+            ])
+        );
+
+        let without_frontmatter = SynonymProperties {
+            frontmatter_aliases: false,
+            ..properties(&["synonyms"])
+        };
+        assert_eq!(
+            parse_synonyms(contents, &without_frontmatter),
+            Some(vec!["kubectl".to_string()])
+        );
+
+        // A horizontal rule isn't a frontmatter
+        let contents = "Some notes\n\n---\naliases: foo\n---\n";
+        assert_eq!(parse_synonyms(contents, &properties(&["synonyms"])), None);
+    }
+
+    #[test]
+    fn test_concept_relations() {
+        let documents = vec![
+            (
+                PathBuf::from("postgres.md"),
+                "synonyms:: pg\nbroader:: [[database]]\nnarrower:: pgvector".to_string(),
+            ),
+            (
+                PathBuf::from("database.md"),
+                "---\naliases: [db]\nrelated: storage\n---\n".to_string(),
+            ),
+            (PathBuf::from("sqlite.md"), "parent:: DB".to_string()),
+        ];
+        let thesaurus = index_inner(
+            "test_concept_relations".to_string(),
+            documents,
+            SynonymProperties::default(),
+        );
+        let id = |term: &str| {
+            thesaurus
+                .get(&NormalizedTermValue::new(term.to_string()))
+                .unwrap()
+                .id
+        };
+        // `pgvector` and `storage` don't have a document, but are added
+        assert_eq!(thesaurus.len(), 7);
+        assert_eq!(
+            thesaurus.narrower_transitive(id("db")),
+            [id("postgres"), id("pgvector"), id("sqlite")]
+                .into_iter()
+                .collect()
+        );
+        assert_eq!(
+            thesaurus.related_concepts(id("storage"), RelationKind::Related),
+            [id("database")].into_iter().collect()
+        );
+
+        // Updating a file replaces only the relations it declares
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("postgres.md");
+        std::fs::write(&path, "related:: sqlite").unwrap();
+        let mut updated = thesaurus.clone();
+        update_concept_from_file(&mut updated, &path, &SynonymProperties::default()).unwrap();
+        assert_eq!(updated.get(&"pg".into()), None);
+        assert_eq!(
+            updated.narrower_transitive(id("database")),
+            [id("sqlite")].into_iter().collect()
+        );
+        assert_eq!(
+            updated.related_concepts(id("sqlite"), RelationKind::Related),
+            [id("postgres")].into_iter().collect()
+        );
+        assert_eq!(
+            updated.related_concepts(id("database"), RelationKind::Related),
+            [id("storage")].into_iter().collect()
+        );
+    }
+}
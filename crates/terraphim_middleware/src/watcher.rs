@@ -0,0 +1,327 @@
+//! Incremental reindexing of haystacks and local knowledge graphs.
+//!
+//! The watcher listens for filesystem changes in every `Haystack.path` and
+//! `KnowledgeGraphLocal.path` of the config. Instead of rebuilding
+//! everything on change, it only touches what is affected:
+//!
+//! * a changed haystack document replaces its previous version in the
+//!   rolegraphs, a removed one is dropped from them
+//! * a changed Markdown knowledge graph file only updates the thesaurus
+//!   entries of the concept it defines
+//! * a changed JSON knowledge graph file rebuilds the thesaurus from the
+//!   knowledge graph, as its concepts can span several files
+//!
+//! Rolegraphs are updated in place through their `RoleGraphSync`, so every
+//! clone of the `ConfigState` sees the changes without a restart.
+
+use ignore::types::{Types, TypesBuilder};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use terraphim_config::{ConfigState, SynonymProperties};
+use terraphim_persistence::Persistable;
+use terraphim_types::{KnowledgeGraphInputType, RoleName};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+use crate::indexer::document_from_path;
+use crate::thesaurus::{update_concept_from_file, JsonKg, ThesaurusBuilder};
+use crate::{Error, Result};
+
+/// Time to wait for more events after the first change
+///
+/// Editors usually emit a burst of events for a single save.
+const DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// A path watched for changes
+#[derive(Debug, Clone)]
+enum Target {
+    /// A haystack, with the path as configured (used for document IDs) and
+    /// the canonical path (used to match filesystem events)
+    Haystack { path: PathBuf, root: PathBuf },
+    /// The local knowledge graph of a role, with its format and the
+    /// properties to read synonyms from
+    KnowledgeGraph {
+        role: RoleName,
+        root: PathBuf,
+        input_type: KnowledgeGraphInputType,
+        synonyms: SynonymProperties,
+    },
+}
+
+impl Target {
+    fn root(&self) -> &Path {
+        match self {
+            Target::Haystack { root, .. } => root,
+            Target::KnowledgeGraph { root, .. } => root,
+        }
+    }
+
+    /// The format of the files of the target, haystacks are always Markdown
+    fn input_type(&self) -> &KnowledgeGraphInputType {
+        match self {
+            Target::Haystack { .. } => &KnowledgeGraphInputType::Markdown,
+            Target::KnowledgeGraph { input_type, .. } => input_type,
+        }
+    }
+}
+
+/// Handle to the background watcher
+///
+/// Watching stops when the handle is dropped.
+#[derive(Debug)]
+pub struct IndexWatcher {
+    _watcher: RecommendedWatcher,
+    task: JoinHandle<()>,
+}
+
+impl Drop for IndexWatcher {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// Start watching all haystacks and local knowledge graphs of the config
+///
+/// Paths which don't exist are skipped with a warning.
+///
+/// # Errors
+///
+/// Returns an error if the filesystem watcher can't be created
+pub async fn watch(config_state: ConfigState) -> Result<IndexWatcher> {
+    let targets = collect_targets(&config_state).await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel::<PathBuf>();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+        match event {
+            Ok(event) => {
+                if matches!(
+                    event.kind,
+                    EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                ) {
+                    for path in event.paths {
+                        // The receiver is gone once the watcher is dropped
+                        let _ = tx.send(path);
+                    }
+                }
+            }
+            Err(e) => log::warn!("Filesystem watcher error: {e:?}"),
+        }
+    })?;
+
+    let mut roots: Vec<&Path> = targets.iter().map(Target::root).collect();
+    roots.sort();
+    roots.dedup();
+    for root in &roots {
+        // Nested roots are already covered by the recursive parent watch
+        if roots
+            .iter()
+            .any(|other| other != root && root.starts_with(other))
+        {
+            continue;
+        }
+        log::info!("Watching {root:?} for changes");
+        watcher.watch(root, RecursiveMode::Recursive)?;
+    }
+
+    let markdown_types = file_types("markdown")?;
+    let json_types = file_types("json")?;
+    let task = tokio::spawn(async move {
+        let mut config_state = config_state;
+        while let Some(path) = rx.recv().await {
+            let mut changed = HashSet::from([path]);
+            tokio::time::sleep(DEBOUNCE).await;
+            while let Ok(path) = rx.try_recv() {
+                changed.insert(path);
+            }
+
+            for path in changed {
+                for target in &targets {
+                    let types = match target.input_type() {
+                        KnowledgeGraphInputType::Markdown => &markdown_types,
+                        KnowledgeGraphInputType::Json => &json_types,
+                    };
+                    if !types.matched(&path, false).is_whitelist() {
+                        continue;
+                    }
+                    if let Err(e) = handle_change(&mut config_state, target, &path).await {
+                        log::error!("Failed to reindex {path:?}: {e:?}");
+                    }
+                }
+            }
+        }
+    });
+
+    Ok(IndexWatcher {
+        _watcher: watcher,
+        task,
+    })
+}
+
+/// Collects the haystacks and local knowledge graphs from the config
+async fn collect_targets(config_state: &ConfigState) -> Vec<Target> {
+    let config = config_state.config.lock().await.clone();
+    let mut targets = Vec::new();
+    let mut haystack_roots = HashSet::new();
+
+    for (role_name, role) in &config.roles {
+        for haystack in &role.haystacks {
+            let Some(root) = canonical_root(&haystack.path) else {
+                continue;
+            };
+            // Documents get inserted into all rolegraphs, so every
+            // haystack only needs to be watched once
+            if haystack_roots.insert(root.clone()) {
+                targets.push(Target::Haystack {
+                    path: haystack.path.clone(),
+                    root,
+                });
+            }
+        }
+
+        // Only roles with a rolegraph have a thesaurus to update
+        if !config_state.roles.contains_key(role_name) {
+            continue;
+        }
+        let kg_local = role
+            .kg
+            .as_ref()
+            .and_then(|kg| kg.knowledge_graph_local.as_ref());
+        if let Some(kg_local) = kg_local {
+            if let Some(root) = canonical_root(&kg_local.path) {
+                targets.push(Target::KnowledgeGraph {
+                    role: role_name.clone(),
+                    root,
+                    input_type: kg_local.input_type.clone(),
+                    synonyms: kg_local.synonyms.clone(),
+                });
+            }
+        }
+    }
+    targets
+}
+
+fn canonical_root(path: &Path) -> Option<PathBuf> {
+    match path.canonicalize() {
+        Ok(root) => Some(root),
+        Err(e) => {
+            log::warn!("Not watching {path:?}: {e}");
+            None
+        }
+    }
+}
+
+/// Filter for the files of the given type definition (e.g. `markdown`)
+fn file_types(name: &str) -> Result<Types> {
+    let mut types = TypesBuilder::new();
+    types.add_defaults();
+    types.select(name);
+    types
+        .build()
+        .map_err(|e| Error::Indexation(format!("Invalid file type filter: {e}")))
+}
+
+async fn handle_change(config_state: &mut ConfigState, target: &Target, path: &Path) -> Result<()> {
+    match target {
+        Target::Haystack {
+            path: haystack_path,
+            root,
+        } => {
+            let Ok(relative_path) = path.strip_prefix(root) else {
+                return Ok(());
+            };
+            reindex_document(config_state, &haystack_path.join(relative_path), path).await
+        }
+        Target::KnowledgeGraph {
+            role,
+            root,
+            input_type,
+            synonyms,
+        } => {
+            if !path.starts_with(root) {
+                return Ok(());
+            }
+            match input_type {
+                KnowledgeGraphInputType::Markdown => {
+                    update_thesaurus(config_state, role, path, synonyms).await
+                }
+                KnowledgeGraphInputType::Json => rebuild_thesaurus(config_state, role, root).await,
+            }
+        }
+    }
+}
+
+/// Re-inserts a changed haystack document into the rolegraphs, or removes
+/// it if the file is gone
+///
+/// `document_path` is the path relative to the configured haystack, so
+/// that the document ID matches the one assigned during indexing.
+async fn reindex_document(
+    config_state: &mut ConfigState,
+    document_path: &Path,
+    path: &Path,
+) -> Result<()> {
+    if !path.exists() {
+        let document = document_from_path(document_path, String::new());
+        if config_state.remove_from_roles(&document.id).await {
+            log::info!("Removed document {document_path:?} from rolegraphs");
+        }
+        return Ok(());
+    }
+    let body = tokio::fs::read_to_string(path).await?;
+    let document = document_from_path(document_path, body);
+    log::debug!("Reindexing document {document_path:?}");
+    config_state
+        .update_in_roles(&document)
+        .await
+        .map_err(|e| Error::Indexation(format!("Failed to reindex {document_path:?}: {e}")))?;
+    Ok(())
+}
+
+/// Updates the thesaurus entries of the concept defined by a knowledge
+/// graph file and rebuilds the automata of the role
+async fn update_thesaurus(
+    config_state: &ConfigState,
+    role: &RoleName,
+    path: &Path,
+    synonyms: &SynonymProperties,
+) -> Result<()> {
+    let Some(rolegraph_state) = config_state.roles.get(role) else {
+        return Ok(());
+    };
+    let mut rolegraph = rolegraph_state.lock_mut().await;
+    let mut thesaurus = rolegraph.automata.thesaurus();
+    update_concept_from_file(&mut thesaurus, path, synonyms)?;
+    rolegraph.update_thesaurus(thesaurus.clone())?;
+    drop(rolegraph);
+    log::info!("Updated thesaurus of role `{role}` from {path:?}");
+
+    if let Err(e) = thesaurus.save().await {
+        log::warn!("Failed to save thesaurus: {e:?}");
+    }
+    Ok(())
+}
+
+/// Rebuilds the thesaurus of a role from its JSON knowledge graph and
+/// rebuilds the automata of the role
+///
+/// Concepts of a JSON knowledge graph can span several files and get IDs
+/// in the order of all files, so the whole knowledge graph is read again.
+async fn rebuild_thesaurus(config_state: &ConfigState, role: &RoleName, root: &Path) -> Result<()> {
+    let Some(rolegraph_state) = config_state.roles.get(role) else {
+        return Ok(());
+    };
+    let name = rolegraph_state.lock().await.automata.name().to_string();
+    let thesaurus = JsonKg.build(name, root).await?;
+    rolegraph_state
+        .lock_mut()
+        .await
+        .update_thesaurus(thesaurus.clone())?;
+    log::info!("Rebuilt thesaurus of role `{role}` from {root:?}");
+
+    if let Err(e) = thesaurus.save().await {
+        log::warn!("Failed to save thesaurus: {e:?}");
+    }
+    Ok(())
+}
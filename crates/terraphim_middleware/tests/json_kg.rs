@@ -0,0 +1,68 @@
+#[cfg(test)]
+mod tests {
+
+    use terraphim_middleware::thesaurus::{JsonKg, ThesaurusBuilder};
+
+    use terraphim_middleware::{Error, Result};
+    use terraphim_types::NormalizedTermValue;
+
+    #[tokio::test]
+    /// Test creating a thesaurus from a directory of JSON knowledge graphs
+    /// Uses `fixtures/json_kg` as the haystack
+    async fn test_json_thesaurus() -> Result<()> {
+        let thesaurus = JsonKg
+            .build("some_role".to_string(), "fixtures/json_kg")
+            .await?;
+        let get = |term: &str| {
+            thesaurus
+                .get(&NormalizedTermValue::new(term.to_string()))
+                .unwrap()
+        };
+
+        // The thesaurus written next to the knowledge graph is skipped
+        assert_eq!(thesaurus.len(), 9);
+        assert_eq!(get("k8s").id, 42);
+        assert_eq!(
+            get("k8s").value,
+            NormalizedTermValue::new("kubernetes".to_string())
+        );
+        assert_eq!(get("k8s").url.as_deref(), Some("https://kubernetes.io"));
+        // Synonyms from several files are combined
+        assert_eq!(get("container orchestration").id, 42);
+        // Concepts without an ID are numbered after the explicit IDs
+        assert_eq!(get("helm chart").id, 43);
+        assert_eq!(get("o11y").id, 44);
+        // The first concept keeps a contested synonym
+        assert_eq!(
+            get("kube").value,
+            NormalizedTermValue::new("kubernetes".to_string())
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_json_thesaurus_from_file() -> Result<()> {
+        let thesaurus = JsonKg
+            .build("some_role".to_string(), "fixtures/json_kg/devops.json")
+            .await?;
+        assert_eq!(thesaurus.len(), 5);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_invalid_json_thesaurus() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(
+            dir.path().join("broken.json"),
+            r#"{"concepts": [{"synonyms": ["no term"]}]}"#,
+        )
+        .unwrap();
+        let result = JsonKg.build("some_role".to_string(), dir.path()).await;
+        assert!(matches!(result, Err(Error::Indexation(_))));
+
+        let result = JsonKg
+            .build("some_role".to_string(), dir.path().join("missing"))
+            .await;
+        assert!(matches!(result, Err(Error::Indexation(_))));
+    }
+}
@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use ahash::AHashMap;
+    use terraphim_config::{ConfigBuilder, ConfigState, Haystack, Role, ServiceType};
+    use terraphim_middleware::indexer::{IndexMiddleware, NativeIndexer};
+    use terraphim_middleware::{search_haystacks, Result};
+    use terraphim_types::{RelevanceFunction, SearchQuery};
+
+    #[tokio::test]
+    /// Test indexing a haystack in-process, without ripgrep
+    async fn test_native_index() -> Result<()> {
+        let haystack = tempfile::tempdir()?;
+        // `.gitignore` files are only respected inside of a git repository
+        fs::create_dir(haystack.path().join(".git"))?;
+        fs::write(haystack.path().join(".gitignore"), "ignored.md\n")?;
+        fs::write(
+            haystack.path().join("kafka.md"),
+            "# Kafka\n\nApache Kafka is a distributed event store.\n",
+        )?;
+        fs::write(
+            haystack.path().join("ignored.md"),
+            "Kafka is mentioned here, but the file is ignored.\n",
+        )?;
+        fs::write(
+            haystack.path().join("notes.txt"),
+            "Kafka in a plain text file is not a Markdown document.\n",
+        )?;
+        fs::write(haystack.path().join("other.md"), "Nothing to see here.\n")?;
+
+        let index = NativeIndexer::default()
+            .index("kafka", haystack.path())
+            .await?;
+
+        assert_eq!(index.len(), 1);
+        let document = index.values().next().unwrap();
+        assert_eq!(document.title, "kafka");
+        assert!(document.url.ends_with("kafka.md"));
+        assert!(document.body.contains("distributed event store"));
+        let description = document.description.as_ref().unwrap();
+        assert!(description.starts_with("# Kafka"));
+        assert!(description.contains("Apache Kafka is a distributed event store."));
+
+        let index = NativeIndexer::default()
+            .with_file_types(vec!["markdown".to_string(), "txt".to_string()])
+            .index("kafka", haystack.path())
+            .await?;
+        assert_eq!(index.len(), 2);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    /// Test that boolean queries are applied to the documents found in a
+    /// haystack
+    async fn test_boolean_search() -> Result<()> {
+        let haystack = tempfile::tempdir()?;
+        fs::write(
+            haystack.path().join("kafka.md"),
+            "Kafka is an event store.\n",
+        )?;
+        fs::write(
+            haystack.path().join("zookeeper.md"),
+            "Kafka used to depend on Zookeeper.\n",
+        )?;
+        fs::write(
+            haystack.path().join("cpp.md"),
+            "Lambdas came to C++ (or rather, to C++)\n",
+        )?;
+
+        let role = Role {
+            shortname: Some("native".into()),
+            name: "Native".into(),
+            relevance_function: RelevanceFunction::TitleScorer,
+            theme: "lumen".to_string(),
+            kg: None,
+            haystacks: vec![Haystack {
+                path: haystack.path().to_path_buf(),
+                service: ServiceType::Native,
+            }],
+            extra: AHashMap::new(),
+        };
+        let mut config = ConfigBuilder::new()
+            .add_role("Native", role)
+            .default_role("Native")?
+            .build()?;
+        let config_state = ConfigState::new(&mut config).await?;
+
+        // Deserialized search terms keep the case of the operators
+        let search_query: SearchQuery =
+            serde_json::from_str(r#"{"search_term": "kafka AND NOT zookeeper"}"#)?;
+        let index = search_haystacks(config_state.clone(), search_query).await?;
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.values().next().unwrap().title, "kafka");
+
+        let search_query: SearchQuery =
+            serde_json::from_str(r#"{"search_term": "kafka OR zookeeper"}"#)?;
+        let index = search_haystacks(config_state.clone(), search_query).await?;
+        assert_eq!(index.len(), 2);
+
+        // Search terms which aren't well-formed queries are searched for
+        // as they are
+        let search_query: SearchQuery = serde_json::from_str(r#"{"search_term": "c++)"}"#)?;
+        let index = search_haystacks(config_state, search_query).await?;
+        assert_eq!(index.len(), 1);
+        assert_eq!(index.values().next().unwrap().title, "cpp");
+
+        Ok(())
+    }
+}
@@ -6,7 +6,7 @@ mod tests {
     use terraphim_automata::AutomataPath;
     use terraphim_config::{
         ConfigBuilder, ConfigState, Haystack, KnowledgeGraph, KnowledgeGraphLocal, Role,
-        ServiceType,
+        ServiceType, SynonymProperties,
     };
     use terraphim_middleware::search_haystacks;
     use terraphim_types::{IndexedDocument, KnowledgeGraphInputType, RelevanceFunction};
@@ -39,6 +39,7 @@ mod tests {
                 knowledge_graph_local: Some(KnowledgeGraphLocal {
                     input_type: KnowledgeGraphInputType::Markdown,
                     path: docs_path.join("kg"),
+                    synonyms: SynonymProperties::default(),
                 }),
             }),
             haystacks: vec![Haystack {
@@ -87,6 +88,7 @@ mod tests {
                 knowledge_graph_local: Some(KnowledgeGraphLocal {
                     input_type: KnowledgeGraphInputType::Markdown,
                     path: PathBuf::from("/tmp/system_operator/pages/"),
+                    synonyms: SynonymProperties::default(),
                 }),
                 public: true,
                 publish: true,
@@ -0,0 +1,52 @@
+#[cfg(test)]
+mod synonym_properties {
+
+    use terraphim_config::SynonymProperties;
+    use terraphim_middleware::thesaurus::{Logseq, ThesaurusBuilder};
+
+    use terraphim_middleware::Result;
+    use terraphim_types::NormalizedTermValue;
+
+    #[tokio::test]
+    /// Test reading synonyms from configured properties and from the YAML
+    /// frontmatter, as used by Obsidian
+    /// Uses `fixtures/obsidian` as the haystack
+    async fn test_configured_synonym_keys() -> Result<()> {
+        let logseq = Logseq::new(SynonymProperties {
+            keys: vec!["alias".to_string(), "aka".to_string()],
+            ..Default::default()
+        });
+        let thesaurus = logseq
+            .build("some_role".to_string(), "fixtures/obsidian")
+            .await?;
+        let concept = |term: &str| {
+            thesaurus
+                .get(&NormalizedTermValue::new(term.to_string()))
+                .map(|nterm| nterm.value.to_string())
+        };
+
+        assert_eq!(thesaurus.len(), 10);
+        assert_eq!(concept("k8s").as_deref(), Some("kubernetes"));
+        assert_eq!(concept("kube").as_deref(), Some("kubernetes"));
+        assert_eq!(concept("helm package").as_deref(), Some("helm"));
+        assert_eq!(concept("charts").as_deref(), Some("helm"));
+        assert_eq!(concept("terraform cli").as_deref(), Some("terraform"));
+        assert_eq!(concept("containers"), None);
+        assert_eq!(concept("notes"), None);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_custom_delimiter_without_frontmatter() -> Result<()> {
+        let logseq = Logseq::new(SynonymProperties {
+            delimiter: "=".to_string(),
+            keys: vec!["aka".to_string()],
+            frontmatter_aliases: false,
+        });
+        let thesaurus = logseq
+            .build("some_role".to_string(), "fixtures/obsidian")
+            .await?;
+        assert_eq!(thesaurus.len(), 0);
+        Ok(())
+    }
+}
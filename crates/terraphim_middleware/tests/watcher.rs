@@ -0,0 +1,233 @@
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+    use std::time::Duration;
+
+    use ahash::AHashMap;
+    use terraphim_automata::AutomataPath;
+    use terraphim_config::{
+        ConfigBuilder, ConfigState, Haystack, KnowledgeGraph, KnowledgeGraphLocal, Role,
+        ServiceType, SynonymProperties,
+    };
+    use terraphim_middleware::watcher;
+    use terraphim_middleware::Result;
+    use terraphim_types::{
+        KnowledgeGraphInputType, NormalizedTerm, NormalizedTermValue, RelevanceFunction, RoleName,
+        Thesaurus,
+    };
+
+    fn write_thesaurus(path: &Path) {
+        let mut thesaurus = Thesaurus::new("watcher".to_string());
+        let kafka = NormalizedTerm::new(1, "kafka".into());
+        let zookeeper = NormalizedTerm::new(2, "zookeeper".into());
+        thesaurus.insert("kafka".into(), kafka.clone());
+        thesaurus.insert("zookeeper".into(), zookeeper);
+        fs::write(path, serde_json::to_string(&thesaurus).unwrap()).unwrap();
+    }
+
+    /// Polls `check` until it returns true or the timeout is reached
+    async fn eventually<F, Fut>(mut check: F) -> bool
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = bool>,
+    {
+        for _ in 0..50 {
+            if check().await {
+                return true;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        false
+    }
+
+    #[tokio::test]
+    /// Test that changes to haystacks and the local knowledge graph are
+    /// picked up without rebuilding the config state
+    async fn test_watch_haystack_and_kg() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let haystack = dir.path().join("haystack");
+        let kg = dir.path().join("kg");
+        fs::create_dir_all(&haystack)?;
+        fs::create_dir_all(&kg)?;
+        let thesaurus_path = dir.path().join("thesaurus.json");
+        write_thesaurus(&thesaurus_path);
+
+        let role_name = RoleName::new("Watcher");
+        let role = Role {
+            shortname: Some("watcher".into()),
+            name: role_name.clone(),
+            relevance_function: RelevanceFunction::TerraphimGraph,
+            theme: "lumen".to_string(),
+            kg: Some(KnowledgeGraph {
+                automata_path: Some(AutomataPath::from_local(&thesaurus_path)),
+                knowledge_graph_local: Some(KnowledgeGraphLocal {
+                    input_type: KnowledgeGraphInputType::Markdown,
+                    path: kg.clone(),
+                    synonyms: SynonymProperties::default(),
+                }),
+                public: false,
+                publish: false,
+            }),
+            haystacks: vec![Haystack {
+                path: haystack.clone(),
+                service: ServiceType::Native,
+            }],
+            extra: AHashMap::new(),
+        };
+        let mut config = ConfigBuilder::new().add_role("Watcher", role).build()?;
+        let config_state = ConfigState::new(&mut config).await?;
+        let rolegraph = config_state.roles.get(&role_name).unwrap().clone();
+
+        let _watcher = watcher::watch(config_state.clone()).await?;
+
+        // A new haystack document shows up in the rolegraph
+        fs::write(
+            haystack.join("streaming.md"),
+            "Kafka depends on zookeeper for coordination",
+        )?;
+        let indexed = eventually(|| async {
+            rolegraph
+                .lock()
+                .await
+                .query_graph("kafka", None, None)
+                .is_ok_and(|results| results.len() == 1)
+        })
+        .await;
+        assert!(indexed, "haystack document was not indexed");
+        let edges = rolegraph.lock().await.to_snapshot().edges;
+
+        // Saving the document again doesn't count its edges twice
+        fs::write(
+            haystack.join("streaming.md"),
+            "Kafka depends on zookeeper for coordination.",
+        )?;
+        tokio::time::sleep(Duration::from_millis(1000)).await;
+        assert_eq!(rolegraph.lock().await.to_snapshot().edges, edges);
+
+        // A removed haystack document is dropped from the rolegraph
+        fs::remove_file(haystack.join("streaming.md"))?;
+        let removed =
+            eventually(|| async { rolegraph.lock().await.to_snapshot().edges.is_empty() }).await;
+        assert!(removed, "haystack document was not removed");
+
+        // A new concept in the knowledge graph only adds its own entries
+        fs::write(kg.join("postgres.md"), "synonyms:: postgresql, pg\n")?;
+        let updated = eventually(|| async {
+            let rolegraph = rolegraph.lock().await;
+            rolegraph
+                .automata
+                .get(&NormalizedTermValue::from("postgresql"))
+                .is_some()
+        })
+        .await;
+        assert!(updated, "thesaurus was not updated");
+        {
+            let rolegraph = rolegraph.lock().await;
+            assert_eq!(rolegraph.automata.len(), 5);
+            let postgres = rolegraph
+                .automata
+                .get(&NormalizedTermValue::from("pg"))
+                .unwrap();
+            assert_eq!(postgres.value, NormalizedTermValue::from("postgres"));
+            assert_eq!(postgres.id, 3);
+            assert_eq!(
+                rolegraph
+                    .automata
+                    .get(&NormalizedTermValue::from("kafka"))
+                    .unwrap()
+                    .id,
+                1
+            );
+            assert_eq!(rolegraph.find_matching_node_ids("We run PostgreSQL"), [3]);
+        }
+
+        // Removing the concept file drops the concept again
+        fs::remove_file(kg.join("postgres.md"))?;
+        let removed = eventually(|| async { rolegraph.lock().await.automata.len() == 2 }).await;
+        assert!(removed, "concept was not removed from thesaurus");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    /// Test that changes to a JSON knowledge graph rebuild the thesaurus,
+    /// while other files in it are ignored
+    async fn test_watch_json_kg() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let haystack = dir.path().join("haystack");
+        let kg = dir.path().join("kg");
+        fs::create_dir_all(&haystack)?;
+        fs::create_dir_all(&kg)?;
+        let thesaurus_path = dir.path().join("thesaurus.json");
+        write_thesaurus(&thesaurus_path);
+        fs::write(
+            kg.join("streaming.json"),
+            r#"{"concepts": [{"term": "kafka", "id": 1}, {"term": "zookeeper", "id": 2}]}"#,
+        )?;
+
+        let role_name = RoleName::new("JSON Watcher");
+        let role = Role {
+            shortname: Some("json-watcher".into()),
+            name: role_name.clone(),
+            relevance_function: RelevanceFunction::TerraphimGraph,
+            theme: "lumen".to_string(),
+            kg: Some(KnowledgeGraph {
+                automata_path: Some(AutomataPath::from_local(&thesaurus_path)),
+                knowledge_graph_local: Some(KnowledgeGraphLocal {
+                    input_type: KnowledgeGraphInputType::Json,
+                    path: kg.clone(),
+                    synonyms: SynonymProperties::default(),
+                }),
+                public: false,
+                publish: false,
+            }),
+            haystacks: vec![Haystack {
+                path: haystack.clone(),
+                service: ServiceType::Native,
+            }],
+            extra: AHashMap::new(),
+        };
+        let mut config = ConfigBuilder::new()
+            .add_role("JSON Watcher", role)
+            .build()?;
+        let config_state = ConfigState::new(&mut config).await?;
+        let rolegraph = config_state.roles.get(&role_name).unwrap().clone();
+
+        let _watcher = watcher::watch(config_state.clone()).await?;
+
+        // Markdown files aren't concepts of a JSON knowledge graph
+        fs::write(kg.join("notes.md"), "synonyms:: notes\n")?;
+
+        // A new file adds its concepts
+        fs::write(
+            kg.join("storage.json"),
+            r#"{"concepts": [{"term": "postgres", "synonyms": ["pg"], "id": 3}]}"#,
+        )?;
+        let updated = eventually(|| async {
+            let rolegraph = rolegraph.lock().await;
+            rolegraph
+                .automata
+                .get(&NormalizedTermValue::from("pg"))
+                .is_some()
+        })
+        .await;
+        assert!(updated, "thesaurus was not rebuilt");
+        {
+            let rolegraph = rolegraph.lock().await;
+            assert_eq!(rolegraph.automata.len(), 4);
+            assert!(rolegraph
+                .automata
+                .get(&NormalizedTermValue::from("notes"))
+                .is_none());
+            assert_eq!(rolegraph.find_matching_node_ids("We run pg"), [3]);
+        }
+
+        // Removing the file drops its concepts again
+        fs::remove_file(kg.join("storage.json"))?;
+        let removed = eventually(|| async { rolegraph.lock().await.automata.len() == 2 }).await;
+        assert!(removed, "concepts were not removed from thesaurus");
+
+        Ok(())
+    }
+}
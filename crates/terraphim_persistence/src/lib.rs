@@ -63,15 +63,24 @@ pub trait Persistable: Serialize + DeserializeOwned {
     fn new(key: String) -> Self;
 
     /// Save to all profiles
-    async fn save(&self) -> Result<()>;
+    async fn save(&self) -> Result<()> {
+        self.save_to_all().await
+    }
 
     /// Save to a single profile
-    async fn save_to_one(&self, profile_name: &str) -> Result<()>;
+    async fn save_to_one(&self, profile_name: &str) -> Result<()> {
+        self.save_to_profile(profile_name).await
+    }
 
     /// Load a key from the fastest operator
     async fn load(&mut self) -> Result<Self>
     where
-        Self: Sized;
+        Self: Sized + Sync,
+    {
+        let op = &self.load_config().await?.1;
+        let key = self.get_key();
+        self.load_from_operator(&key, op).await
+    }
 
     /// Load the configuration
     async fn load_config(&self) -> Result<(HashMap<String, (Operator, u128)>, Operator)> {
@@ -58,6 +58,7 @@ fn dummy_document(id: String, body: String) -> Document {
         rank: None,
         tags: None,
         body,
+        score_breakdown: None,
     }
 }
 
@@ -0,0 +1,298 @@
+//! Export of a `RoleGraph` into graph exchange formats
+//!
+//! The exported graph contains one node per concept, labelled with its
+//! normalized term, and one directed edge per pair of co-occurring concepts.
+//! Every edge carries its rank as weight and the IDs of the documents it was
+//! found in, so that the graph can be analysed in tools like Gephi or
+//! Graphviz.
+//!
+//! Supported formats are [GraphML](http://graphml.graphdrawing.org/),
+//! [DOT](https://graphviz.org/doc/info/lang.html) and the
+//! [JSON Graph Format](https://jsongraphformat.info/).
+
+use std::fmt::Write;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use terraphim_types::{Edge, Node};
+
+use crate::{magic_unpair, Error, Result, RoleGraph};
+
+/// Format to export a `RoleGraph` to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// GraphML, as read by Gephi and yEd
+    #[default]
+    GraphML,
+    /// DOT, as read by Graphviz
+    Dot,
+    /// JSON Graph Format
+    Json,
+}
+
+impl ExportFormat {
+    /// MIME type of the exported graph
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            ExportFormat::GraphML => "application/graphml+xml",
+            ExportFormat::Dot => "text/vnd.graphviz",
+            ExportFormat::Json => "application/json",
+        }
+    }
+
+    /// Common file extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::GraphML => "graphml",
+            ExportFormat::Dot => "dot",
+            ExportFormat::Json => "json",
+        }
+    }
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "graphml" => Ok(ExportFormat::GraphML),
+            "dot" | "graphviz" => Ok(ExportFormat::Dot),
+            "json" | "jgf" => Ok(ExportFormat::Json),
+            _ => Err(Error::ExportFormat(s.to_string())),
+        }
+    }
+}
+
+impl RoleGraph {
+    /// Export the graph to the given format
+    pub fn export(&self, format: ExportFormat) -> Result<String> {
+        match format {
+            ExportFormat::GraphML => Ok(self.to_graphml()),
+            ExportFormat::Dot => Ok(self.to_dot()),
+            ExportFormat::Json => Ok(serde_json::to_string_pretty(&self.to_json_graph())?),
+        }
+    }
+
+    /// Export the graph to GraphML
+    pub fn to_graphml(&self) -> String {
+        let mut out = String::new();
+        out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        out.push_str(
+            "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        );
+        out.push_str("  <key id=\"rank\" for=\"node\" attr.name=\"rank\" attr.type=\"long\"/>\n");
+        out.push_str(
+            "  <key id=\"weight\" for=\"edge\" attr.name=\"weight\" attr.type=\"long\"/>\n",
+        );
+        out.push_str(
+            "  <key id=\"documents\" for=\"edge\" attr.name=\"documents\" attr.type=\"string\"/>\n",
+        );
+        let _ = writeln!(
+            out,
+            "  <graph id=\"{}\" edgedefault=\"directed\">",
+            escape_xml(&self.role.original)
+        );
+        for node in self.sorted_nodes() {
+            let _ = writeln!(out, "    <node id=\"n{}\">", node.id);
+            let _ = writeln!(
+                out,
+                "      <data key=\"label\">{}</data>",
+                escape_xml(&self.label(node.id))
+            );
+            let _ = writeln!(out, "      <data key=\"rank\">{}</data>", node.rank);
+            out.push_str("    </node>\n");
+        }
+        for edge in self.sorted_edges() {
+            let (source, target) = magic_unpair(edge.id);
+            let _ = writeln!(
+                out,
+                "    <edge id=\"e{}\" source=\"n{source}\" target=\"n{target}\">",
+                edge.id
+            );
+            let _ = writeln!(out, "      <data key=\"weight\">{}</data>", edge.rank);
+            let _ = writeln!(
+                out,
+                "      <data key=\"documents\">{}</data>",
+                escape_xml(&edge_documents(edge).join(","))
+            );
+            out.push_str("    </edge>\n");
+        }
+        out.push_str("  </graph>\n");
+        out.push_str("</graphml>\n");
+        out
+    }
+
+    /// Export the graph to DOT
+    pub fn to_dot(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "digraph \"{}\" {{", escape_dot(&self.role.original));
+        for node in self.sorted_nodes() {
+            let _ = writeln!(
+                out,
+                "  {} [label=\"{}\", rank={}];",
+                node.id,
+                escape_dot(&self.label(node.id)),
+                node.rank
+            );
+        }
+        for edge in self.sorted_edges() {
+            let (source, target) = magic_unpair(edge.id);
+            let _ = writeln!(
+                out,
+                "  {source} -> {target} [id={}, weight={}, documents=\"{}\"];",
+                edge.id,
+                edge.rank,
+                escape_dot(&edge_documents(edge).join(","))
+            );
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    /// Export the graph to the JSON Graph Format
+    pub fn to_json_graph(&self) -> serde_json::Value {
+        let nodes: serde_json::Map<String, serde_json::Value> = self
+            .sorted_nodes()
+            .into_iter()
+            .map(|node| {
+                (
+                    node.id.to_string(),
+                    json!({
+                        "label": self.label(node.id),
+                        "metadata": { "rank": node.rank },
+                    }),
+                )
+            })
+            .collect();
+        let edges: Vec<serde_json::Value> = self
+            .sorted_edges()
+            .into_iter()
+            .map(|edge| {
+                let (source, target) = magic_unpair(edge.id);
+                json!({
+                    "id": edge.id.to_string(),
+                    "source": source.to_string(),
+                    "target": target.to_string(),
+                    "metadata": {
+                        "weight": edge.rank,
+                        "documents": edge_documents(edge),
+                    },
+                })
+            })
+            .collect();
+        json!({
+            "graph": {
+                "id": self.role.original,
+                "label": self.role.original,
+                "directed": true,
+                "nodes": nodes,
+                "edges": edges,
+            }
+        })
+    }
+
+    /// Returns the normalized term of a node, or its ID if it isn't part of
+    /// the thesaurus anymore
+    fn label(&self, node_id: u64) -> String {
+        self.automata
+            .concept(node_id)
+            .map(|term| term.value.to_string())
+            .unwrap_or_else(|| node_id.to_string())
+    }
+
+    /// Nodes ordered by ID, to keep exports stable
+    fn sorted_nodes(&self) -> Vec<&Node> {
+        let mut nodes: Vec<&Node> = self.nodes.values().collect();
+        nodes.sort_by_key(|node| node.id);
+        nodes
+    }
+
+    /// Edges ordered by ID, to keep exports stable
+    fn sorted_edges(&self) -> Vec<&Edge> {
+        let mut edges: Vec<&Edge> = self.edges.values().collect();
+        edges.sort_by_key(|edge| edge.id);
+        edges
+    }
+}
+
+/// IDs of the documents an edge was found in, in alphabetical order
+fn edge_documents(edge: &Edge) -> Vec<&str> {
+    let mut documents: Vec<&str> = edge.doc_hash.keys().map(String::as_str).collect();
+    documents.sort_unstable();
+    documents
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+fn escape_dot(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use terraphim_types::Document;
+
+    use crate::tests::thesaurus;
+
+    async fn sample_rolegraph() -> RoleGraph {
+        let thesaurus = thesaurus(&[(1, "kafka"), (2, "r&d \"lab\"")]);
+        let mut rolegraph = RoleGraph::new("Streaming".into(), thesaurus).await.unwrap();
+        let document = Document {
+            id: "doc1".to_string(),
+            body: "Kafka in the r&d \"lab\"".to_string(),
+            ..Default::default()
+        };
+        rolegraph.insert_document("doc1", document);
+        rolegraph
+    }
+
+    #[test]
+    fn test_export_format() {
+        assert_eq!(
+            "GraphML".parse::<ExportFormat>().unwrap(),
+            ExportFormat::GraphML
+        );
+        assert_eq!("dot".parse::<ExportFormat>().unwrap(), ExportFormat::Dot);
+        assert_eq!("json".parse::<ExportFormat>().unwrap(), ExportFormat::Json);
+        assert!("csv".parse::<ExportFormat>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_export() {
+        let rolegraph = sample_rolegraph().await;
+        let edge_id = crate::magic_pair(1, 2);
+
+        let graphml = rolegraph.export(ExportFormat::GraphML).unwrap();
+        assert!(graphml.contains("<data key=\"label\">r&amp;d &quot;lab&quot;</data>"));
+        assert!(graphml.contains(&format!(
+            "<edge id=\"e{edge_id}\" source=\"n1\" target=\"n2\">"
+        )));
+        assert!(graphml.contains("<data key=\"documents\">doc1</data>"));
+
+        let dot = rolegraph.export(ExportFormat::Dot).unwrap();
+        assert!(dot.starts_with("digraph \"Streaming\" {"));
+        assert!(dot.contains("2 [label=\"r&d \\\"lab\\\"\", rank=1];"));
+        assert!(dot.contains(&format!(
+            "1 -> 2 [id={edge_id}, weight=1, documents=\"doc1\"];"
+        )));
+
+        let json: serde_json::Value =
+            serde_json::from_str(&rolegraph.export(ExportFormat::Json).unwrap()).unwrap();
+        let graph = &json["graph"];
+        assert_eq!(graph["nodes"]["1"]["label"], "kafka");
+        assert_eq!(graph["edges"][0]["source"], "1");
+        assert_eq!(graph["edges"][0]["target"], "2");
+        assert_eq!(graph["edges"][0]["metadata"]["documents"][0], "doc1");
+    }
+}
@@ -1,16 +1,26 @@
-use ahash::AHashMap;
+use ahash::{AHashMap, AHashSet};
 use itertools::Itertools;
 use memoize::memoize;
 use regex::Regex;
 use std::collections::hash_map::Entry;
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use terraphim_types::{
-    Document, Edge, IndexedDocument, Node, NormalizedTermValue, RoleName, Thesaurus,
+    ConnectedConcept, Correction, Document, Edge, Explanation, IndexedDocument, Neighbour, Node,
+    NormalizedTerm, NormalizedTermValue, Query, QueryError, RankContribution, RoleName, Suggestion,
+    Thesaurus,
 };
 use tokio::sync::{Mutex, MutexGuard};
+pub mod export;
 pub mod input;
-use aho_corasick::{AhoCorasick, MatchKind};
+pub mod snapshot;
+pub mod window;
+pub use export::ExportFormat;
+pub use snapshot::{RoleGraphSnapshot, SNAPSHOT_VERSION};
+use terraphim_automata::{Automata, FuzzyDistance, MatchMode, NormalizationOptions};
 use unicode_segmentation::UnicodeSegmentation;
+pub use window::CoOccurrenceWindow;
 
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
@@ -24,6 +34,14 @@ pub enum Error {
     TerraphimAutomataError(#[from] terraphim_automata::TerraphimAutomataError),
     #[error("Indexing error: {0}")]
     AhoCorasickError(#[from] aho_corasick::BuildError),
+    #[error("Unsupported rolegraph snapshot version {found}, expected {expected}")]
+    SnapshotVersion { found: u32, expected: u32 },
+    #[error("The rolegraph snapshot was built with a different thesaurus")]
+    SnapshotThesaurus,
+    #[error("Invalid query: {0}")]
+    Query(#[from] QueryError),
+    #[error("Unknown export format `{0}`, expected graphml, dot or json")]
+    ExportFormat(String),
 }
 
 type Result<T> = std::result::Result<T, Error>;
@@ -43,61 +61,152 @@ pub struct RoleGraph {
     edges: AHashMap<u64, Edge>,
     /// A mapping from document IDs to indexed documents
     documents: AHashMap<String, IndexedDocument>,
-    /// A thesaurus is a mapping from synonyms to concepts
-    pub thesaurus: Thesaurus,
-    /// Aho-Corasick values
-    aho_corasick_values: Vec<u64>,
-    /// Aho-Corasick automata
-    pub ac: AhoCorasick,
-    /// reverse lookup - matched id into normalized term
-    pub ac_reverse_nterm: AHashMap<u64, NormalizedTermValue>,
+    /// Automata for matching the thesaurus, which also knows how to
+    /// normalize text before matching
+    ///
+    /// It holds the thesaurus, a mapping from synonyms to concepts, so
+    /// concepts are looked up through it and not copied into the graph.
+    pub automata: Arc<Automata>,
+    /// Which hits of the automata are accepted as matches
+    match_mode: MatchMode,
+    /// Where consecutive matches of a document are connected by an edge
+    co_occurrence_window: CoOccurrenceWindow,
+    /// Distance for correcting misspelled queries, `None` disables it
+    fuzzy: Option<FuzzyDistance>,
+    /// Whether queries also match the narrower concepts of their concepts
+    query_expansion: bool,
 }
 
 impl RoleGraph {
     /// Creates a new `RoleGraph` with the given role and thesaurus
     pub async fn new(role: RoleName, thesaurus: Thesaurus) -> Result<Self> {
-        // We need to iterate over keys and values at the same time
-        // because the order of entries is not guaranteed
-        // when using `.keys()` and `.values()`.
-        // let (keys, values): (Vec<&str>, Vec<Id>) = thesaurus
-        //     .iter()
-        //     .map(|(key, value)| (key.as_str(), value.id))
-        //     .unzip();
-        let mut keys = Vec::new();
-        let mut values = Vec::new();
-        let mut ac_reverse_nterm = AHashMap::new();
-
-        for (key, normalized_term) in &thesaurus {
-            keys.push(key);
-            values.push(normalized_term.id);
-            ac_reverse_nterm.insert(normalized_term.id, normalized_term.value.clone());
-        }
-
-        let ac = AhoCorasick::builder()
-            .match_kind(MatchKind::LeftmostLongest)
-            .ascii_case_insensitive(true)
-            .build(keys)?;
-
-        Ok(Self {
+        let automata = Automata::new(&thesaurus, NormalizationOptions::default())?;
+        Ok(Self::from_automata(role, Arc::new(automata)))
+    }
+
+    /// Creates a new `RoleGraph` with the given role, sharing a prebuilt
+    /// automata
+    ///
+    /// The thesaurus and the normalization are taken from the automata.
+    pub fn from_automata(role: RoleName, automata: Arc<Automata>) -> Self {
+        Self {
             role,
             nodes: AHashMap::new(),
             edges: AHashMap::new(),
             documents: AHashMap::new(),
-            thesaurus,
-            aho_corasick_values: values,
-            ac,
-            ac_reverse_nterm,
-        })
+            automata,
+            match_mode: MatchMode::default(),
+            co_occurrence_window: CoOccurrenceWindow::default(),
+            fuzzy: None,
+            query_expansion: false,
+        }
+    }
+
+    /// Returns how text is normalized before matching
+    pub fn normalization(&self) -> NormalizationOptions {
+        self.automata.normalization()
+    }
+
+    /// Changes how text is normalized before matching and rebuilds the
+    /// automata
+    ///
+    /// Documents which were already indexed aren't re-indexed.
+    pub fn set_normalization(&mut self, normalization: NormalizationOptions) -> Result<()> {
+        if normalization != self.normalization() {
+            self.automata = Arc::new(Automata::new(&self.automata.thesaurus(), normalization)?);
+        }
+        Ok(())
+    }
+
+    /// Returns which hits of the automata are accepted as matches
+    pub fn match_mode(&self) -> MatchMode {
+        self.match_mode
+    }
+
+    /// Changes which hits of the automata are accepted as matches
+    ///
+    /// Documents which were already indexed aren't re-indexed.
+    pub fn set_match_mode(&mut self, match_mode: MatchMode) {
+        self.match_mode = match_mode;
+    }
+
+    /// Returns where consecutive matches of a document co-occur
+    pub fn co_occurrence_window(&self) -> CoOccurrenceWindow {
+        self.co_occurrence_window
+    }
+
+    /// Changes where consecutive matches of a document co-occur
+    ///
+    /// Documents which were already indexed aren't re-indexed.
+    pub fn set_co_occurrence_window(&mut self, co_occurrence_window: CoOccurrenceWindow) {
+        self.co_occurrence_window = co_occurrence_window;
+    }
+
+    /// Returns the distance for correcting misspelled queries
+    pub fn fuzzy(&self) -> Option<FuzzyDistance> {
+        self.fuzzy
+    }
+
+    /// Enables or disables correcting misspelled queries
+    pub fn set_fuzzy(&mut self, fuzzy: Option<FuzzyDistance>) {
+        self.fuzzy = fuzzy;
+    }
+
+    /// Returns whether queries are expanded to narrower concepts
+    pub fn query_expansion(&self) -> bool {
+        self.query_expansion
+    }
+
+    /// Enables or disables expanding queries to narrower concepts
+    ///
+    /// With expansion, a query for "database" also matches documents about
+    /// "postgres" if that is a narrower concept of "database" in the
+    /// thesaurus.
+    pub fn set_query_expansion(&mut self, query_expansion: bool) {
+        self.query_expansion = query_expansion;
+    }
+
+    /// Corrects the misspelled words of a query to terms of the thesaurus
+    ///
+    /// Only the terms and phrases of the query are corrected, operators,
+    /// parentheses and concept IDs are left as they are. Returns no
+    /// corrections if fuzzy matching is disabled.
+    pub fn correct(&self, query_string: &str) -> Vec<Correction> {
+        let Some(distance) = self.fuzzy else {
+            return Vec::new();
+        };
+        let ranges = Query::text_ranges(query_string);
+        self.automata
+            .correct(query_string, distance)
+            .into_iter()
+            .filter(|correction| {
+                let (start, end) = correction.pos;
+                ranges.iter().any(|&(from, to)| from <= start && end <= to)
+            })
+            .collect()
+    }
+
+    /// Replaces the thesaurus of the graph and rebuilds the automata
+    ///
+    /// Nodes, edges and documents are kept as they are, so this is meant
+    /// for incremental updates of the thesaurus, where the IDs of existing
+    /// concepts stay the same.
+    pub fn update_thesaurus(&mut self, thesaurus: Thesaurus) -> Result<()> {
+        self.automata = Arc::new(Automata::new(&thesaurus, self.normalization())?);
+        Ok(())
     }
 
     /// Find all matches in the rolegraph for the given text
     ///
+    /// The text is normalized the same way as the thesaurus before matching,
+    /// and only hits allowed by the match mode of the graph are returned.
     /// Returns a list of IDs of the matched nodes
     pub fn find_matching_node_ids(&self, text: &str) -> Vec<u64> {
         log::trace!("Finding matching node IDs for text: '{text}'");
-        self.ac
-            .find_iter(text)
-            .map(|mat| self.aho_corasick_values[mat.pattern()])
+        self.automata
+            .find_terms(text, self.match_mode)
+            .into_iter()
+            .map(|term| term.id)
             .collect()
     }
 
@@ -145,6 +254,10 @@ impl RoleGraph {
 
     /// Performs a query on the graph using the query string.
     ///
+    /// The query string is parsed into a [`Query`], see
+    /// [`terraphim_types::query`] for the syntax. Query strings which
+    /// aren't well-formed are matched as a single term.
+    ///
     /// Returns a list of document IDs ranked and weighted by the weighted mean
     /// average of node rank, edge rank, and document rank.
     pub fn query_graph(
@@ -154,12 +267,82 @@ impl RoleGraph {
         limit: Option<usize>,
     ) -> Result<Vec<(String, IndexedDocument)>> {
         log::debug!("Performing graph query with string: '{query_string}'");
-        let node_ids = self.find_matching_node_ids(query_string);
+        if query_string.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let query = Query::parse_lenient(query_string)?;
+        self.query(&query, offset, limit)
+    }
+
+    /// Performs a parsed query on the graph
+    ///
+    /// Only documents which match the whole query are returned. They are
+    /// ranked by the concepts of the query which are not negated.
+    pub fn query(
+        &self,
+        query: &Query,
+        offset: Option<usize>,
+        limit: Option<usize>,
+    ) -> Result<Vec<(String, IndexedDocument)>> {
+        log::debug!("Performing graph query: {query}");
+        let documents: Vec<_> = self
+            .rank(query)?
+            .into_iter()
+            .map(|(document, _)| (document.id.clone(), document))
+            .skip(offset.unwrap_or(0))
+            .take(limit.unwrap_or(usize::MAX))
+            .collect();
+
+        log::debug!("Query resulted in {} documents", documents.len());
+        Ok(documents)
+    }
 
-        let mut results = AHashMap::new();
+    /// Explains the ranking of a query on the graph
+    ///
+    /// Returns the same documents in the same order as [`Self::query_graph`],
+    /// but instead of the indexed documents it returns the matched concepts,
+    /// the traversed edges and the node, edge and document rank
+    /// contributions, which add up to the rank of each document.
+    pub fn explain(
+        &self,
+        query_string: &str,
+        offset: Option<usize>,
+        limit: Option<usize>,
+    ) -> Result<Vec<Explanation>> {
+        log::debug!("Explaining graph query with string: '{query_string}'");
+        if query_string.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        let query = Query::parse_lenient(query_string)?;
+        Ok(self
+            .rank(&query)?
+            .into_iter()
+            .map(|(_, explanation)| explanation)
+            .skip(offset.unwrap_or(0))
+            .take(limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    /// Ranks all documents matching the query, highest rank first
+    ///
+    /// Every document comes with an explanation of its rank.
+    fn rank(&self, query: &Query) -> Result<Vec<(IndexedDocument, Explanation)>> {
+        let matching_documents = self.matching_documents(query);
+        let node_ids: Vec<u64> = query
+            .positive_leaves()
+            .into_iter()
+            .flat_map(|leaf| self.leaf_node_ids(leaf))
+            .collect();
+        let node_ids = self.expand(node_ids);
+
+        let mut results: AHashMap<String, (IndexedDocument, Explanation)> = AHashMap::new();
         for node_id in node_ids {
-            let node = self.nodes.get(&node_id).ok_or(Error::NodeIdNotFound)?;
-            let Some(normalized_term) = self.ac_reverse_nterm.get(&node_id) else {
+            // Concepts without any documents don't contribute to the rank
+            let Some(node) = self.nodes.get(&node_id) else {
+                continue;
+            };
+            let Some(normalized_term) = self.automata.concept(node_id).map(|term| &term.value)
+            else {
                 return Err(Error::NodeIdNotFound);
             };
             log::debug!("Processing node ID: {:?} with rank: {}", node_id, node.rank);
@@ -169,41 +352,310 @@ impl RoleGraph {
                 log::trace!("Processing edge ID: {:?} with rank: {}", edge_id, edge.rank);
 
                 for (document_id, document_rank) in &edge.doc_hash {
+                    if !matching_documents.contains(document_id) {
+                        continue;
+                    }
+                    let contribution = RankContribution {
+                        node_id,
+                        concept: normalized_term.clone(),
+                        edge_id: edge.id,
+                        node_rank: node.rank,
+                        edge_rank: edge.rank,
+                        document_rank: *document_rank,
+                    };
                     // For now, this sums up over nodes and edges
-                    let total_rank = node.rank + edge.rank + document_rank;
+                    let total_rank = contribution.total();
                     match results.entry(document_id.clone()) {
                         Entry::Vacant(e) => {
-                            e.insert(IndexedDocument {
-                                id: document_id.clone(),
-                                matched_edges: vec![edge.clone()],
-                                rank: total_rank,
-                                tags: vec![normalized_term.to_string()],
-                                nodes: vec![node_id],
-                            });
+                            let mut explanation = Explanation::new(document_id.clone());
+                            explanation.add(contribution);
+                            e.insert((
+                                IndexedDocument {
+                                    id: document_id.clone(),
+                                    matched_edges: vec![edge.clone()],
+                                    rank: total_rank,
+                                    tags: vec![normalized_term.to_string()],
+                                    nodes: vec![node_id],
+                                },
+                                explanation,
+                            ));
                         }
                         Entry::Occupied(mut e) => {
-                            let doc = e.get_mut();
+                            let (doc, explanation) = e.get_mut();
                             doc.rank += total_rank; // Adjust to correctly aggregate the rank
                             doc.matched_edges.push(edge.clone());
                             // Remove duplicate edges based on unique IDs
                             doc.matched_edges.dedup_by_key(|e| e.id);
+                            explanation.add(contribution);
                         }
                     }
                 }
             }
         }
 
-        let mut ranked_documents = results.into_iter().collect::<Vec<_>>();
-        ranked_documents.sort_by_key(|(_, doc)| std::cmp::Reverse(doc.rank));
+        // Documents which only match because of negated parts of the query
+        for document_id in matching_documents {
+            if let Entry::Vacant(e) = results.entry(document_id.clone()) {
+                e.insert((
+                    IndexedDocument {
+                        id: document_id.clone(),
+                        matched_edges: Vec::new(),
+                        rank: 0,
+                        tags: Vec::new(),
+                        nodes: Vec::new(),
+                    },
+                    Explanation::new(document_id),
+                ));
+            }
+        }
+
+        let mut ranked_documents = results.into_values().collect::<Vec<_>>();
+        ranked_documents.sort_by_key(|(doc, _)| std::cmp::Reverse(doc.rank));
+        Ok(ranked_documents)
+    }
+
+    /// Returns the IDs of all documents matching the query
+    fn matching_documents(&self, query: &Query) -> AHashSet<String> {
+        match query {
+            Query::Term(_) | Query::Phrase(_) | Query::Concept(_) => self
+                .expand(self.leaf_node_ids(query))
+                .into_iter()
+                .flat_map(|node_id| self.node_documents(node_id))
+                .collect(),
+            Query::And(left, right) => {
+                &self.matching_documents(left) & &self.matching_documents(right)
+            }
+            Query::Or(left, right) => {
+                &self.matching_documents(left) | &self.matching_documents(right)
+            }
+            Query::Not(inner) => {
+                let all_documents: AHashSet<String> = self.documents.keys().cloned().collect();
+                &all_documents - &self.matching_documents(inner)
+            }
+        }
+    }
+
+    /// Returns the node IDs of the concepts a leaf of a query refers to
+    fn leaf_node_ids(&self, leaf: &Query) -> Vec<u64> {
+        match leaf {
+            Query::Term(text) => self.find_matching_node_ids(text),
+            Query::Phrase(text) => self.phrase_node_id(text).into_iter().collect(),
+            Query::Concept(id) => vec![*id],
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the node ID of the concept a phrase refers to
+    ///
+    /// The graph only knows which concepts a document mentions, not where,
+    /// so a phrase can only be matched as a whole if it is a single term of
+    /// the thesaurus. Phrases spanning several concepts match nothing.
+    fn phrase_node_id(&self, phrase: &str) -> Option<u64> {
+        let phrase = phrase.trim();
+        match self
+            .automata
+            .find_matches(phrase, true, self.match_mode)
+            .as_slice()
+        {
+            [matched] if matched.pos == Some((0, phrase.len())) => Some(matched.normalized_term.id),
+            _ => {
+                log::debug!("Phrase `{phrase}` isn't a single concept of the knowledge graph");
+                None
+            }
+        }
+    }
+
+    /// Adds the narrower concepts of the given concepts if queries are
+    /// expanded
+    ///
+    /// Concepts which are already part of the list aren't added again, so
+    /// they don't count twice when ranking.
+    fn expand(&self, mut node_ids: Vec<u64>) -> Vec<u64> {
+        if !self.query_expansion {
+            return node_ids;
+        }
+        let mut seen: AHashSet<u64> = node_ids.iter().copied().collect();
+        for node_id in node_ids.clone() {
+            for narrower in self.automata.narrower_transitive(node_id) {
+                if seen.insert(narrower) {
+                    node_ids.push(narrower);
+                }
+            }
+        }
+        node_ids
+    }
+
+    /// Returns the IDs of all documents connected to a node
+    fn node_documents(&self, node_id: u64) -> AHashSet<String> {
+        let Some(node) = self.nodes.get(&node_id) else {
+            return AHashSet::new();
+        };
+        node.connected_with
+            .iter()
+            .filter_map(|edge_id| self.edges.get(edge_id))
+            .flat_map(|edge| edge.doc_hash.keys().cloned())
+            .collect()
+    }
+
+    /// Looks up a concept by its ID or by one of its synonyms
+    pub fn find_concept(&self, concept: &str) -> Option<NormalizedTerm> {
+        if let Ok(id) = concept.trim().parse::<u64>() {
+            if let Some(term) = self.normalized_term(id) {
+                return Some(term);
+            }
+        }
+        self.automata
+            .get(&NormalizedTermValue::new(concept.to_string()))
+            .cloned()
+    }
+
+    /// Returns the concepts co-occurring with the given concept
+    ///
+    /// The neighbours are sorted by the weight of the connecting edge,
+    /// heaviest first. Unknown concepts don't have any neighbours.
+    pub fn neighbours(&self, node_id: u64) -> Vec<Neighbour> {
+        let Some(node) = self.nodes.get(&node_id) else {
+            return Vec::new();
+        };
+        // Edges are directed, so a neighbour can be connected by two edges
+        let mut neighbours: AHashMap<u64, Neighbour> = AHashMap::new();
+        for edge in node
+            .connected_with
+            .iter()
+            .filter_map(|edge_id| self.edges.get(edge_id))
+        {
+            let other = match magic_unpair(edge.id) {
+                (a, b) if a == node_id => b,
+                (a, _) => a,
+            };
+            // A concept which directly follows itself isn't a neighbour
+            if other == node_id {
+                continue;
+            }
+            let neighbour = match neighbours.entry(other) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let Some(concept) = self.normalized_term(other) else {
+                        continue;
+                    };
+                    entry.insert(Neighbour {
+                        concept,
+                        edges: Vec::new(),
+                        weight: 0,
+                    })
+                }
+            };
+            neighbour.edges.push(edge.id);
+            neighbour.edges.sort_unstable();
+            neighbour.weight += edge.rank;
+        }
+        let mut neighbours: Vec<Neighbour> = neighbours.into_values().collect();
+        neighbours.sort_by(|a, b| {
+            b.weight
+                .cmp(&a.weight)
+                .then_with(|| a.concept.id.cmp(&b.concept.id))
+        });
+        neighbours
+    }
+
+    /// Finds the shortest path between two concepts
+    ///
+    /// Every step of the path goes from a concept to one of its neighbours.
+    /// Returns `None` if the concepts aren't connected.
+    pub fn shortest_path(&self, from: u64, to: u64) -> Option<Vec<NormalizedTerm>> {
+        if from == to {
+            return self.normalized_term(from).map(|term| vec![term]);
+        }
+        let mut previous: AHashMap<u64, u64> = AHashMap::new();
+        let mut queue = VecDeque::from([from]);
+        previous.insert(from, from);
+        while let Some(node_id) = queue.pop_front() {
+            for neighbour in self.neighbours(node_id) {
+                let next = neighbour.concept.id;
+                if previous.contains_key(&next) {
+                    continue;
+                }
+                previous.insert(next, node_id);
+                if next == to {
+                    let mut path = vec![to];
+                    let mut current = to;
+                    while current != from {
+                        current = previous[&current];
+                        path.push(current);
+                    }
+                    return path
+                        .into_iter()
+                        .rev()
+                        .map(|id| self.normalized_term(id))
+                        .collect();
+                }
+                queue.push_back(next);
+            }
+        }
+        None
+    }
+
+    /// Returns the `limit` concepts with the most connections
+    ///
+    /// Concepts with the same number of connections are ordered by rank.
+    pub fn top_concepts(&self, limit: usize) -> Vec<ConnectedConcept> {
+        let mut concepts: Vec<ConnectedConcept> = self
+            .nodes
+            .values()
+            .filter_map(|node| {
+                Some(ConnectedConcept {
+                    concept: self.normalized_term(node.id)?,
+                    rank: node.rank,
+                    degree: node.connected_with.len(),
+                })
+            })
+            .collect();
+        concepts.sort_by(|a, b| {
+            b.degree
+                .cmp(&a.degree)
+                .then_with(|| b.rank.cmp(&a.rank))
+                .then_with(|| a.concept.id.cmp(&b.concept.id))
+        });
+        concepts.truncate(limit);
+        concepts
+    }
 
-        let documents: Vec<_> = ranked_documents
+    /// Suggests up to `limit` synonyms completing what was typed
+    ///
+    /// Synonyms starting with the query come before synonyms which only
+    /// match after fixing a typo, and are ranked by the rank of their
+    /// concept in the graph.
+    pub fn autocomplete(&self, query: &str, limit: usize) -> Vec<Suggestion> {
+        let mut suggestions: Vec<Suggestion> = self
+            .automata
+            .complete(query)
             .into_iter()
-            .skip(offset.unwrap_or(0))
-            .take(limit.unwrap_or(std::usize::MAX))
+            .map(|completion| Suggestion {
+                term: completion.key.clone(),
+                concept: completion.term.clone(),
+                rank: self
+                    .nodes
+                    .get(&completion.term.id)
+                    .map_or(0, |node| node.rank),
+                fuzzy: completion.fuzzy,
+            })
             .collect();
+        suggestions.sort_by(|a, b| {
+            a.fuzzy
+                .cmp(&b.fuzzy)
+                .then_with(|| b.rank.cmp(&a.rank))
+                .then_with(|| a.term.as_str().len().cmp(&b.term.as_str().len()))
+                .then_with(|| a.term.cmp(&b.term))
+        });
+        suggestions.truncate(limit);
+        suggestions
+    }
 
-        log::debug!("Query resulted in {} documents", documents.len());
-        Ok(documents)
+    /// Returns the normalized term of a node
+    fn normalized_term(&self, node_id: u64) -> Option<NormalizedTerm> {
+        self.automata
+            .concept(node_id)
+            .map(|term| NormalizedTerm::new(node_id, term.value.clone()))
     }
 
     // pub fn parse_document_to_pair(&mut self, document_id: &str, text: &str) {
@@ -215,12 +667,85 @@ impl RoleGraph {
     //     }
     // }
 
+    /// Finds all matches in the text, together with the pairs of
+    /// consecutive matches which co-occur within the co-occurrence window
+    ///
+    /// Returns the IDs of the matched nodes and the pairs of them.
+    pub fn find_co_occurrences(&self, text: &str) -> (Vec<u64>, Vec<(u64, u64)>) {
+        let window = self.co_occurrence_window;
+        if let CoOccurrenceWindow::Tokens(_) = window {
+            let words = window::words(text);
+            let matched = self.automata.find_matches(text, true, self.match_mode);
+            let pairs = matched
+                .iter()
+                .tuple_windows()
+                .filter(|(a, b)| {
+                    a.pos
+                        .zip(b.pos)
+                        .is_some_and(|(a, b)| window.fits(&words, a, b))
+                })
+                .map(|(a, b)| (a.normalized_term.id, b.normalized_term.id))
+                .collect();
+            let matches = matched
+                .iter()
+                .map(|matched| matched.normalized_term.id)
+                .collect();
+            return (matches, pairs);
+        }
+
+        let mut matches = Vec::new();
+        let mut pairs = Vec::new();
+        for segment in window.segments(text) {
+            let matched = self.find_matching_node_ids(segment);
+            pairs.extend(matched.iter().copied().tuple_windows::<(u64, u64)>());
+            matches.extend(matched);
+        }
+        (matches, pairs)
+    }
+
     /// Inserts an document into the rolegraph
+    ///
+    /// Consecutive matches in the document are connected by an edge if they
+    /// co-occur within the co-occurrence window, see
+    /// [`RoleGraph::set_co_occurrence_window`]. Besides updating nodes and
+    /// edges, this keeps an `IndexedDocument` entry for the document, so
+    /// that the indexed state can be snapshotted and restored later on.
     pub fn insert_document(&mut self, document_id: &str, document: Document) {
-        let matches = self.find_matching_node_ids(&document.to_string());
-        for (a, b) in matches.into_iter().tuple_windows() {
+        let (matches, pairs) = self.find_co_occurrences(&document.to_string());
+        let mut edge_ids = Vec::new();
+        for (a, b) in pairs {
             self.add_or_update_document(document_id, a, b);
+            edge_ids.push(magic_pair(a, b));
         }
+        edge_ids.sort_unstable();
+        edge_ids.dedup();
+
+        let matched_edges: Vec<Edge> = edge_ids
+            .iter()
+            .filter_map(|edge_id| self.edges.get(edge_id).cloned())
+            .collect();
+        let rank = matched_edges
+            .iter()
+            .filter_map(|edge| edge.doc_hash.get(document_id))
+            .sum();
+        let nodes: Vec<u64> = matches.into_iter().unique().collect();
+        let tags = nodes
+            .iter()
+            .filter_map(|node_id| self.automata.concept(*node_id))
+            .map(|nterm| nterm.value.to_string())
+            .unique()
+            .collect();
+
+        self.documents.insert(
+            document_id.to_string(),
+            IndexedDocument {
+                id: document_id.to_string(),
+                matched_edges,
+                rank,
+                tags,
+                nodes,
+            },
+        );
     }
 
     pub fn add_or_update_document(&mut self, document_id: &str, x: u64, y: u64) {
@@ -253,19 +778,81 @@ impl RoleGraph {
             }
             Entry::Occupied(entry) => {
                 let edge = entry.into_mut();
-                *edge.doc_hash.entry(document_id.to_string()).or_insert(1) += 1;
+                // Count every co-occurrence, so that it can be subtracted
+                // again when the document is removed
+                edge.rank += 1;
+                *edge.doc_hash.entry(document_id.to_string()).or_insert(0) += 1;
 
                 edge.clone()
             }
         };
         edge
     }
+
+    /// Removes a document from the rolegraph
+    ///
+    /// Decrements the rank of every edge and node the document contributed
+    /// to, and prunes edges and nodes which are no longer connected to any
+    /// document.
+    ///
+    /// Returns `true` if the document was part of the rolegraph.
+    pub fn remove_document(&mut self, document_id: &str) -> bool {
+        let mut removed = self.documents.remove(document_id).is_some();
+
+        let edge_ids: Vec<u64> = self
+            .edges
+            .values()
+            .filter(|edge| edge.doc_hash.contains_key(document_id))
+            .map(|edge| edge.id)
+            .collect();
+        for edge_id in edge_ids {
+            let Entry::Occupied(mut entry) = self.edges.entry(edge_id) else {
+                continue;
+            };
+            let edge = entry.get_mut();
+            let count = edge.doc_hash.remove(document_id).unwrap_or_default();
+            edge.rank = edge.rank.saturating_sub(count);
+            let prune_edge = edge.doc_hash.is_empty();
+            if prune_edge {
+                entry.remove();
+            }
+            removed = true;
+
+            // Every co-occurrence was counted once for each side of the edge
+            let (x, y) = magic_unpair(edge_id);
+            for node_id in [x, y] {
+                let Entry::Occupied(mut entry) = self.nodes.entry(node_id) else {
+                    continue;
+                };
+                let node = entry.get_mut();
+                node.rank = node.rank.saturating_sub(count);
+                if prune_edge {
+                    node.connected_with.remove(&edge_id);
+                }
+                if node.connected_with.is_empty() {
+                    entry.remove();
+                }
+            }
+        }
+        removed
+    }
+
+    /// Replaces a document in the rolegraph
+    ///
+    /// The previous version of the document is removed first, so that
+    /// edges which are no longer supported by the document don't linger.
+    pub fn update_document(&mut self, document_id: &str, document: Document) {
+        self.remove_document(document_id);
+        self.insert_document(document_id, document);
+    }
 }
 
 /// Wraps the `RoleGraph` for ingesting documents and is `Send` and `Sync`
 #[derive(Debug, Clone)]
 pub struct RoleGraphSync {
     inner: Arc<Mutex<RoleGraph>>,
+    /// Whether the rolegraph changed since its last snapshot was saved
+    dirty: Arc<AtomicBool>,
 }
 
 impl RoleGraphSync {
@@ -273,12 +860,31 @@ impl RoleGraphSync {
     pub async fn lock(&self) -> MutexGuard<'_, RoleGraph> {
         self.inner.lock().await
     }
+
+    /// Locks the rolegraph for changing it, which marks it as dirty
+    pub async fn lock_mut(&self) -> MutexGuard<'_, RoleGraph> {
+        let rolegraph = self.inner.lock().await;
+        self.mark_dirty();
+        rolegraph
+    }
+
+    /// Marks the rolegraph as changed since its last snapshot
+    pub fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// Returns whether the rolegraph changed since its last snapshot, and
+    /// resets it
+    pub fn take_dirty(&self) -> bool {
+        self.dirty.swap(false, Ordering::AcqRel)
+    }
 }
 
 impl From<RoleGraph> for RoleGraphSync {
     fn from(rolegraph: RoleGraph) -> Self {
         Self {
             inner: Arc::new(Mutex::new(rolegraph)),
+            dirty: Arc::new(AtomicBool::new(false)),
         }
     }
 }
@@ -324,7 +930,15 @@ pub fn magic_pair(x: u64, y: u64) -> u64 {
 /// }
 #[memoize(CustomHasher: ahash::AHashMap)]
 pub fn magic_unpair(z: u64) -> (u64, u64) {
-    let q = (z as f32).sqrt().floor() as u64;
+    // Floating point precision isn't sufficient for large numbers,
+    // so correct the estimate of the integer square root
+    let mut q = (z as f64).sqrt() as u64;
+    while q * q > z {
+        q -= 1;
+    }
+    while (q + 1) * (q + 1) <= z {
+        q += 1;
+    }
     let l = z - q * q;
     if l < q {
         (l, q)
@@ -338,9 +952,19 @@ mod tests {
     use super::*;
 
     use terraphim_automata::{load_thesaurus, AutomataPath};
+    use terraphim_types::RelationKind;
     use tokio::test;
     use ulid::Ulid;
 
+    /// A thesaurus where every term is its own concept
+    pub(crate) fn thesaurus(entries: &[(u64, &str)]) -> Thesaurus {
+        let mut thesaurus = Thesaurus::new("test".to_string());
+        for &(id, term) in entries {
+            thesaurus.insert(term.into(), NormalizedTerm::new(id, term.into()));
+        }
+        thesaurus
+    }
+
     async fn load_sample_thesaurus() -> Thesaurus {
         load_thesaurus(&AutomataPath::local_example_full())
             .await
@@ -388,12 +1012,12 @@ mod tests {
         let matches = rolegraph.find_matching_node_ids(query);
         println!("matches: {:?}", matches);
         for each_match in matches.iter() {
-            let ac_reverse_nterm = rolegraph.ac_reverse_nterm.get(each_match).unwrap();
-            println!("{each_match} ac_reverse_nterm: {:?}", ac_reverse_nterm);
+            let concept = rolegraph.automata.concept(*each_match).unwrap();
+            println!("{each_match} concept: {:?}", concept.value);
         }
         assert_eq!(
-            rolegraph.ac_reverse_nterm.get(&matches[0]).unwrap(),
-            &NormalizedTermValue::new("life cycle models".to_string())
+            rolegraph.automata.concept(matches[0]).unwrap().value,
+            NormalizedTermValue::new("life cycle models".to_string())
         );
     }
 
@@ -434,6 +1058,7 @@ mod tests {
             title: "README".to_string(),
             body: test_document.to_string(),
             description: None,
+            score_breakdown: None,
         };
         rolegraph.insert_document(&document_id, document);
         println!("query with {}", "terraphim-graph and service".to_string());
@@ -469,6 +1094,7 @@ mod tests {
             title: "terraphim-graph".to_string(),
             body: test_document2.to_string(),
             description: None,
+            score_breakdown: None,
         };
         rolegraph.insert_document(&document_id2, document2);
         log::debug!("Query graph");
@@ -484,6 +1110,455 @@ mod tests {
         println!("Edges count {:?}", rolegraph.edges.len());
     }
 
+    fn sample_document(id: &str, body: &str) -> Document {
+        Document {
+            id: id.to_string(),
+            url: format!("/path/to/{id}"),
+            title: id.to_string(),
+            body: body.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    async fn test_remove_document() {
+        let role = "system operator".to_string();
+        let thesaurus = load_sample_thesaurus().await;
+        let document1 = sample_document(
+            "document1",
+            "Life cycle concepts and Trained operators and maintainers, project direction, some bingo words Paradigm Map and project planning",
+        );
+        let document2 = sample_document(
+            "document2",
+            "Trained operators and maintainers, some bingo words Paradigm Map and project planning, then again: project direction and Life cycle concepts",
+        );
+
+        let mut expected = RoleGraph::new(role.clone().into(), thesaurus.clone())
+            .await
+            .unwrap();
+        expected.insert_document("document1", document1.clone());
+
+        let mut rolegraph = RoleGraph::new(role.into(), thesaurus).await.unwrap();
+        rolegraph.insert_document("document1", document1);
+        rolegraph.insert_document("document2", document2);
+        assert!(!expected.edges.is_empty());
+        assert_ne!(rolegraph.edges, expected.edges);
+
+        assert!(rolegraph.remove_document("document2"));
+        assert_eq!(rolegraph.nodes, expected.nodes);
+        assert_eq!(rolegraph.edges, expected.edges);
+        assert_eq!(rolegraph.documents, expected.documents);
+        assert!(!rolegraph.remove_document("document2"));
+
+        assert!(rolegraph.remove_document("document1"));
+        assert!(rolegraph.nodes.is_empty());
+        assert!(rolegraph.edges.is_empty());
+        assert!(rolegraph.documents.is_empty());
+    }
+
+    #[test]
+    async fn test_update_document() {
+        let role = "system operator".to_string();
+        let thesaurus = load_sample_thesaurus().await;
+        let original = sample_document(
+            "document1",
+            "Life cycle concepts and Trained operators and maintainers, project direction",
+        );
+        let updated = sample_document(
+            "document1",
+            "project direction, some bingo words Paradigm Map and project planning",
+        );
+
+        let mut expected = RoleGraph::new(role.clone().into(), thesaurus.clone())
+            .await
+            .unwrap();
+        expected.insert_document("document1", updated.clone());
+
+        let mut rolegraph = RoleGraph::new(role.into(), thesaurus).await.unwrap();
+        rolegraph.insert_document("document1", original);
+        assert!(!expected.edges.is_empty());
+        assert_ne!(rolegraph.edges, expected.edges);
+        rolegraph.update_document("document1", updated);
+        assert_eq!(rolegraph.nodes, expected.nodes);
+        assert_eq!(rolegraph.edges, expected.edges);
+        assert_eq!(rolegraph.documents, expected.documents);
+    }
+
+    #[test]
+    async fn test_dirty() {
+        let rolegraph = RoleGraph::new("streaming".into(), Thesaurus::new("streaming".to_string()))
+            .await
+            .unwrap();
+        let rolegraph = RoleGraphSync::from(rolegraph);
+        assert!(!rolegraph.take_dirty());
+
+        rolegraph
+            .lock()
+            .await
+            .query_graph("kafka", None, None)
+            .unwrap();
+        assert!(!rolegraph.take_dirty());
+
+        rolegraph
+            .lock_mut()
+            .await
+            .insert_document("kafka", sample_document("kafka", "Kafka"));
+        assert!(rolegraph.take_dirty());
+        assert!(!rolegraph.take_dirty());
+    }
+
+    #[test]
+    async fn test_boolean_query() {
+        let thesaurus = thesaurus(&[
+            (1, "kafka"),
+            (2, "zookeeper"),
+            (3, "streaming"),
+            (4, "event streaming"),
+        ]);
+        let mut rolegraph = RoleGraph::new("streaming".into(), thesaurus).await.unwrap();
+        for (id, body) in [
+            ("kafka", "Kafka is used for streaming"),
+            ("both", "Kafka uses zookeeper for streaming"),
+            ("zookeeper", "Zookeeper is not made for streaming"),
+            ("events", "Kafka does event streaming"),
+            ("none", "Nothing to see here"),
+        ] {
+            rolegraph.insert_document(id, sample_document(id, body));
+        }
+
+        let ids = |query: &str| -> Vec<String> {
+            let mut ids: Vec<String> = rolegraph
+                .query_graph(query, None, None)
+                .unwrap()
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert_eq!(ids("kafka AND NOT zookeeper"), ["events", "kafka"]);
+        assert_eq!(
+            ids("kafka zookeeper"),
+            ["both", "events", "kafka", "zookeeper"]
+        );
+        assert_eq!(
+            ids("kafka OR zookeeper"),
+            ["both", "events", "kafka", "zookeeper"]
+        );
+        assert_eq!(ids("#2 AND streaming"), ["both", "zookeeper"]);
+        // Documents without edges are part of every negation
+        assert_eq!(ids("NOT kafka"), ["none", "zookeeper"]);
+        assert!(ids("").is_empty());
+        // Phrases only match as a single concept
+        assert_eq!(ids("\"Event Streaming\""), ["events"]);
+        assert!(ids("\"kafka zookeeper\"").is_empty());
+        assert!(ids("\"kafka streaming\"").is_empty());
+        // Malformed queries are matched as a single term
+        assert_eq!(ids("kafka AND"), ["both", "events", "kafka"]);
+        assert_eq!(ids("\"zookeeper"), ["both", "zookeeper"]);
+    }
+
+    #[test]
+    async fn test_query_expansion() {
+        let mut thesaurus = thesaurus(&[
+            (1, "database"),
+            (2, "postgres"),
+            (3, "pgvector"),
+            (4, "backup"),
+        ]);
+        thesaurus.add_relation(2, RelationKind::Broader, 1);
+        thesaurus.add_relation(2, RelationKind::Narrower, 3);
+        let mut rolegraph = RoleGraph::new("databases".into(), thesaurus).await.unwrap();
+        for (id, body) in [
+            ("database", "Backup your database"),
+            ("postgres", "Backup postgres with pg_dump"),
+            ("pgvector", "Backup pgvector indexes"),
+        ] {
+            rolegraph.insert_document(id, sample_document(id, body));
+        }
+
+        let ids = |rolegraph: &RoleGraph, query: &str| -> Vec<String> {
+            let mut ids: Vec<String> = rolegraph
+                .query_graph(query, None, None)
+                .unwrap()
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        assert!(!rolegraph.query_expansion());
+        assert_eq!(ids(&rolegraph, "database"), ["database"]);
+
+        rolegraph.set_query_expansion(true);
+        assert_eq!(
+            ids(&rolegraph, "database"),
+            ["database", "pgvector", "postgres"]
+        );
+        assert_eq!(ids(&rolegraph, "postgres"), ["pgvector", "postgres"]);
+        assert_eq!(
+            ids(&rolegraph, "backup AND database"),
+            ["database", "pgvector", "postgres"]
+        );
+        assert!(ids(&rolegraph, "backup AND NOT database").is_empty());
+        // Only narrower concepts are expanded
+        assert_eq!(ids(&rolegraph, "pgvector"), ["pgvector"]);
+    }
+
+    #[test]
+    async fn test_co_occurrence_window() {
+        let thesaurus = thesaurus(&[(1, "rust"), (2, "tokio"), (3, "helm"), (4, "kubernetes")]);
+        let mut rolegraph = RoleGraph::new("devops".into(), thesaurus).await.unwrap();
+        let text = "Rust is fast. Tokio runs async Rust code.\n\nHelm deploys to Kubernetes.";
+
+        let pairs = |rolegraph: &RoleGraph| rolegraph.find_co_occurrences(text).1;
+        assert_eq!(
+            rolegraph.co_occurrence_window(),
+            CoOccurrenceWindow::Document
+        );
+        assert_eq!(pairs(&rolegraph), [(1, 2), (2, 1), (1, 3), (3, 4)]);
+
+        rolegraph.set_co_occurrence_window(CoOccurrenceWindow::Paragraph);
+        assert_eq!(pairs(&rolegraph), [(1, 2), (2, 1), (3, 4)]);
+
+        rolegraph.set_co_occurrence_window(CoOccurrenceWindow::Sentence);
+        assert_eq!(pairs(&rolegraph), [(2, 1), (3, 4)]);
+
+        // Token windows ignore sentences and paragraphs
+        rolegraph.set_co_occurrence_window(CoOccurrenceWindow::Tokens(4));
+        assert_eq!(pairs(&rolegraph), [(1, 2), (2, 1), (1, 3), (3, 4)]);
+        rolegraph.set_co_occurrence_window(CoOccurrenceWindow::Tokens(3));
+        assert_eq!(pairs(&rolegraph), [(1, 3)]);
+
+        // All matches are kept, even without a co-occurring match
+        rolegraph.set_co_occurrence_window(CoOccurrenceWindow::Sentence);
+        assert_eq!(rolegraph.find_co_occurrences(text).0, [1, 2, 1, 3, 4]);
+        rolegraph.insert_document("notes", sample_document("notes", text));
+        assert_eq!(rolegraph.edges.len(), 2);
+        assert!(!rolegraph.edges.contains_key(&magic_pair(1, 3)));
+        let document = rolegraph.documents.get("notes").unwrap();
+        assert_eq!(document.nodes, [1, 2, 3, 4]);
+
+        let snapshot = rolegraph.to_snapshot();
+        assert_eq!(snapshot.co_occurrence_window, CoOccurrenceWindow::Sentence);
+        let restored = RoleGraph::from_snapshot(snapshot, rolegraph.automata.clone()).unwrap();
+        assert_eq!(
+            restored.co_occurrence_window(),
+            CoOccurrenceWindow::Sentence
+        );
+    }
+
+    #[test]
+    async fn test_explain() {
+        let thesaurus = thesaurus(&[(1, "kafka"), (2, "zookeeper"), (3, "streaming")]);
+        let mut rolegraph = RoleGraph::new("streaming".into(), thesaurus).await.unwrap();
+        for (id, body) in [
+            ("kafka", "Kafka is used for streaming"),
+            (
+                "both",
+                "Kafka uses zookeeper for streaming, zookeeper and kafka",
+            ),
+        ] {
+            rolegraph.insert_document(id, sample_document(id, body));
+        }
+
+        let results = rolegraph
+            .query_graph("kafka streaming", None, None)
+            .unwrap();
+        let explanations = rolegraph.explain("kafka streaming", None, None).unwrap();
+        assert_eq!(results.len(), explanations.len());
+        for ((id, document), explanation) in results.iter().zip(&explanations) {
+            assert_eq!(id, &explanation.document_id);
+            assert_eq!(document.rank, explanation.rank);
+            let total: u64 = explanation
+                .contributions
+                .iter()
+                .map(RankContribution::total)
+                .sum();
+            assert_eq!(total, explanation.rank);
+            assert!(!explanation.edges.is_empty());
+        }
+
+        let both = explanations
+            .iter()
+            .find(|explanation| explanation.document_id == "both")
+            .unwrap();
+        assert!(both.concepts.contains(&"kafka".into()));
+        assert!(both.concepts.contains(&"streaming".into()));
+        assert!(!both.concepts.contains(&"zookeeper".into()));
+
+        assert_eq!(
+            rolegraph.explain("kafka", Some(1), Some(1)).unwrap().len(),
+            1
+        );
+        assert!(rolegraph.explain("", None, None).unwrap().is_empty());
+    }
+
+    #[test]
+    async fn test_graph_exploration() {
+        let mut thesaurus = thesaurus(&[
+            (1, "kafka"),
+            (2, "zookeeper"),
+            (3, "streaming"),
+            (4, "flink"),
+            (5, "unused"),
+        ]);
+        thesaurus.insert("zk".into(), NormalizedTerm::new(2, "zookeeper".into()));
+        let mut rolegraph = RoleGraph::new("streaming".into(), thesaurus).await.unwrap();
+        for (id, body) in [
+            ("first", "Kafka uses zookeeper, kafka is for streaming"),
+            ("second", "Kafka and streaming, kafka kafka"),
+            ("third", "Flink does streaming"),
+        ] {
+            rolegraph.insert_document(id, sample_document(id, body));
+        }
+
+        assert_eq!(rolegraph.find_concept("ZK").unwrap().id, 2);
+        assert_eq!(
+            rolegraph.find_concept("3").unwrap().value,
+            "streaming".into()
+        );
+        assert!(rolegraph.find_concept("spark").is_none());
+
+        let neighbours = rolegraph.neighbours(1);
+        let ids: Vec<u64> = neighbours.iter().map(|n| n.concept.id).collect();
+        assert_eq!(ids, [3, 2]);
+        assert_eq!(neighbours[0].weight, 3);
+        assert_eq!(neighbours[0].edges.len(), 2);
+        assert_eq!(neighbours[1].weight, 2);
+        assert!(rolegraph.neighbours(5).is_empty());
+
+        let path: Vec<u64> = rolegraph
+            .shortest_path(2, 4)
+            .unwrap()
+            .into_iter()
+            .map(|term| term.id)
+            .collect();
+        assert_eq!(path, [2, 1, 3, 4]);
+        assert_eq!(rolegraph.shortest_path(1, 1).unwrap().len(), 1);
+        assert!(rolegraph.shortest_path(1, 5).is_none());
+
+        let top = rolegraph.top_concepts(2);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].concept.id, 1);
+        assert!(top[0].degree >= top[1].degree);
+    }
+
+    #[test]
+    async fn test_autocomplete() {
+        let mut thesaurus = thesaurus(&[(1, "kafka"), (2, "kanban"), (3, "karate"), (4, "flink")]);
+        thesaurus.insert(
+            "kanban board".into(),
+            NormalizedTerm::new(2, "kanban".into()),
+        );
+        let mut rolegraph = RoleGraph::new("agile".into(), thesaurus).await.unwrap();
+        rolegraph.insert_document("first", sample_document("first", "Kanban with Flink"));
+
+        let suggestions = rolegraph.autocomplete("KA", 10);
+        let terms: Vec<&str> = suggestions.iter().map(|s| s.term.as_str()).collect();
+        assert_eq!(terms, ["kanban", "kanban board", "kafka", "karate"]);
+        assert_eq!(suggestions[1].concept.value, "kanban".into());
+        assert!(suggestions[0].rank > 0);
+        assert!(suggestions.iter().all(|s| !s.fuzzy));
+
+        assert_eq!(rolegraph.autocomplete("ka", 2).len(), 2);
+        let suggestions = rolegraph.autocomplete("kanbam", 10);
+        assert_eq!(suggestions[0].concept.id, 2);
+        assert!(suggestions[0].fuzzy);
+        assert!(rolegraph.autocomplete("spark", 10).is_empty());
+    }
+
+    #[test]
+    async fn test_fuzzy_correction() {
+        let thesaurus = thesaurus(&[(1, "kubernetes"), (2, "docker")]);
+        let mut rolegraph = RoleGraph::new("devops".into(), thesaurus).await.unwrap();
+        rolegraph.insert_document("first", sample_document("first", "Kubernetes runs Docker"));
+
+        let query = "kuberentes";
+        assert!(rolegraph.correct(query).is_empty());
+        assert!(rolegraph.query_graph(query, None, None).unwrap().is_empty());
+
+        // Swapped letters are two edits
+        rolegraph.set_fuzzy(Some(FuzzyDistance::Levenshtein { max_edits: 2 }));
+        let corrections = rolegraph.correct(query);
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].concept.id, 1);
+        let corrected = terraphim_automata::apply_corrections(query, &corrections);
+        assert_eq!(
+            rolegraph.query_graph(&corrected, None, None).unwrap().len(),
+            1
+        );
+    }
+
+    #[test]
+    async fn test_unicode_normalization() {
+        let thesaurus = thesaurus(&[(1, "ärzte"), (2, "krankenhaus"), (3, "fiebermessung")]);
+        let mut rolegraph = RoleGraph::new("medizin".into(), thesaurus).await.unwrap();
+
+        // Case folding and NFKC are on by default
+        assert_eq!(
+            rolegraph.find_matching_node_ids("ÄRZTE im KRANKENHAUS, ﬁebermessung"),
+            [1, 2, 3]
+        );
+        assert_eq!(rolegraph.find_matching_node_ids("Arzte"), Vec::<u64>::new());
+
+        rolegraph
+            .set_normalization(NormalizationOptions {
+                strip_diacritics: true,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rolegraph.find_matching_node_ids("Arzte"), [1]);
+
+        let restored =
+            RoleGraph::from_snapshot(rolegraph.to_snapshot(), rolegraph.automata.clone()).unwrap();
+        assert_eq!(restored.normalization(), rolegraph.normalization());
+        assert_eq!(restored.find_matching_node_ids("Arzte"), [1]);
+    }
+
+    #[test]
+    async fn test_word_boundary_match_mode() {
+        let thesaurus = thesaurus(&[(1, "ai"), (2, "rust")]);
+        let mut rolegraph = RoleGraph::new("tech".into(), thesaurus).await.unwrap();
+        let text = "Maintain trusted AI written in Rust";
+        assert_eq!(rolegraph.match_mode(), MatchMode::Substring);
+        assert_eq!(rolegraph.find_matching_node_ids(text), [1, 1, 2, 1, 2]);
+
+        rolegraph.set_match_mode(MatchMode::WordBoundary);
+        assert_eq!(rolegraph.find_matching_node_ids(text), [1, 2]);
+
+        let restored =
+            RoleGraph::from_snapshot(rolegraph.to_snapshot(), rolegraph.automata.clone()).unwrap();
+        assert_eq!(restored.match_mode(), MatchMode::WordBoundary);
+        assert_eq!(restored.find_matching_node_ids(text), [1, 2]);
+    }
+
+    #[test]
+    async fn test_from_automata() {
+        let thesaurus = load_sample_thesaurus().await;
+        let automata =
+            Arc::new(Automata::new(&thesaurus, NormalizationOptions::default()).unwrap());
+        let first = RoleGraph::from_automata("system operator".into(), automata.clone());
+        let second = RoleGraph::from_automata("engineer".into(), automata.clone());
+        assert!(Arc::ptr_eq(&first.automata, &second.automata));
+        assert_eq!(first.automata.thesaurus(), thesaurus);
+
+        let text = "Life cycle concepts and project direction";
+        let expected = RoleGraph::new("system operator".into(), thesaurus)
+            .await
+            .unwrap()
+            .find_matching_node_ids(text);
+        assert!(!expected.is_empty());
+        assert_eq!(first.find_matching_node_ids(text), expected);
+    }
+
+    #[test]
+    async fn test_magic_unpair() {
+        for (x, y) in [(0, 0), (1, 7), (7, 1), (123_456_789, 987_654_321)] {
+            assert_eq!(magic_unpair(magic_pair(x, y)), (x, y));
+        }
+    }
+
     #[test]
     async fn test_rolegraph() {
         let role = "system operator".to_string();
@@ -519,6 +1594,7 @@ mod tests {
             title: "Life cycle concepts and project direction".to_string(),
             body: query4.to_string(),
             description: None,
+            score_breakdown: None,
         };
         rolegraph.insert_document(&document_id4, document);
         log::debug!("Query graph");
@@ -0,0 +1,219 @@
+//! Snapshots of a `RoleGraph`
+//!
+//! A `RoleGraph` only lives in memory, so every document indexed into it is
+//! lost on restart. A `RoleGraphSnapshot` captures all nodes, edges and
+//! indexed documents of a graph, together with the content hash of its
+//! thesaurus. It is persisted through `terraphim_persistence` and turned
+//! back into a `RoleGraph` sharing an automata of the same thesaurus with
+//! [`RoleGraph::from_snapshot`].
+//!
+//! The snapshot format is versioned. Whenever the layout of the snapshot
+//! changes, bump [`SNAPSHOT_VERSION`], so that stale snapshots are rejected
+//! instead of being loaded into an inconsistent graph.
+
+use std::sync::Arc;
+
+use ahash::AHashMap;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use terraphim_automata::{Automata, MatchMode, NormalizationOptions};
+use terraphim_persistence::Persistable;
+use terraphim_types::{Edge, IndexedDocument, Node, RoleName};
+
+use crate::{CoOccurrenceWindow, Error, Result, RoleGraph};
+
+/// Current version of the snapshot format
+///
+/// Version 2 counts every co-occurrence in `Edge.rank` and `Edge.doc_hash`,
+/// which is required for removing documents again. Version 3 stores the
+/// content hash of the thesaurus instead of the thesaurus itself.
+pub const SNAPSHOT_VERSION: u32 = 3;
+
+/// Serializable state of a `RoleGraph`
+///
+/// Neither the [`Automata`] nor its thesaurus are part of the snapshot. The
+/// automata of the thesaurus is shared when the snapshot is restored, see
+/// [`RoleGraph::from_snapshot`].
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RoleGraphSnapshot {
+    /// Version of the snapshot format
+    pub version: u32,
+    /// The role of the graph
+    pub role: RoleName,
+    /// Content hash of the thesaurus the graph was built with, see
+    /// [`Automata::thesaurus_hash`]
+    pub thesaurus_hash: u64,
+    /// A mapping from node IDs to nodes
+    pub nodes: AHashMap<u64, Node>,
+    /// A mapping from edge IDs to edges
+    pub edges: AHashMap<u64, Edge>,
+    /// A mapping from document IDs to indexed documents
+    pub documents: AHashMap<String, IndexedDocument>,
+    /// Normalization of the text the graph was built with
+    #[serde(default)]
+    pub normalization: NormalizationOptions,
+    /// Match mode the graph was built with
+    #[serde(default)]
+    pub match_mode: MatchMode,
+    /// Co-occurrence window the graph was built with
+    #[serde(default)]
+    pub co_occurrence_window: CoOccurrenceWindow,
+}
+
+impl RoleGraphSnapshot {
+    /// Create a new, empty snapshot for the given role
+    pub fn new(role: RoleName) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            role,
+            thesaurus_hash: 0,
+            nodes: AHashMap::new(),
+            edges: AHashMap::new(),
+            documents: AHashMap::new(),
+            normalization: NormalizationOptions::default(),
+            match_mode: MatchMode::default(),
+            co_occurrence_window: CoOccurrenceWindow::default(),
+        }
+    }
+
+    /// Returns an error if the snapshot was written with a different version
+    /// of the snapshot format
+    fn check_version(&self) -> Result<()> {
+        if self.version != SNAPSHOT_VERSION {
+            return Err(Error::SnapshotVersion {
+                found: self.version,
+                expected: SNAPSHOT_VERSION,
+            });
+        }
+        Ok(())
+    }
+
+    /// Check if the snapshot contains any indexed state
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty() && self.edges.is_empty() && self.documents.is_empty()
+    }
+}
+
+impl RoleGraph {
+    /// Take a snapshot of the current state of the graph
+    pub fn to_snapshot(&self) -> RoleGraphSnapshot {
+        RoleGraphSnapshot {
+            version: SNAPSHOT_VERSION,
+            role: self.role.clone(),
+            thesaurus_hash: self.automata.thesaurus_hash(),
+            nodes: self.nodes.clone(),
+            edges: self.edges.clone(),
+            documents: self.documents.clone(),
+            normalization: self.normalization(),
+            match_mode: self.match_mode,
+            co_occurrence_window: self.co_occurrence_window,
+        }
+    }
+
+    /// Restore a `RoleGraph` from a snapshot, sharing the given automata
+    ///
+    /// Returns an error if the snapshot was written with a different
+    /// version of the snapshot format, or if the automata wasn't built from
+    /// the same thesaurus as the graph. The normalization of the automata
+    /// takes precedence over the one of the snapshot.
+    pub fn from_snapshot(snapshot: RoleGraphSnapshot, automata: Arc<Automata>) -> Result<Self> {
+        snapshot.check_version()?;
+        if snapshot.thesaurus_hash != automata.thesaurus_hash() {
+            return Err(Error::SnapshotThesaurus);
+        }
+        let mut rolegraph = RoleGraph::from_automata(snapshot.role, automata);
+        rolegraph.match_mode = snapshot.match_mode;
+        rolegraph.co_occurrence_window = snapshot.co_occurrence_window;
+        rolegraph.nodes = snapshot.nodes;
+        rolegraph.edges = snapshot.edges;
+        rolegraph.documents = snapshot.documents;
+        Ok(rolegraph)
+    }
+}
+
+#[async_trait]
+impl Persistable for RoleGraphSnapshot {
+    fn new(key: String) -> Self {
+        RoleGraphSnapshot::new(RoleName::new(&key))
+    }
+
+    /// returns key + .json
+    fn get_key(&self) -> String {
+        format!(
+            "rolegraph_{}.json",
+            self.normalize_key(self.role.as_lowercase())
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use terraphim_automata::{load_thesaurus, AutomataPath};
+    use terraphim_types::{Document, NormalizedTerm};
+
+    async fn sample_rolegraph() -> RoleGraph {
+        let thesaurus = load_thesaurus(&AutomataPath::local_example_full())
+            .await
+            .unwrap();
+        let mut rolegraph = RoleGraph::new("System Operator".into(), thesaurus)
+            .await
+            .unwrap();
+        let document = Document {
+            id: "document1".to_string(),
+            url: "/path/to/document1".to_string(),
+            title: "Life cycle concepts and project direction".to_string(),
+            body: "Trained operators and maintainers, some bingo words Paradigm Map and project planning".to_string(),
+            ..Default::default()
+        };
+        rolegraph.insert_document(&document.id.clone(), document);
+        rolegraph
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_roundtrip() {
+        let rolegraph = sample_rolegraph().await;
+        let snapshot = rolegraph.to_snapshot();
+        assert!(!snapshot.is_empty());
+        assert!(snapshot.documents.contains_key("document1"));
+
+        // Make sure the snapshot survives a trip through JSON
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let snapshot: RoleGraphSnapshot = serde_json::from_str(&json).unwrap();
+
+        let restored = RoleGraph::from_snapshot(snapshot, rolegraph.automata.clone()).unwrap();
+        assert_eq!(restored.to_snapshot(), rolegraph.to_snapshot());
+
+        let query = "Life cycle concepts and project direction";
+        let expected = rolegraph.query_graph(query, None, None).unwrap();
+        let results = restored.query_graph(query, None, None).unwrap();
+        assert_eq!(results.len(), expected.len());
+        assert_eq!(results[0].0, "document1");
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_version_mismatch() {
+        let rolegraph = sample_rolegraph().await;
+        let mut snapshot = rolegraph.to_snapshot();
+        snapshot.version = SNAPSHOT_VERSION + 1;
+        let result = RoleGraph::from_snapshot(snapshot, rolegraph.automata.clone());
+        assert!(matches!(result, Err(Error::SnapshotVersion { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_thesaurus_mismatch() {
+        let rolegraph = sample_rolegraph().await;
+        let mut thesaurus = rolegraph.automata.thesaurus();
+        thesaurus.insert("kafka".into(), NormalizedTerm::new(1, "kafka".into()));
+        let automata = Automata::new(&thesaurus, rolegraph.normalization()).unwrap();
+        let result = RoleGraph::from_snapshot(rolegraph.to_snapshot(), Arc::new(automata));
+        assert!(matches!(result, Err(Error::SnapshotThesaurus)));
+    }
+
+    #[test]
+    fn test_get_key() {
+        let snapshot = RoleGraphSnapshot::new("System Operator".into());
+        assert_eq!(snapshot.get_key(), "rolegraph_systemoperator.json");
+    }
+}
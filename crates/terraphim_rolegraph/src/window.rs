@@ -0,0 +1,120 @@
+//! Co-occurrence windows
+//!
+//! Concepts matched in a document are connected by an edge if they follow
+//! each other. A co-occurrence window limits how far apart they can be, so
+//! that concepts pages apart in a long document aren't connected.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::split_paragraphs;
+
+lazy_static! {
+    static ref PARAGRAPH_BREAK: Regex = Regex::new(r"\n\s*\n").unwrap();
+}
+
+/// Where consecutive matches of a document count as co-occurring
+///
+/// It is configured as e.g. `"sentence"` or `{ "tokens": 20 }`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CoOccurrenceWindow {
+    /// Anywhere in the document
+    #[default]
+    Document,
+    /// Within a paragraph, paragraphs are separated by blank lines
+    Paragraph,
+    /// Within a sentence, see [`split_paragraphs`]
+    Sentence,
+    /// Within the given number of consecutive words
+    Tokens(usize),
+}
+
+impl CoOccurrenceWindow {
+    /// Splits a text into the segments which are matched separately
+    ///
+    /// Token windows don't split the text, matches are compared by their
+    /// positions instead, see [`CoOccurrenceWindow::fits`].
+    pub fn segments(self, text: &str) -> Vec<&str> {
+        match self {
+            CoOccurrenceWindow::Document | CoOccurrenceWindow::Tokens(_) => vec![text],
+            CoOccurrenceWindow::Paragraph => PARAGRAPH_BREAK
+                .split(text)
+                .map(str::trim)
+                .filter(|paragraph| !paragraph.is_empty())
+                .collect(),
+            CoOccurrenceWindow::Sentence => split_paragraphs(text),
+        }
+    }
+
+    /// Whether two matches, given by their byte ranges in the text, fit into
+    /// the window
+    ///
+    /// `words` are the byte ranges of the words of the text, see [`words`].
+    /// Matches always fit into the other windows, as only matches within the
+    /// same segment are compared.
+    pub fn fits(
+        self,
+        words: &[(usize, usize)],
+        first: (usize, usize),
+        second: (usize, usize),
+    ) -> bool {
+        let CoOccurrenceWindow::Tokens(tokens) = self else {
+            return true;
+        };
+        // Count the words overlapping the span of both matches
+        let (start, end) = (first.0.min(second.0), first.1.max(second.1));
+        let first_word = words.partition_point(|&(_, word_end)| word_end <= start);
+        let last_word = words.partition_point(|&(word_start, _)| word_start < end);
+        last_word.saturating_sub(first_word) <= tokens
+    }
+}
+
+/// Byte ranges of the words of a text
+pub fn words(text: &str) -> Vec<(usize, usize)> {
+    text.unicode_word_indices()
+        .map(|(start, word)| (start, start + word.len()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_segments() {
+        let text = "Rust is fast. Tokio is async.\n\n  \nHelm charts deploy. Kubernetes runs them.";
+        assert_eq!(CoOccurrenceWindow::Document.segments(text), vec![text]);
+        assert_eq!(
+            CoOccurrenceWindow::Paragraph.segments(text),
+            vec![
+                "Rust is fast. Tokio is async.",
+                "Helm charts deploy. Kubernetes runs them."
+            ]
+        );
+        assert_eq!(CoOccurrenceWindow::Sentence.segments(text).len(), 4);
+    }
+
+    #[test]
+    fn test_fits() {
+        let text = "rust is fast, but the tokio runtime is async";
+        let words = words(text);
+        let rust = (0, 4);
+        let tokio = (22, 27);
+        let runtime = (22, 35);
+        assert!(CoOccurrenceWindow::Sentence.fits(&words, rust, tokio));
+        assert!(CoOccurrenceWindow::Tokens(6).fits(&words, rust, tokio));
+        assert!(!CoOccurrenceWindow::Tokens(5).fits(&words, rust, tokio));
+        assert!(!CoOccurrenceWindow::Tokens(6).fits(&words, rust, runtime));
+        assert!(CoOccurrenceWindow::Tokens(2).fits(&words, tokio, runtime));
+    }
+
+    #[test]
+    fn test_serde() {
+        let window: CoOccurrenceWindow = serde_json::from_str(r#"{"tokens": 20}"#).unwrap();
+        assert_eq!(window, CoOccurrenceWindow::Tokens(20));
+        let window: CoOccurrenceWindow = serde_json::from_str(r#""paragraph""#).unwrap();
+        assert_eq!(window, CoOccurrenceWindow::Paragraph);
+    }
+}
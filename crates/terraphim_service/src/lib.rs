@@ -1,12 +1,13 @@
 use ahash::AHashMap;
-use terraphim_automata::{load_thesaurus, AutomataPath};
+use terraphim_automata::{apply_corrections, load_thesaurus, AutomataPath};
 use terraphim_config::{ConfigState, Role};
 use terraphim_middleware::thesaurus::{self, build_thesaurus_from_haystack};
 use terraphim_persistence::error;
 use terraphim_persistence::Persistable;
-use terraphim_rolegraph::{RoleGraph, RoleGraphSync};
+use terraphim_rolegraph::{ExportFormat, RoleGraph, RoleGraphSync};
 use terraphim_types::{
-    Document, Index, IndexedDocument, RelevanceFunction, RoleName, SearchQuery, Thesaurus,
+    ConnectedConcept, Correction, Document, Explanation, Index, IndexedDocument, Neighbour,
+    NormalizedTerm, RelevanceFunction, RoleName, SearchQuery, Suggestion, Thesaurus,
 };
 mod score;
 
@@ -23,10 +24,33 @@ pub enum ServiceError {
 
     #[error("Config error: {0}")]
     Config(String),
+
+    #[error("Role `{0}` doesn't have a knowledge graph")]
+    RoleGraphNotFound(RoleName),
+
+    #[error("Concept `{0}` not found")]
+    ConceptNotFound(String),
+
+    #[error("RoleGraph error: {0}")]
+    RoleGraph(#[from] terraphim_rolegraph::Error),
 }
 
 pub type Result<T> = std::result::Result<T, ServiceError>;
 
+/// Sets the rank of sorted documents, from the number of documents for the
+/// first one down to 1 for the last one
+fn rank_documents(documents: Vec<Document>) -> Vec<Document> {
+    let total_length = documents.len();
+    documents
+        .into_iter()
+        .enumerate()
+        .map(|(idx, mut document)| {
+            document.rank = Some((total_length - idx).try_into().unwrap());
+            document
+        })
+        .collect()
+}
+
 pub struct TerraphimService {
     config_state: ConfigState,
 }
@@ -68,7 +92,8 @@ impl<'a> TerraphimService {
         println!("Role keys {:?}", self.config_state.roles.keys());
         let mut rolegraphs = self.config_state.roles.clone();
         if let Some(rolegraph_value) = rolegraphs.get(role_name) {
-            let mut thesaurus_result = rolegraph_value.lock().await.thesaurus.clone().load().await;
+            let name = rolegraph_value.lock().await.automata.name().to_string();
+            let mut thesaurus_result = Thesaurus::new(name).load().await;
             match thesaurus_result {
                 Ok(thesaurus) => {
                     println!("Thesaurus loaded: {:#?}", thesaurus);
@@ -101,6 +126,21 @@ impl<'a> TerraphimService {
         Ok(document)
     }
 
+    /// Update document
+    ///
+    /// Replaces the previous version of the document in all rolegraphs
+    pub async fn update_document(&mut self, document: Document) -> Result<Document> {
+        self.config_state.update_in_roles(&document).await?;
+        Ok(document)
+    }
+
+    /// Delete document
+    ///
+    /// Returns `false` if the document isn't part of any rolegraph
+    pub async fn delete_document(&mut self, document_id: &str) -> Result<bool> {
+        Ok(self.config_state.remove_from_roles(document_id).await)
+    }
+
     /// Get the role for the given search query
     async fn get_search_role(&self, search_query: &SearchQuery) -> Result<Role> {
         let search_role = match &search_query.role {
@@ -118,8 +158,44 @@ impl<'a> TerraphimService {
         Ok(role)
     }
 
+    /// Correct the misspelled words of the search term to concepts of the
+    /// knowledge graph of the search role
+    ///
+    /// Returns the corrected search query together with the corrections, or
+    /// the unchanged query and no corrections if fuzzy matching is disabled
+    /// for the role.
+    pub async fn correct_search_query(
+        &self,
+        search_query: &SearchQuery,
+    ) -> Result<(SearchQuery, Vec<Correction>)> {
+        let role = self.get_search_role(search_query).await?;
+        let corrections = self
+            .config_state
+            .correct_search_term(search_query, &role)
+            .await;
+        if corrections.is_empty() {
+            return Ok((search_query.clone(), corrections));
+        }
+        let search_term = apply_corrections(search_query.search_term.as_str(), &corrections);
+        log::debug!(
+            "Corrected search term `{}` to `{search_term}`",
+            search_query.search_term
+        );
+        Ok((search_query.with_search_term(&search_term), corrections))
+    }
+
     /// Search for documents in the haystacks
+    ///
+    /// If fuzzy matching is enabled for the role, misspelled words of the
+    /// search term are corrected first, see [`Self::correct_search_query`].
     pub async fn search(&mut self, search_query: &SearchQuery) -> Result<Vec<Document>> {
+        let (search_query, _) = self.correct_search_query(search_query).await?;
+        self.search_corrected(&search_query).await
+    }
+
+    /// Search for documents in the haystacks with a search query which was
+    /// already corrected, see [`Self::correct_search_query`]
+    pub async fn search_corrected(&mut self, search_query: &SearchQuery) -> Result<Vec<Document>> {
         // Get the role from the config
         log::debug!("Role for searching: {:?}", search_query.role);
         let role = self.get_search_role(search_query).await?;
@@ -138,15 +214,41 @@ impl<'a> TerraphimService {
                 log::debug!("Sorting documents by relevance");
                 // Sort the documents by relevance
                 let documents = score::sort_documents(search_query, documents);
-                let total_length = documents.len();
-                let mut docs_ranked = Vec::new();
-                for (idx, doc) in documents.iter().enumerate() {
-                    let document: &mut terraphim_types::Document = &mut doc.clone();
-                    let rank = (total_length - idx).try_into().unwrap();
-                    document.rank = Some(rank);
-                    docs_ranked.push(document.clone());
-                }
-                Ok(docs_ranked)
+                Ok(rank_documents(documents))
+            }
+            RelevanceFunction::Hybrid => {
+                log::debug!("Searching haystack with hybrid scorer");
+                self.build_thesaurus(search_query).await?;
+                // Offset and limit are applied to the combined ranking
+                let graph_query = SearchQuery {
+                    skip: None,
+                    limit: None,
+                    ..search_query.clone()
+                };
+                let scored_index_docs: Vec<IndexedDocument> = self
+                    .config_state
+                    .search_indexed_documents(&graph_query, &role)
+                    .await;
+
+                let documents = score::sort_documents_hybrid(
+                    search_query,
+                    index.get_all_documents(),
+                    scored_index_docs,
+                    role.hybrid_weights(),
+                );
+                let documents = rank_documents(documents)
+                    .into_iter()
+                    .skip(search_query.skip.unwrap_or(0))
+                    .take(search_query.limit.unwrap_or(usize::MAX))
+                    .collect();
+                Ok(documents)
+            }
+            RelevanceFunction::BM25 => {
+                log::debug!("Searching haystack with BM25 scorer");
+
+                let documents = index.get_all_documents();
+                let documents = score::sort_documents_by_bm25(search_query, documents);
+                Ok(rank_documents(documents))
             }
             RelevanceFunction::TerraphimGraph => {
                 self.build_thesaurus(search_query).await?;
@@ -167,6 +269,125 @@ impl<'a> TerraphimService {
         }
     }
 
+    /// Search for documents and explain their ranking
+    ///
+    /// Returns the same documents as [`Self::search`], each together with
+    /// the concepts, edges and rank contributions which led to its graph
+    /// rank. Documents which aren't part of the rolegraph of the role get
+    /// an empty explanation.
+    pub async fn explain(
+        &mut self,
+        search_query: &SearchQuery,
+    ) -> Result<Vec<(Document, Explanation)>> {
+        let (search_query, _) = self.correct_search_query(search_query).await?;
+        self.explain_corrected(&search_query).await
+    }
+
+    /// Search for documents and explain their ranking with a search query
+    /// which was already corrected, see [`Self::correct_search_query`]
+    pub async fn explain_corrected(
+        &mut self,
+        search_query: &SearchQuery,
+    ) -> Result<Vec<(Document, Explanation)>> {
+        let documents = self.search_corrected(search_query).await?;
+        let role = self.get_search_role(search_query).await?;
+        let mut explanations: AHashMap<String, Explanation> = self
+            .config_state
+            .explain_indexed_documents(search_query, &role)
+            .await
+            .into_iter()
+            .map(|explanation| (explanation.document_id.clone(), explanation))
+            .collect();
+
+        Ok(documents
+            .into_iter()
+            .map(|document| {
+                let explanation = explanations
+                    .remove(&document.id)
+                    .unwrap_or_else(|| Explanation::new(document.id.clone()));
+                (document, explanation)
+            })
+            .collect())
+    }
+
+    /// Get the rolegraph of the given role
+    fn rolegraph(&self, role_name: &RoleName) -> Result<RoleGraphSync> {
+        self.config_state
+            .roles
+            .get(role_name)
+            .cloned()
+            .ok_or_else(|| ServiceError::RoleGraphNotFound(role_name.clone()))
+    }
+
+    /// List the concepts co-occurring with a concept in the knowledge graph
+    /// of the role
+    ///
+    /// The concept can be given by its ID or by one of its synonyms.
+    pub async fn neighbours(&self, role_name: &RoleName, concept: &str) -> Result<Vec<Neighbour>> {
+        let rolegraph = self.rolegraph(role_name)?;
+        let rolegraph = rolegraph.lock().await;
+        let concept = rolegraph
+            .find_concept(concept)
+            .ok_or_else(|| ServiceError::ConceptNotFound(concept.to_string()))?;
+        Ok(rolegraph.neighbours(concept.id))
+    }
+
+    /// Find the shortest path between two concepts in the knowledge graph of
+    /// the role
+    ///
+    /// Returns `None` if the concepts aren't connected.
+    pub async fn shortest_path(
+        &self,
+        role_name: &RoleName,
+        from: &str,
+        to: &str,
+    ) -> Result<Option<Vec<NormalizedTerm>>> {
+        let rolegraph = self.rolegraph(role_name)?;
+        let rolegraph = rolegraph.lock().await;
+        let find = |concept: &str| {
+            rolegraph
+                .find_concept(concept)
+                .ok_or_else(|| ServiceError::ConceptNotFound(concept.to_string()))
+        };
+        let (from, to) = (find(from)?, find(to)?);
+        Ok(rolegraph.shortest_path(from.id, to.id))
+    }
+
+    /// List the most connected concepts in the knowledge graph of the role
+    pub async fn top_concepts(
+        &self,
+        role_name: &RoleName,
+        limit: usize,
+    ) -> Result<Vec<ConnectedConcept>> {
+        let rolegraph = self.rolegraph(role_name)?;
+        let rolegraph = rolegraph.lock().await;
+        Ok(rolegraph.top_concepts(limit))
+    }
+
+    /// Suggest up to `limit` synonyms of the knowledge graph of the role
+    /// completing what was typed
+    pub async fn autocomplete(
+        &self,
+        role_name: &RoleName,
+        query: &str,
+        limit: usize,
+    ) -> Result<Vec<Suggestion>> {
+        let rolegraph = self.rolegraph(role_name)?;
+        let rolegraph = rolegraph.lock().await;
+        Ok(rolegraph.autocomplete(query, limit))
+    }
+
+    /// Export the knowledge graph of the role
+    pub async fn export_rolegraph(
+        &self,
+        role_name: &RoleName,
+        format: ExportFormat,
+    ) -> Result<String> {
+        let rolegraph = self.rolegraph(role_name)?;
+        let rolegraph = rolegraph.lock().await;
+        Ok(rolegraph.export(format)?)
+    }
+
     /// Fetch the current config
     pub async fn fetch_config(&self) -> terraphim_config::Config {
         let current_config = self.config_state.config.lock().await;
@@ -186,3 +407,75 @@ impl<'a> TerraphimService {
         Ok(config)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use terraphim_config::{ConfigBuilder, Haystack, ServiceType, FUZZY_KEY};
+
+    #[tokio::test]
+    /// Test that correcting a misspelled boolean query keeps its operators
+    async fn test_search_corrects_boolean_query() {
+        let haystack = tempfile::tempdir().unwrap();
+        fs::write(
+            haystack.path().join("kafka.md"),
+            "Kafka is an event store.\n",
+        )
+        .unwrap();
+        fs::write(
+            haystack.path().join("zookeeper.md"),
+            "Kafka used to depend on Zookeeper.\n",
+        )
+        .unwrap();
+
+        let mut extra = AHashMap::new();
+        extra.insert(
+            FUZZY_KEY.to_string(),
+            serde_json::json!({ "metric": "levenshtein", "max_edits": 1 }),
+        );
+        let role = Role {
+            shortname: Some("fuzzy".into()),
+            name: "Fuzzy".into(),
+            relevance_function: RelevanceFunction::TitleScorer,
+            theme: "lumen".to_string(),
+            kg: None,
+            haystacks: vec![Haystack {
+                path: haystack.path().to_path_buf(),
+                service: ServiceType::Native,
+            }],
+            extra,
+        };
+        let mut config = ConfigBuilder::new()
+            .add_role("Fuzzy", role.clone())
+            .default_role("Fuzzy")
+            .unwrap()
+            .build()
+            .unwrap();
+        let mut config_state = ConfigState::new(&mut config).await.unwrap();
+
+        let mut thesaurus = Thesaurus::new("fuzzy".to_string());
+        for (id, term) in [(1, "kafka"), (2, "zookeeper")] {
+            thesaurus.insert(term.into(), NormalizedTerm::new(id, term.into()));
+        }
+        let mut rolegraph = RoleGraph::new(role.name.clone(), thesaurus).await.unwrap();
+        rolegraph.set_fuzzy(role.fuzzy());
+        config_state
+            .roles
+            .insert(role.name.clone(), RoleGraphSync::from(rolegraph));
+
+        let search_query: SearchQuery =
+            serde_json::from_str(r#"{"search_term": "Kafka AND NOT zookeper"}"#).unwrap();
+        let mut service = TerraphimService::new(config_state);
+        let (corrected, corrections) = service.correct_search_query(&search_query).await.unwrap();
+        assert_eq!(corrections.len(), 1);
+        assert_eq!(corrections[0].original, "zookeper");
+        assert_eq!(corrected.search_term.as_str(), "Kafka AND NOT zookeeper");
+
+        let documents = service.search(&search_query).await.unwrap();
+        assert_eq!(documents.len(), 1);
+        assert_eq!(documents[0].title, "kafka");
+    }
+}
@@ -0,0 +1,123 @@
+use ahash::{AHashMap, AHashSet};
+use terraphim_types::Document;
+
+/// Controls how quickly the score saturates with the term frequency
+const K1: f64 = 1.2;
+/// Controls how much the score is normalized by the document length
+const B: f64 = 0.75;
+
+/// An inverted index over the bodies of a set of documents
+///
+/// It maps every term to the documents it occurs in, which is all that is
+/// needed to rank the documents by full-text relevance for a query.
+#[derive(Debug, Default)]
+pub struct InvertedIndex {
+    /// For every term, the number of occurrences in each document
+    postings: AHashMap<String, AHashMap<usize, u32>>,
+    /// Number of terms in each document
+    lengths: Vec<usize>,
+}
+
+impl InvertedIndex {
+    /// Build the index over the bodies of the given documents
+    ///
+    /// Documents are referred to by their position in `documents`.
+    pub fn new(documents: &[Document]) -> Self {
+        let mut index = InvertedIndex::default();
+        for (position, document) in documents.iter().enumerate() {
+            let terms = tokenize(&document.body);
+            index.lengths.push(terms.len());
+            for term in terms {
+                let frequencies = index.postings.entry(term).or_default();
+                *frequencies.entry(position).or_insert(0) += 1;
+            }
+        }
+        index
+    }
+
+    /// Returns the number of indexed documents
+    pub fn len(&self) -> usize {
+        self.lengths.len()
+    }
+
+    /// Returns true if no documents are indexed
+    pub fn is_empty(&self) -> bool {
+        self.lengths.is_empty()
+    }
+
+    /// Score every document for the given query terms with Okapi BM25
+    ///
+    /// Returns one score per document, in the order the documents were
+    /// indexed. Documents without any of the query terms score `0`.
+    pub fn score(&self, query: &[String]) -> Vec<f64> {
+        let mut scores = vec![0.0; self.len()];
+        if self.is_empty() {
+            return scores;
+        }
+        let query: AHashSet<&String> = query.iter().collect();
+        let documents = self.len() as f64;
+        let average_length = self.lengths.iter().sum::<usize>() as f64 / documents;
+
+        for term in &query {
+            let Some(frequencies) = self.postings.get(*term) else {
+                continue;
+            };
+            let document_frequency = frequencies.len() as f64;
+            let idf =
+                (1.0 + (documents - document_frequency + 0.5) / (document_frequency + 0.5)).ln();
+            for (&position, &frequency) in frequencies {
+                let frequency = frequency as f64;
+                let length = self.lengths[position] as f64;
+                let norm = K1 * (1.0 - B + B * length / average_length.max(1.0));
+                scores[position] += idf * frequency * (K1 + 1.0) / (frequency + norm);
+            }
+        }
+        scores
+    }
+}
+
+/// Splits a text into lowercase terms at every character which is not
+/// alphanumeric
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(|term| term.to_lowercase())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(body: &str) -> Document {
+        Document {
+            body: body.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_tokenize() {
+        assert_eq!(
+            tokenize("Kafka, Zookeeper & the JVM!"),
+            ["kafka", "zookeeper", "the", "jvm"]
+        );
+    }
+
+    #[test]
+    fn test_bm25() {
+        let index = InvertedIndex::new(&[
+            document("Kafka is a distributed event store. Kafka scales."),
+            document("Kafka is mentioned once in a much longer text about many other things"),
+            document("Nothing relevant here"),
+        ]);
+        let scores = index.score(&tokenize("kafka"));
+        assert!(scores[0] > scores[1]);
+        assert!(scores[1] > 0.0);
+        assert_eq!(scores[2], 0.0);
+
+        // Rare terms weigh more than common ones
+        let scores = index.score(&tokenize("kafka relevant"));
+        assert!(scores[2] > scores[1]);
+    }
+}
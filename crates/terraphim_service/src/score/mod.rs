@@ -2,16 +2,21 @@ use std::f64;
 use std::fmt;
 use std::result;
 
+mod bm25;
 mod names;
 mod scored;
 
 use crate::error::Result;
+use ahash::AHashMap;
+use bm25::{tokenize, InvertedIndex};
 use names::NameScorer;
 use scored::{Scored, SearchResults};
 use serde::{Serialize, Serializer};
 
+use terraphim_config::HybridWeights;
 use terraphim_types::Document;
-use terraphim_types::SearchQuery;
+use terraphim_types::Query as SearchTerm;
+use terraphim_types::{IndexedDocument, ScoreBreakdown, SearchQuery};
 
 /// Sort the documents by relevance.
 ///
@@ -37,6 +42,114 @@ pub fn sort_documents(search_query: &SearchQuery, documents: Vec<Document>) -> V
         .collect()
 }
 
+/// Sort the documents by the Okapi BM25 relevance of their bodies.
+///
+/// The terms and phrases of the search query which are not negated are
+/// used as query terms.
+pub fn sort_documents_by_bm25(
+    search_query: &SearchQuery,
+    documents: Vec<Document>,
+) -> Vec<Document> {
+    log::debug!("Sorting documents by BM25 relevance");
+    let terms = query_terms(search_query);
+    let index = InvertedIndex::new(&documents);
+    let scores: AHashMap<String, f64> = documents
+        .iter()
+        .map(|document| document.id.clone())
+        .zip(index.score(&terms))
+        .collect();
+
+    let mut results = SearchResults::new();
+    for document in documents {
+        results.push(Scored::new(document));
+    }
+    results.rescore(|document| scores.get(&document.id).copied().unwrap_or_default());
+    results.normalize();
+    results
+        .into_vec()
+        .into_iter()
+        .map(Scored::into_value)
+        .collect()
+}
+
+/// Sort the documents by a weighted combination of their rank in the
+/// knowledge graph and the lexical similarity of their title.
+///
+/// Both components are normalized to `[0, 1]` before they are combined, and
+/// every document carries the breakdown of its score. Documents which aren't
+/// part of the graph have a graph score of `0`.
+pub fn sort_documents_hybrid(
+    search_query: &SearchQuery,
+    documents: Vec<Document>,
+    indexed_documents: Vec<IndexedDocument>,
+    weights: HybridWeights,
+) -> Vec<Document> {
+    log::debug!("Sorting documents by hybrid relevance with weights {weights:?}");
+    let indexed_documents: AHashMap<String, IndexedDocument> = indexed_documents
+        .into_iter()
+        .map(|document| (document.id.clone(), document))
+        .collect();
+    let max_graph_rank = indexed_documents
+        .values()
+        .map(|document| document.rank)
+        .max()
+        .unwrap_or_default();
+
+    let query = Query::new(search_query.search_term.as_str()).similarity(Similarity::Levenshtein);
+    let mut lexical = Scorer::new().score_documents(&query, documents).unwrap();
+    lexical.normalize();
+
+    let total_weight = weights.graph + weights.lexical;
+    let mut results = SearchResults::new();
+    for scored in lexical.into_vec() {
+        let (lexical, mut document) = scored.into_pair();
+        let graph = match indexed_documents.get(&document.id) {
+            Some(indexed) if max_graph_rank > 0 => {
+                document.tags = Some(indexed.tags.clone());
+                indexed.rank as f64 / max_graph_rank as f64
+            }
+            _ => 0.0,
+        };
+        let combined = if total_weight > 0.0 {
+            (weights.graph * graph + weights.lexical * lexical) / total_weight
+        } else {
+            0.0
+        };
+        document.score_breakdown = Some(ScoreBreakdown {
+            graph,
+            lexical,
+            combined,
+        });
+        results.push(Scored::new(document));
+    }
+    results.rescore(|document| {
+        document
+            .score_breakdown
+            .map(|breakdown| breakdown.combined)
+            .unwrap_or_default()
+    });
+    results
+        .into_vec()
+        .into_iter()
+        .map(Scored::into_value)
+        .collect()
+}
+
+/// Extracts the terms to rank documents by from the search query
+fn query_terms(search_query: &SearchQuery) -> Vec<String> {
+    match search_query.query() {
+        Ok(query) => query
+            .positive_leaves()
+            .into_iter()
+            .flat_map(|leaf| match leaf {
+                SearchTerm::Term(text) | SearchTerm::Phrase(text) => tokenize(text),
+                _ => Vec::new(),
+            })
+            .collect(),
+        Err(_) => tokenize(search_query.search_term.as_str()),
+    }
+}
+
 #[derive(Debug)]
 pub struct Scorer {}
 
@@ -266,3 +379,73 @@ impl fmt::Display for Similarity {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn document(id: &str, title: &str) -> Document {
+        Document {
+            id: id.to_string(),
+            title: title.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn indexed_document(id: &str, rank: u64) -> IndexedDocument {
+        IndexedDocument {
+            id: id.to_string(),
+            matched_edges: Vec::new(),
+            rank,
+            tags: vec!["kafka".to_string()],
+            nodes: Vec::new(),
+        }
+    }
+
+    fn hybrid_ids(weights: HybridWeights) -> Vec<String> {
+        let search_query = SearchQuery {
+            search_term: "kafka".into(),
+            ..Default::default()
+        };
+        // The title of `graph` is far off, but it ranks high in the graph
+        let documents = vec![
+            document("graph", "Distributed event stores"),
+            document("title", "Kafka"),
+        ];
+        let indexed_documents = vec![indexed_document("graph", 10), indexed_document("title", 1)];
+        sort_documents_hybrid(&search_query, documents, indexed_documents, weights)
+            .into_iter()
+            .map(|document| document.id)
+            .collect()
+    }
+
+    #[test]
+    fn test_sort_documents_hybrid() {
+        let graph_only = HybridWeights {
+            graph: 1.0,
+            lexical: 0.0,
+        };
+        let lexical_only = HybridWeights {
+            graph: 0.0,
+            lexical: 1.0,
+        };
+        assert_eq!(hybrid_ids(graph_only), ["graph", "title"]);
+        assert_eq!(hybrid_ids(lexical_only), ["title", "graph"]);
+
+        let search_query = SearchQuery {
+            search_term: "kafka".into(),
+            ..Default::default()
+        };
+        let documents = sort_documents_hybrid(
+            &search_query,
+            vec![document("title", "kafka")],
+            vec![indexed_document("title", 5)],
+            HybridWeights::default(),
+        );
+        let breakdown = documents[0].score_breakdown.unwrap();
+        assert_eq!(breakdown.graph, 1.0);
+        assert_eq!(breakdown.lexical, 1.0);
+        assert_eq!(breakdown.combined, 1.0);
+        assert_eq!(documents[0].tags, Some(vec!["kafka".to_string()]));
+    }
+}
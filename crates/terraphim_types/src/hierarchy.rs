@@ -0,0 +1,200 @@
+//! Relations between the concepts of a thesaurus
+//!
+//! Concepts can be related like in SKOS: a concept has broader concepts
+//! (e.g. "database" for "postgres"), narrower concepts and otherwise related
+//! concepts. Relations are stored as they were declared, e.g. by the file of
+//! a concept, and their inverse is derived when looking them up, so that a
+//! concept can be updated without losing what other concepts declared.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, VecDeque};
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use crate::Thesaurus;
+
+/// How a concept is related to another one
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationKind {
+    /// The other concept is more general, e.g. "database" for "postgres"
+    Broader,
+    /// The other concept is more specific, e.g. "postgres" for "database"
+    Narrower,
+    /// The other concept is associated, but not more general or specific
+    Related,
+}
+
+impl RelationKind {
+    /// The relation seen from the other concept
+    pub fn inverse(self) -> Self {
+        match self {
+            RelationKind::Broader => RelationKind::Narrower,
+            RelationKind::Narrower => RelationKind::Broader,
+            RelationKind::Related => RelationKind::Related,
+        }
+    }
+}
+
+impl Display for RelationKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            RelationKind::Broader => write!(f, "broader"),
+            RelationKind::Narrower => write!(f, "narrower"),
+            RelationKind::Related => write!(f, "related"),
+        }
+    }
+}
+
+impl FromStr for RelationKind {
+    type Err = String;
+
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "broader" => Ok(RelationKind::Broader),
+            "narrower" => Ok(RelationKind::Narrower),
+            "related" => Ok(RelationKind::Related),
+            _ => Err(format!(
+                "Unknown relation `{kind}`, expected broader, narrower or related"
+            )),
+        }
+    }
+}
+
+/// A declared relation from one concept to another, by their IDs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct ConceptRelation {
+    pub from: u64,
+    pub kind: RelationKind,
+    pub to: u64,
+}
+
+impl Thesaurus {
+    /// Declares a relation from one concept to another
+    ///
+    /// Relations of a concept to itself are ignored.
+    pub fn add_relation(&mut self, from: u64, kind: RelationKind, to: u64) {
+        if from != to {
+            self.relations.insert(ConceptRelation { from, kind, to });
+        }
+    }
+
+    /// All declared relations, ordered by the concept they are declared for
+    pub fn relations(&self) -> impl Iterator<Item = &ConceptRelation> {
+        self.relations.iter()
+    }
+
+    /// Retains only the relations specified by the predicate
+    pub fn retain_relations<F>(&mut self, f: F)
+    where
+        F: FnMut(&ConceptRelation) -> bool,
+    {
+        self.relations.retain(f);
+    }
+
+    /// IDs of the concepts related to a concept in the given way
+    ///
+    /// Both the relations declared for the concept and the inverse of the
+    /// relations declared for other concepts count, e.g. a concept is
+    /// narrower than "database" if it declares "database" as broader, or
+    /// if "database" declares it as narrower.
+    pub fn related_concepts(&self, id: u64, kind: RelationKind) -> BTreeSet<u64> {
+        related_concepts(&self.relations, id, kind)
+    }
+
+    /// IDs of all concepts below a concept, e.g. "postgres" and "pgvector"
+    /// for "database"
+    ///
+    /// The concept itself isn't included, even if the hierarchy has cycles.
+    pub fn narrower_transitive(&self, id: u64) -> BTreeSet<u64> {
+        narrower_transitive(&self.relations, id)
+    }
+}
+
+/// IDs of the concepts related to a concept in the given way, see
+/// [`Thesaurus::related_concepts`]
+///
+/// This works on any collection of relations, e.g. the relations of a
+/// prebuilt automata.
+pub fn related_concepts<'a>(
+    relations: impl IntoIterator<Item = &'a ConceptRelation>,
+    id: u64,
+    kind: RelationKind,
+) -> BTreeSet<u64> {
+    let inverse = kind.inverse();
+    relations
+        .into_iter()
+        .filter_map(|relation| {
+            if relation.from == id && relation.kind == kind {
+                Some(relation.to)
+            } else if relation.to == id && relation.kind == inverse {
+                Some(relation.from)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// IDs of all concepts below a concept, see
+/// [`Thesaurus::narrower_transitive`]
+pub fn narrower_transitive<'a>(
+    relations: impl IntoIterator<Item = &'a ConceptRelation> + Copy,
+    id: u64,
+) -> BTreeSet<u64> {
+    let mut narrower = BTreeSet::new();
+    let mut queue = VecDeque::from([id]);
+    while let Some(current) = queue.pop_front() {
+        for next in related_concepts(relations, current, RelationKind::Narrower) {
+            if next != id && narrower.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+    narrower
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relations() {
+        let mut thesaurus = Thesaurus::new("databases".to_string());
+        // database > sql > postgres, and postgres > database as a cycle
+        thesaurus.add_relation(2, RelationKind::Broader, 1);
+        thesaurus.add_relation(1, RelationKind::Narrower, 2);
+        thesaurus.add_relation(3, RelationKind::Broader, 2);
+        thesaurus.add_relation(3, RelationKind::Narrower, 1);
+        thesaurus.add_relation(3, RelationKind::Related, 4);
+        thesaurus.add_relation(4, RelationKind::Related, 4);
+        assert_eq!(thesaurus.relations().count(), 5);
+
+        assert_eq!(
+            thesaurus.related_concepts(1, RelationKind::Narrower),
+            BTreeSet::from([2])
+        );
+        assert_eq!(
+            thesaurus.related_concepts(1, RelationKind::Broader),
+            BTreeSet::from([3])
+        );
+        assert_eq!(
+            thesaurus.related_concepts(4, RelationKind::Related),
+            BTreeSet::from([3])
+        );
+        assert_eq!(thesaurus.narrower_transitive(2), BTreeSet::from([1, 3]));
+        assert_eq!(thesaurus.narrower_transitive(4), BTreeSet::new());
+
+        // Relations survive serialization
+        let json = serde_json::to_string(&thesaurus).unwrap();
+        let restored: Thesaurus = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, thesaurus);
+
+        thesaurus.retain_relations(|relation| relation.from != 3);
+        assert_eq!(thesaurus.narrower_transitive(1), BTreeSet::from([2]));
+        assert_eq!(
+            thesaurus.related_concepts(4, RelationKind::Related),
+            BTreeSet::new()
+        );
+    }
+}
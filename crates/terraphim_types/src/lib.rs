@@ -1,13 +1,22 @@
 use ahash::AHashMap;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::hash_map::Iter;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::fmt::{self, Display, Formatter};
 use std::iter::IntoIterator;
 use std::ops::{Deref, DerefMut};
 
 use std::str::FromStr;
 
+pub mod hierarchy;
+pub mod query;
+pub mod thesaurus;
+pub use hierarchy::{ConceptRelation, RelationKind};
+pub use query::{Query, QueryError};
+pub use thesaurus::{
+    MergeConflict, MergeError, MergePolicy, Remapped, ThesaurusDiff, ThesaurusIssue,
+};
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
 pub struct RoleName {
     pub original: String,
@@ -130,11 +139,24 @@ pub struct NormalizedTerm {
     // This field is currently called `nterm` in the JSON
     #[serde(rename = "nterm")]
     pub value: NormalizedTermValue,
+    /// Where the concept is described, e.g. the page of the knowledge graph
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
 }
 
 impl NormalizedTerm {
     pub fn new(id: u64, value: NormalizedTermValue) -> Self {
-        Self { id, value }
+        Self {
+            id,
+            value,
+            url: None,
+        }
+    }
+
+    /// Set the URL of the concept
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.url = Some(url.into());
+        self
     }
 }
 
@@ -197,6 +219,23 @@ pub struct Document {
     pub tags: Option<Vec<String>>,
     /// Rank of the document in the search results
     pub rank: Option<u64>,
+    /// Breakdown of the score, if the document was ranked by a hybrid
+    /// relevance function
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score_breakdown: Option<ScoreBreakdown>,
+}
+
+/// Per-component score of a document ranked by a hybrid relevance function
+///
+/// All scores are normalized to the range `[0, 1]`.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScoreBreakdown {
+    /// Rank of the document in the knowledge graph
+    pub graph: f64,
+    /// Lexical similarity of the document title with the search term
+    pub lexical: f64,
+    /// Weighted combination of the other scores
+    pub combined: f64,
 }
 
 impl fmt::Display for Document {
@@ -270,6 +309,59 @@ impl Node {
     // }
 }
 
+/// A concept which co-occurs with another concept in the `RoleGraph`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Neighbour {
+    /// The neighbouring concept
+    pub concept: NormalizedTerm,
+    /// IDs of the edges between both concepts, one per direction
+    pub edges: Vec<u64>,
+    /// Weight of the connection, the number of co-occurrences of both
+    /// concepts
+    pub weight: u64,
+}
+
+/// A concept together with the number of concepts it is connected to
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ConnectedConcept {
+    /// The concept
+    pub concept: NormalizedTerm,
+    /// Rank of the node, the number of co-occurrences with any concept
+    pub rank: u64,
+    /// Number of edges of the node
+    pub degree: usize,
+}
+
+/// A synonym of the thesaurus suggested while typing
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Suggestion {
+    /// The synonym completing what was typed
+    pub term: NormalizedTermValue,
+    /// The concept of the synonym
+    pub concept: NormalizedTerm,
+    /// Rank of the concept in the knowledge graph
+    pub rank: u64,
+    /// Whether the synonym only matches after fixing a typo
+    pub fuzzy: bool,
+}
+
+/// A misspelled part of a text corrected to a synonym of the thesaurus
+///
+/// Used to show "did you mean" hints for queries.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Correction {
+    /// The text as it was written
+    pub original: String,
+    /// Byte range of the original text
+    pub pos: (usize, usize),
+    /// The synonym it was corrected to
+    pub term: NormalizedTermValue,
+    /// The concept of the synonym
+    pub concept: NormalizedTerm,
+    /// Similarity of the original text and the synonym, between 0 and 1
+    pub similarity: f64,
+}
+
 /// A thesaurus is a dictionary with synonyms which map to upper-level concepts.
 ///
 /// It holds the normalized terms for a resource
@@ -281,6 +373,9 @@ pub struct Thesaurus {
     name: String,
     /// The inner hashmap of normalized terms
     data: AHashMap<NormalizedTermValue, NormalizedTerm>,
+    /// Broader, narrower and related concepts, see [`hierarchy`]
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    relations: BTreeSet<ConceptRelation>,
 }
 
 impl Thesaurus {
@@ -289,6 +384,7 @@ impl Thesaurus {
         Self {
             name,
             data: AHashMap::new(),
+            relations: BTreeSet::new(),
         }
     }
 
@@ -322,6 +418,14 @@ impl Thesaurus {
     pub fn keys(&self) -> std::collections::hash_map::Keys<NormalizedTermValue, NormalizedTerm> {
         self.data.keys()
     }
+
+    /// Retains only the entries specified by the predicate.
+    pub fn retain<F>(&mut self, f: F)
+    where
+        F: FnMut(&NormalizedTermValue, &mut NormalizedTerm) -> bool,
+    {
+        self.data.retain(f);
+    }
 }
 
 // Implement `IntoIterator` for a reference to `Thesaurus`
@@ -445,6 +549,71 @@ impl IndexedDocument {
     }
 }
 
+/// Contribution of a matched concept and one of its edges to the rank of a
+/// document in the `RoleGraph`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct RankContribution {
+    /// ID of the matched concept
+    pub node_id: u64,
+    /// Normalized term of the matched concept
+    pub concept: NormalizedTermValue,
+    /// ID of the traversed edge
+    pub edge_id: u64,
+    /// Rank of the node (number of co-occurrences of the concept)
+    pub node_rank: u64,
+    /// Rank of the edge (number of co-occurrences over all documents)
+    pub edge_rank: u64,
+    /// Number of co-occurrences of the edge in the document
+    pub document_rank: u64,
+}
+
+impl RankContribution {
+    /// The sum of the node, edge and document rank
+    pub fn total(&self) -> u64 {
+        self.node_rank + self.edge_rank + self.document_rank
+    }
+}
+
+/// Explanation of why a document ranked where it did in the `RoleGraph`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Explanation {
+    /// ID of the explained document
+    pub document_id: String,
+    /// Final rank, the sum of all contributions
+    pub rank: u64,
+    /// Concepts of the query matched in the document
+    pub concepts: Vec<NormalizedTermValue>,
+    /// IDs of the edges traversed to reach the document
+    pub edges: Vec<u64>,
+    /// Contributions which add up to the final rank
+    pub contributions: Vec<RankContribution>,
+}
+
+impl Explanation {
+    /// Create an explanation without any contributions
+    pub fn new(document_id: String) -> Self {
+        Self {
+            document_id,
+            rank: 0,
+            concepts: Vec::new(),
+            edges: Vec::new(),
+            contributions: Vec::new(),
+        }
+    }
+
+    /// Add a contribution to the rank
+    pub fn add(&mut self, contribution: RankContribution) {
+        self.rank += contribution.total();
+        if !self.concepts.contains(&contribution.concept) {
+            self.concepts.push(contribution.concept.clone());
+        }
+        if !self.edges.contains(&contribution.edge_id) {
+            self.edges.push(contribution.edge_id);
+        }
+        self.contributions.push(contribution);
+    }
+}
+
 /// Query type for searching documents in the `RoleGraph`.
 /// It contains the search term, skip and limit parameters.
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
@@ -455,6 +624,34 @@ pub struct SearchQuery {
     pub role: Option<RoleName>,
 }
 
+impl SearchQuery {
+    /// Parse the search term into a `Query`
+    ///
+    /// See the [`query`] module for the syntax. Note that operators are
+    /// upper case, so they are lost if the search term is created with
+    /// `NormalizedTermValue::new`, which lowercases it. Search terms which
+    /// aren't well-formed queries are searched for as they are, see
+    /// [`Query::parse_lenient`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the search term is empty
+    pub fn query(&self) -> Result<Query, QueryError> {
+        Query::parse_lenient(self.search_term.as_str())
+    }
+
+    /// Returns a copy of the query with another search term
+    ///
+    /// Unlike `NormalizedTermValue::new`, the case of the search term is
+    /// kept, so that its operators aren't lost.
+    pub fn with_search_term(&self, search_term: &str) -> Self {
+        Self {
+            search_term: NormalizedTermValue(search_term.trim().to_string()),
+            ..self.clone()
+        }
+    }
+}
+
 /// Defines the relevance function (scorer) to be used for ranking search
 /// results for the `Role`.
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Copy)]
@@ -469,6 +666,16 @@ pub enum RelevanceFunction {
     /// Scorer for ranking search results based on the title of a document
     #[serde(rename = "title-scorer")]
     TitleScorer,
+    /// Scorer for ranking search results by the full-text relevance of the
+    /// body of a document, using Okapi BM25
+    #[serde(rename = "bm25")]
+    BM25,
+    /// Scorer combining the rank in the Terraphim graph with the lexical
+    /// similarity of the title
+    ///
+    /// The weights of both are configured per role.
+    #[serde(rename = "hybrid")]
+    Hybrid,
 }
 
 /// Defines all supported inputs for the knowledge graph.
@@ -0,0 +1,501 @@
+//! Query language for searching documents
+//!
+//! A search term is parsed into a [`Query`], an AST which is evaluated
+//! against the `RoleGraph` as well as against the documents found in the
+//! haystacks. The syntax is:
+//!
+//! * bare words, e.g. `life cycle concepts`, which match any concept
+//!   found in the words
+//! * quoted phrases, e.g. `"project planning"`, which are matched as a
+//!   whole: texts have to contain the phrase, and in the knowledge graph the
+//!   phrase has to be a single concept
+//! * concept ID literals, e.g. `#42`, which match a concept of the
+//!   knowledge graph by its ID
+//! * the operators `AND`, `OR` and `NOT` (upper case only, so that lower
+//!   case words like "and" stay part of a term), and parentheses for
+//!   grouping
+//!
+//! `NOT` binds stronger than `AND`, which binds stronger than `OR`.
+//! Operands which follow each other without an operator, like
+//! `"kafka" #42`, are combined with `AND`.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::iter::Peekable;
+use std::str::{CharIndices, FromStr};
+
+/// Error while parsing a query
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    #[error("Query is empty")]
+    Empty,
+
+    #[error("Unexpected end of query")]
+    UnexpectedEnd,
+
+    #[error("Unexpected `{0}` in query")]
+    UnexpectedToken(String),
+
+    #[error("Unterminated phrase in query")]
+    UnterminatedPhrase,
+
+    #[error("Invalid concept ID `#{0}` in query")]
+    InvalidConceptId(String),
+}
+
+/// A parsed search query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Query {
+    /// One or more bare words, matching any concept found in them
+    Term(String),
+    /// A quoted phrase, matching as a whole
+    Phrase(String),
+    /// The ID of a concept in the knowledge graph
+    Concept(u64),
+    /// Both sides have to match
+    And(Box<Query>, Box<Query>),
+    /// Either side has to match
+    Or(Box<Query>, Box<Query>),
+    /// The inner query must not match
+    Not(Box<Query>),
+}
+
+impl Query {
+    /// Parse a query string into a `Query`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query is empty or not well-formed
+    pub fn parse(query: &str) -> Result<Self, QueryError> {
+        let tokens: Vec<Token> = tokenize(query)?
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect();
+        if tokens.is_empty() {
+            return Err(QueryError::Empty);
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let query = parser.parse_or()?;
+        match parser.next() {
+            None => Ok(query),
+            Some(token) => Err(QueryError::UnexpectedToken(token.to_string())),
+        }
+    }
+
+    /// Parse a query string into a `Query`, falling back to a single term
+    /// if it isn't well-formed
+    ///
+    /// This is what user input should be parsed with, so that e.g. `c++)`
+    /// or an unterminated quote searches for the input as it is instead
+    /// of failing.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the query is empty
+    pub fn parse_lenient(query: &str) -> Result<Self, QueryError> {
+        match Query::parse(query) {
+            Err(QueryError::Empty) => Err(QueryError::Empty),
+            Err(e) => {
+                log::debug!("Searching for `{query}` as a term, as it isn't a valid query: {e}");
+                Ok(Query::Term(query.trim().to_string()))
+            }
+            query => query,
+        }
+    }
+
+    /// Returns the byte ranges of the terms and phrases of a query string
+    ///
+    /// Consecutive words form a single range, like they form a single
+    /// term. Operators, parentheses, quotes and concept IDs are not part
+    /// of any range. If the query string can't be parsed, the whole string
+    /// is returned as one range.
+    pub fn text_ranges(query: &str) -> Vec<(usize, usize)> {
+        let tokens = match tokenize(query) {
+            Ok(tokens) if Query::parse(query).is_ok() => tokens,
+            _ => return vec![(0, query.len())],
+        };
+        let mut ranges: Vec<(usize, usize)> = Vec::new();
+        let mut previous_word = false;
+        for (token, range) in tokens {
+            match token {
+                Token::Word(_) => match ranges.last_mut() {
+                    Some(last) if previous_word => last.1 = range.1,
+                    _ => ranges.push(range),
+                },
+                Token::Phrase(_) => ranges.push(range),
+                _ => {}
+            }
+            previous_word = matches!(token, Token::Word(_));
+        }
+        ranges
+    }
+
+    /// Returns the leaves (terms, phrases and concepts) which are not
+    /// negated, i.e. the parts of the query a matching document can
+    /// contain
+    pub fn positive_leaves(&self) -> Vec<&Query> {
+        let mut leaves = Vec::new();
+        self.collect_leaves(false, &mut leaves);
+        leaves
+    }
+
+    fn collect_leaves<'a>(&'a self, negated: bool, leaves: &mut Vec<&'a Query>) {
+        match self {
+            Query::Term(_) | Query::Phrase(_) | Query::Concept(_) => {
+                if !negated {
+                    leaves.push(self);
+                }
+            }
+            Query::And(left, right) | Query::Or(left, right) => {
+                left.collect_leaves(negated, leaves);
+                right.collect_leaves(negated, leaves);
+            }
+            Query::Not(inner) => inner.collect_leaves(!negated, leaves),
+        }
+    }
+
+    /// Evaluate the query against a plain text, ignoring case
+    ///
+    /// Terms and phrases match if the text contains them. Concepts match
+    /// if the text contains any of the synonyms returned by `synonyms`.
+    pub fn matches_text<F>(&self, text: &str, synonyms: &F) -> bool
+    where
+        F: Fn(u64) -> Vec<String>,
+    {
+        let text = text.to_lowercase();
+        self.matches_lowercase(&text, synonyms)
+    }
+
+    fn matches_lowercase<F>(&self, text: &str, synonyms: &F) -> bool
+    where
+        F: Fn(u64) -> Vec<String>,
+    {
+        match self {
+            Query::Term(term) | Query::Phrase(term) => text.contains(&term.to_lowercase()),
+            Query::Concept(id) => synonyms(*id)
+                .iter()
+                .any(|synonym| text.contains(&synonym.to_lowercase())),
+            Query::And(left, right) => {
+                left.matches_lowercase(text, synonyms) && right.matches_lowercase(text, synonyms)
+            }
+            Query::Or(left, right) => {
+                left.matches_lowercase(text, synonyms) || right.matches_lowercase(text, synonyms)
+            }
+            Query::Not(inner) => !inner.matches_lowercase(text, synonyms),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s)
+    }
+}
+
+impl Display for Query {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Query::Term(term) => write!(f, "{term}"),
+            Query::Phrase(phrase) => write!(f, "\"{phrase}\""),
+            Query::Concept(id) => write!(f, "#{id}"),
+            Query::And(left, right) => write!(f, "({left} AND {right})"),
+            Query::Or(left, right) => write!(f, "({left} OR {right})"),
+            Query::Not(inner) => write!(f, "NOT {inner}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Phrase(String),
+    Concept(u64),
+    And,
+    Or,
+    Not,
+    LParen,
+    RParen,
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Word(word) => write!(f, "{word}"),
+            Token::Phrase(phrase) => write!(f, "\"{phrase}\""),
+            Token::Concept(id) => write!(f, "#{id}"),
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::Not => write!(f, "NOT"),
+            Token::LParen => write!(f, "("),
+            Token::RParen => write!(f, ")"),
+        }
+    }
+}
+
+/// A token and its byte range in the query string
+type SpannedToken = (Token, (usize, usize));
+
+/// Splits a query string into tokens
+///
+/// The range of a phrase excludes the quotes.
+fn tokenize(query: &str) -> Result<Vec<SpannedToken>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = query.char_indices().peekable();
+    while let Some(&(start, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push((Token::LParen, (start, start + 1)));
+            }
+            ')' => {
+                chars.next();
+                tokens.push((Token::RParen, (start, start + 1)));
+            }
+            '"' => {
+                chars.next();
+                let mut phrase = String::new();
+                let end = loop {
+                    match chars.next() {
+                        Some((end, '"')) => break end,
+                        Some((_, c)) => phrase.push(c),
+                        None => return Err(QueryError::UnterminatedPhrase),
+                    }
+                };
+                tokens.push((Token::Phrase(phrase.trim().to_string()), (start + 1, end)));
+            }
+            '#' => {
+                chars.next();
+                let id = read_word(&mut chars);
+                let end = start + 1 + id.len();
+                let id = id.parse().map_err(|_| QueryError::InvalidConceptId(id))?;
+                tokens.push((Token::Concept(id), (start, end)));
+            }
+            _ => {
+                let word = read_word(&mut chars);
+                let range = (start, start + word.len());
+                let token = match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Word(word),
+                };
+                tokens.push((token, range));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Reads until the next whitespace, parenthesis or quote
+fn read_word(chars: &mut Peekable<CharIndices<'_>>) -> String {
+    let mut word = String::new();
+    while let Some(&(_, c)) = chars.peek() {
+        if c.is_whitespace() || matches!(c, '(' | ')' | '"') {
+            break;
+        }
+        word.push(c);
+        chars.next();
+    }
+    word
+}
+
+/// Recursive descent parser over the tokens of a query
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Query, QueryError> {
+        let mut query = self.parse_and()?;
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            let right = self.parse_and()?;
+            query = Query::Or(Box::new(query), Box::new(right));
+        }
+        Ok(query)
+    }
+
+    fn parse_and(&mut self) -> Result<Query, QueryError> {
+        let mut query = self.parse_not()?;
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                // Operands without an operator in between
+                Some(
+                    Token::Word(_)
+                    | Token::Phrase(_)
+                    | Token::Concept(_)
+                    | Token::Not
+                    | Token::LParen,
+                ) => {}
+                _ => break,
+            }
+            let right = self.parse_not()?;
+            query = Query::And(Box::new(query), Box::new(right));
+        }
+        Ok(query)
+    }
+
+    fn parse_not(&mut self) -> Result<Query, QueryError> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            let inner = self.parse_not()?;
+            return Ok(Query::Not(Box::new(inner)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Query, QueryError> {
+        match self.next() {
+            Some(Token::Word(word)) => {
+                // Consecutive words form a single term
+                let mut term = word;
+                while let Some(Token::Word(word)) = self.peek() {
+                    term.push(' ');
+                    term.push_str(word);
+                    self.next();
+                }
+                Ok(Query::Term(term))
+            }
+            Some(Token::Phrase(phrase)) => Ok(Query::Phrase(phrase)),
+            Some(Token::Concept(id)) => Ok(Query::Concept(id)),
+            Some(Token::LParen) => {
+                let query = self.parse_or()?;
+                match self.next() {
+                    Some(Token::RParen) => Ok(query),
+                    Some(token) => Err(QueryError::UnexpectedToken(token.to_string())),
+                    None => Err(QueryError::UnexpectedEnd),
+                }
+            }
+            Some(token) => Err(QueryError::UnexpectedToken(token.to_string())),
+            None => Err(QueryError::UnexpectedEnd),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(term: &str) -> Box<Query> {
+        Box::new(Query::Term(term.to_string()))
+    }
+
+    #[test]
+    fn test_parse_term() {
+        // Lower case operators are part of the term
+        assert_eq!(
+            Query::parse("Life cycle concepts and project direction").unwrap(),
+            Query::Term("Life cycle concepts and project direction".to_string())
+        );
+    }
+
+    #[test]
+    fn test_parse_operators() {
+        assert_eq!(
+            Query::parse("kafka AND NOT zookeeper").unwrap(),
+            Query::And(term("kafka"), Box::new(Query::Not(term("zookeeper"))))
+        );
+        assert_eq!(
+            Query::parse("a OR b AND c").unwrap(),
+            Query::Or(term("a"), Box::new(Query::And(term("b"), term("c"))))
+        );
+        assert_eq!(
+            Query::parse("(a OR b) c").unwrap(),
+            Query::And(Box::new(Query::Or(term("a"), term("b"))), term("c"))
+        );
+    }
+
+    #[test]
+    fn test_parse_phrase_and_concept() {
+        assert_eq!(
+            Query::parse("\"project AND planning\" OR #42").unwrap(),
+            Query::Or(
+                Box::new(Query::Phrase("project AND planning".to_string())),
+                Box::new(Query::Concept(42))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(Query::parse("  "), Err(QueryError::Empty));
+        assert_eq!(Query::parse("kafka AND"), Err(QueryError::UnexpectedEnd));
+        assert_eq!(
+            Query::parse("kafka)"),
+            Err(QueryError::UnexpectedToken(")".to_string()))
+        );
+        assert_eq!(Query::parse("\"kafka"), Err(QueryError::UnterminatedPhrase));
+        assert_eq!(
+            Query::parse("#kafka"),
+            Err(QueryError::InvalidConceptId("kafka".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        assert_eq!(Query::parse_lenient("  "), Err(QueryError::Empty));
+        for query in ["#rust", "c++)", "\"unterminated", "kafka AND"] {
+            assert_eq!(
+                Query::parse_lenient(&format!(" {query} ")).unwrap(),
+                Query::Term(query.to_string())
+            );
+        }
+        assert_eq!(
+            Query::parse_lenient("kafka AND NOT zookeeper").unwrap(),
+            Query::parse("kafka AND NOT zookeeper").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_text_ranges() {
+        let query = "Kafka streams AND NOT (zookeper OR \"event log\") #3";
+        let ranges: Vec<&str> = Query::text_ranges(query)
+            .into_iter()
+            .map(|(start, end)| &query[start..end])
+            .collect();
+        assert_eq!(ranges, vec!["Kafka streams", "zookeper", "event log"]);
+        assert_eq!(Query::text_ranges("kafka AND"), vec![(0, 9)]);
+    }
+
+    #[test]
+    fn test_positive_leaves() {
+        let query = Query::parse("kafka AND NOT (zookeeper AND NOT #3)").unwrap();
+        assert_eq!(
+            query.positive_leaves(),
+            vec![&Query::Term("kafka".to_string()), &Query::Concept(3)]
+        );
+    }
+
+    #[test]
+    fn test_matches_text() {
+        let synonyms = |id| match id {
+            3 => vec!["zk".to_string()],
+            _ => vec![],
+        };
+        let query = Query::parse("kafka AND NOT zookeeper").unwrap();
+        assert!(query.matches_text("Kafka without coordination", &synonyms));
+        assert!(!query.matches_text("Kafka depends on Zookeeper", &synonyms));
+
+        let query = Query::parse("#3").unwrap();
+        assert!(query.matches_text("Kafka depends on ZK", &synonyms));
+    }
+}
@@ -0,0 +1,402 @@
+//! Merging, diffing and validating thesauri
+//!
+//! Roles often combine several thesauri, which change independently of each
+//! other. [`Thesaurus::merge`] combines two thesauri and reports synonyms
+//! which map to different concepts, [`Thesaurus::diff`] shows what changed
+//! between two versions and [`Thesaurus::validate`] finds entries which
+//! can't work as intended.
+
+use ahash::{AHashMap, AHashSet};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use crate::{NormalizedTerm, NormalizedTermValue, Thesaurus};
+
+/// What to do when both thesauri of a merge map a synonym to different
+/// concepts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MergePolicy {
+    /// Keep the concept of the thesaurus merged into
+    #[default]
+    KeepOurs,
+    /// Take the concept of the thesaurus being merged
+    TakeTheirs,
+    /// Don't merge anything if there is a conflict
+    Fail,
+}
+
+impl FromStr for MergePolicy {
+    type Err = String;
+
+    fn from_str(policy: &str) -> Result<Self, Self::Err> {
+        match policy {
+            "keep_ours" | "keep-ours" | "ours" => Ok(MergePolicy::KeepOurs),
+            "take_theirs" | "take-theirs" | "theirs" => Ok(MergePolicy::TakeTheirs),
+            "fail" => Ok(MergePolicy::Fail),
+            _ => Err(format!(
+                "Unknown merge policy `{policy}`, expected keep_ours, take_theirs or fail"
+            )),
+        }
+    }
+}
+
+/// A synonym which maps to different concepts in two thesauri
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MergeConflict {
+    pub synonym: NormalizedTermValue,
+    pub ours: NormalizedTerm,
+    pub theirs: NormalizedTerm,
+}
+
+impl Display for MergeConflict {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "`{}` maps to `{}` (#{}) and `{}` (#{})",
+            self.synonym, self.ours.value, self.ours.id, self.theirs.value, self.theirs.id
+        )
+    }
+}
+
+/// A merge which failed because of conflicts, see [`MergePolicy::Fail`]
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+#[error("{} conflicting synonyms", conflicts.len())]
+pub struct MergeError {
+    pub conflicts: Vec<MergeConflict>,
+}
+
+/// A synonym which maps to a different concept in the newer thesaurus
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Remapped {
+    pub synonym: NormalizedTermValue,
+    pub from: NormalizedTerm,
+    pub to: NormalizedTerm,
+}
+
+/// Changes between two versions of a thesaurus
+///
+/// All entries are sorted by synonym.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThesaurusDiff {
+    /// Synonyms which only exist in the newer thesaurus
+    pub added: Vec<(NormalizedTermValue, NormalizedTerm)>,
+    /// Synonyms which only exist in the older thesaurus
+    pub removed: Vec<(NormalizedTermValue, NormalizedTerm)>,
+    /// Synonyms which map to different concepts
+    pub remapped: Vec<Remapped>,
+}
+
+impl ThesaurusDiff {
+    /// Whether both thesauri are the same
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.remapped.is_empty()
+    }
+}
+
+impl Display for ThesaurusDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for (synonym, term) in &self.added {
+            writeln!(f, "+ {synonym} -> {} (#{})", term.value, term.id)?;
+        }
+        for (synonym, term) in &self.removed {
+            writeln!(f, "- {synonym} -> {} (#{})", term.value, term.id)?;
+        }
+        for remapped in &self.remapped {
+            writeln!(
+                f,
+                "~ {} -> {} (#{}) => {} (#{})",
+                remapped.synonym,
+                remapped.from.value,
+                remapped.from.id,
+                remapped.to.value,
+                remapped.to.id
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// A problem found by [`Thesaurus::validate`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "issue", rename_all = "snake_case")]
+pub enum ThesaurusIssue {
+    /// A synonym which is empty or only whitespace, it would match everywhere
+    EmptyKey { term: NormalizedTerm },
+    /// Concepts with different normalized terms sharing the same ID
+    IdCollision {
+        id: u64,
+        values: Vec<NormalizedTermValue>,
+    },
+    /// A synonym which contains a synonym of another concept
+    ///
+    /// Matching is leftmost-longest, so wherever `by` occurs in a text, the
+    /// `synonym` inside of it is never matched. This is a warning, it is
+    /// usually intended, like "learning" within "machine learning".
+    Shadowed {
+        synonym: NormalizedTermValue,
+        concept: u64,
+        by: NormalizedTermValue,
+        by_concept: u64,
+    },
+}
+
+impl ThesaurusIssue {
+    /// Whether the issue breaks matching, as opposed to a warning
+    pub fn is_error(&self) -> bool {
+        !matches!(self, ThesaurusIssue::Shadowed { .. })
+    }
+}
+
+impl Display for ThesaurusIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            ThesaurusIssue::EmptyKey { term } => {
+                write!(f, "empty synonym of `{}` (#{})", term.value, term.id)
+            }
+            ThesaurusIssue::IdCollision { id, values } => {
+                let values: Vec<&str> = values.iter().map(NormalizedTermValue::as_str).collect();
+                write!(f, "ID {id} is used by `{}`", values.join("`, `"))
+            }
+            ThesaurusIssue::Shadowed {
+                synonym,
+                concept,
+                by,
+                by_concept,
+            } => write!(
+                f,
+                "`{synonym}` (#{concept}) is shadowed by `{by}` (#{by_concept})"
+            ),
+        }
+    }
+}
+
+impl Thesaurus {
+    /// Merge another thesaurus into this one
+    ///
+    /// Synonyms mapping to different concepts in both thesauri are resolved
+    /// with the policy and returned. With [`MergePolicy::Fail`], nothing is
+    /// merged if there is any conflict.
+    ///
+    /// The relations between concepts of the other thesaurus are added as
+    /// they are.
+    pub fn merge(
+        &mut self,
+        other: &Thesaurus,
+        policy: MergePolicy,
+    ) -> Result<Vec<MergeConflict>, MergeError> {
+        let mut conflicts: Vec<MergeConflict> = other
+            .into_iter()
+            .filter_map(|(synonym, theirs)| {
+                let ours = self.get(synonym)?;
+                (ours.id != theirs.id || ours.value != theirs.value).then(|| MergeConflict {
+                    synonym: synonym.clone(),
+                    ours: ours.clone(),
+                    theirs: theirs.clone(),
+                })
+            })
+            .collect();
+        conflicts.sort_by(|a, b| a.synonym.as_str().cmp(b.synonym.as_str()));
+        if policy == MergePolicy::Fail && !conflicts.is_empty() {
+            return Err(MergeError { conflicts });
+        }
+
+        for (synonym, theirs) in other {
+            if policy == MergePolicy::TakeTheirs || self.get(synonym).is_none() {
+                self.insert(synonym.clone(), theirs.clone());
+            }
+        }
+        self.relations.extend(other.relations().copied());
+        Ok(conflicts)
+    }
+
+    /// Changes from this thesaurus to a newer version of it
+    pub fn diff(&self, newer: &Thesaurus) -> ThesaurusDiff {
+        let mut diff = ThesaurusDiff::default();
+        for (synonym, term) in newer {
+            match self.get(synonym) {
+                None => diff.added.push((synonym.clone(), term.clone())),
+                Some(old) if old.id != term.id || old.value != term.value => {
+                    diff.remapped.push(Remapped {
+                        synonym: synonym.clone(),
+                        from: old.clone(),
+                        to: term.clone(),
+                    })
+                }
+                Some(_) => {}
+            }
+        }
+        for (synonym, term) in self {
+            if newer.get(synonym).is_none() {
+                diff.removed.push((synonym.clone(), term.clone()));
+            }
+        }
+        diff.added.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+        diff.removed.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+        diff.remapped
+            .sort_by(|a, b| a.synonym.as_str().cmp(b.synonym.as_str()));
+        diff
+    }
+
+    /// Find entries which can't be matched as intended
+    ///
+    /// Errors come first, followed by warnings, see
+    /// [`ThesaurusIssue::is_error`].
+    pub fn validate(&self) -> Vec<ThesaurusIssue> {
+        let mut entries: Vec<(&NormalizedTermValue, &NormalizedTerm)> = self.into_iter().collect();
+        entries.sort_by(|a, b| a.0.as_str().cmp(b.0.as_str()));
+
+        let mut issues = Vec::new();
+        let mut values: AHashMap<u64, Vec<NormalizedTermValue>> = AHashMap::new();
+        for (synonym, term) in &entries {
+            if synonym.as_str().trim().is_empty() {
+                issues.push(ThesaurusIssue::EmptyKey {
+                    term: (*term).clone(),
+                });
+            }
+            let values = values.entry(term.id).or_default();
+            if !values.contains(&term.value) {
+                values.push(term.value.clone());
+            }
+        }
+        let mut collisions: Vec<(u64, Vec<NormalizedTermValue>)> = values
+            .into_iter()
+            .filter(|(_, values)| values.len() > 1)
+            .collect();
+        collisions.sort_by_key(|(id, _)| *id);
+        for (id, mut values) in collisions {
+            values.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+            issues.push(ThesaurusIssue::IdCollision { id, values });
+        }
+
+        // Looking up every substring of a synonym is much cheaper than
+        // comparing all pairs of synonyms, synonyms are short
+        let mut reported = AHashSet::new();
+        for (by, by_term) in &entries {
+            let text = by.as_str();
+            let boundaries: Vec<usize> = text
+                .char_indices()
+                .map(|(offset, _)| offset)
+                .chain([text.len()])
+                .collect();
+            for (i, &start) in boundaries.iter().enumerate() {
+                for &end in &boundaries[i + 1..] {
+                    if end - start == text.len() || text[start..end].trim().is_empty() {
+                        continue;
+                    }
+                    let synonym = NormalizedTermValue::new(text[start..end].to_string());
+                    let Some(term) = self.get(&synonym) else {
+                        continue;
+                    };
+                    if term.id != by_term.id && reported.insert((synonym.clone(), (*by).clone())) {
+                        issues.push(ThesaurusIssue::Shadowed {
+                            synonym,
+                            concept: term.id,
+                            by: (*by).clone(),
+                            by_concept: by_term.id,
+                        });
+                    }
+                }
+            }
+        }
+        issues
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn thesaurus(name: &str, entries: &[(&str, u64, &str)]) -> Thesaurus {
+        let mut thesaurus = Thesaurus::new(name.to_string());
+        for (synonym, id, value) in entries {
+            thesaurus.insert((*synonym).into(), NormalizedTerm::new(*id, (*value).into()));
+        }
+        thesaurus
+    }
+
+    #[test]
+    fn test_merge() {
+        let ours = thesaurus("ours", &[("k8s", 1, "kubernetes"), ("helm", 2, "helm")]);
+        let theirs = thesaurus(
+            "theirs",
+            &[
+                ("k8s", 3, "kube"),
+                ("kafka", 4, "kafka"),
+                ("helm", 2, "helm"),
+            ],
+        );
+
+        let mut merged = ours.clone();
+        let conflicts = merged.merge(&theirs, MergePolicy::KeepOurs).unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].synonym.as_str(), "k8s");
+        assert_eq!(merged.len(), 3);
+        assert_eq!(merged.get(&"k8s".into()).unwrap().id, 1);
+
+        let mut merged = ours.clone();
+        merged.merge(&theirs, MergePolicy::TakeTheirs).unwrap();
+        assert_eq!(merged.get(&"k8s".into()).unwrap().id, 3);
+
+        let mut merged = ours.clone();
+        let error = merged.merge(&theirs, MergePolicy::Fail).unwrap_err();
+        assert_eq!(error.conflicts, conflicts);
+        assert_eq!(merged, ours);
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = thesaurus("devops", &[("k8s", 1, "kubernetes"), ("helm", 2, "helm")]);
+        let new = thesaurus("devops", &[("k8s", 3, "kube"), ("kafka", 4, "kafka")]);
+        let diff = old.diff(&new);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].0.as_str(), "kafka");
+        assert_eq!(diff.removed[0].0.as_str(), "helm");
+        assert_eq!(diff.remapped[0].to.id, 3);
+        assert_eq!(
+            diff.to_string(),
+            "+ kafka -> kafka (#4)\n- helm -> helm (#2)\n~ k8s -> kubernetes (#1) => kube (#3)\n"
+        );
+        assert!(old.diff(&old).is_empty());
+    }
+
+    #[test]
+    fn test_validate() {
+        let thesaurus = thesaurus(
+            "ai",
+            &[
+                (" ", 1, "machine learning"),
+                ("machine learning", 1, "machine learning"),
+                ("ml", 1, "ml"),
+                ("learning", 2, "learning"),
+                ("learn", 2, "learning"),
+            ],
+        );
+        let issues = thesaurus.validate();
+        let errors: Vec<String> = issues
+            .iter()
+            .filter(|issue| issue.is_error())
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                "empty synonym of `machine learning` (#1)",
+                "ID 1 is used by `machine learning`, `ml`",
+            ]
+        );
+        let warnings: Vec<String> = issues
+            .iter()
+            .filter(|issue| !issue.is_error())
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                "`learn` (#2) is shadowed by `machine learning` (#1)",
+                "`learning` (#2) is shadowed by `machine learning` (#1)",
+            ]
+        );
+    }
+}
@@ -10,7 +10,7 @@ use terraphim_config::{Config, ConfigState};
 use terraphim_service::TerraphimService;
 use terraphim_settings::DeviceSettings;
 use terraphim_types::Thesaurus;
-use terraphim_types::{Document, SearchQuery};
+use terraphim_types::{Correction, Document, SearchQuery, Suggestion};
 
 use serde::Serializer;
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, Clone, Copy)]
@@ -76,6 +76,9 @@ pub struct SearchResponse {
     pub status: Status,
     /// The search results
     pub results: Vec<Document>,
+    /// Misspelled words of the search term which were corrected before
+    /// searching, to show "did you mean" hints
+    pub corrections: Vec<Correction>,
 }
 
 /// Search All TerraphimGraphs defined in a config by query param
@@ -86,10 +89,42 @@ pub async fn search(
 ) -> Result<SearchResponse> {
     log::info!("Search called with {:?}", search_query);
     let mut terraphim_service = TerraphimService::new(config_state.inner().clone());
-    let results = terraphim_service.search(&search_query).await?;
+    let (search_query, corrections) = terraphim_service.correct_search_query(&search_query).await?;
+    let results = terraphim_service.search_corrected(&search_query).await?;
     Ok(SearchResponse {
         status: Status::Success,
         results,
+        corrections,
+    })
+}
+
+/// Response type for autocompletion
+///
+/// This is used by the search box to suggest concepts while typing
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AutocompleteResponse {
+    /// Status of the autocompletion
+    pub status: Status,
+    /// Suggested synonyms, best first
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// Suggest synonyms of the knowledge graph of a role completing the query
+#[command]
+pub async fn autocomplete(
+    config_state: State<'_, ConfigState>,
+    role_name: String,
+    query: String,
+    limit: Option<usize>,
+) -> Result<AutocompleteResponse> {
+    log::debug!("Autocomplete called with {:?} for role {}", query, role_name);
+    let terraphim_service = TerraphimService::new(config_state.inner().clone());
+    let suggestions = terraphim_service
+        .autocomplete(&role_name.into(), &query, limit.unwrap_or(10))
+        .await?;
+    Ok(AutocompleteResponse {
+        status: Status::Success,
+        suggestions,
     })
 }
 
@@ -15,7 +15,7 @@ use tauri::{
     SystemTrayMenu, WindowBuilder,
 };
 
-use terraphim_config::ConfigState;
+use terraphim_config::{ConfigState, SNAPSHOT_INTERVAL};
 use terraphim_settings::DeviceSettings;
 
 #[tokio::main]
@@ -44,6 +44,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
         Err(e) => panic!("Failed to build config: {:?}", e),
     };
     let config_state = ConfigState::new(&mut config).await?;
+    // Indexed documents are saved in the background, so they survive a restart
+    let _snapshots = config_state.save_rolegraphs_periodically(SNAPSHOT_INTERVAL);
     let current_config = config_state.config.lock().await;
     let global_shortcut = current_config.global_shortcut.clone();
     drop(current_config);
@@ -57,13 +59,15 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .add_item(quit);
     let system_tray = SystemTray::new().with_menu(tray_menu);
 
+    let tray_config_state = config_state.clone();
     let app = tauri::Builder::default()
         .system_tray(system_tray)
-        .on_system_tray_event(|app, event| match event {
+        .on_system_tray_event(move |app, event| match event {
             SystemTrayEvent::MenuItemClick { id, .. } => {
                 let item_handle = app.tray_handle().get_item(&id);
                 match id.as_str() {
                     "quit" => {
+                        save_rolegraphs(&tray_config_state);
                         std::process::exit(0);
                     }
                     "toggle" => {
@@ -86,6 +90,7 @@ async fn main() -> Result<(), Box<dyn Error>> {
         .manage(device_settings.clone())
         .invoke_handler(tauri::generate_handler![
             cmd::search,
+            cmd::autocomplete,
             cmd::get_config,
             cmd::update_config,
             cmd::publish_thesaurus,
@@ -142,4 +147,17 @@ async fn main() -> Result<(), Box<dyn Error>> {
         _ => {}
     });
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Saves the rolegraphs changed since the last periodic save
+///
+/// The tray quits with `std::process::exit`, which doesn't wait for the
+/// periodic save.
+fn save_rolegraphs(config_state: &ConfigState) {
+    let result = tokio::task::block_in_place(|| {
+        tokio::runtime::Handle::current().block_on(config_state.save_rolegraphs())
+    });
+    if let Err(e) = result {
+        log::warn!("Failed to save rolegraph snapshots: {e:?}");
+    }
+}
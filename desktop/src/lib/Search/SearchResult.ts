@@ -7,6 +7,13 @@ export interface Document {
   stub?: string;
   tags?: string[];
   rank?: number;
+  score_breakdown?: ScoreBreakdown;
+}
+
+export interface ScoreBreakdown {
+  graph: number;
+  lexical: number;
+  combined: number;
 }
 
 export interface SearchResponse {
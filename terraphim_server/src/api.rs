@@ -1,6 +1,7 @@
+use anyhow::anyhow;
 use axum::{
-    extract::{Query, State},
-    http::StatusCode,
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
     response::IntoResponse,
     Extension, Json,
 };
@@ -11,11 +12,14 @@ use tokio::sync::Mutex;
 
 use terraphim_config::Config;
 use terraphim_config::ConfigState;
-use terraphim_rolegraph::RoleGraph;
-use terraphim_service::TerraphimService;
-use terraphim_types::{Document, IndexedDocument, SearchQuery};
+use terraphim_rolegraph::{ExportFormat, RoleGraph};
+use terraphim_service::{ServiceError, TerraphimService};
+use terraphim_types::{
+    ConnectedConcept, Correction, Document, Explanation, IndexedDocument, Neighbour,
+    NormalizedTerm, SearchQuery, Suggestion,
+};
 
-use crate::error::{Result, Status};
+use crate::error::{ApiError, Result, Status};
 pub type SearchResultsStream = Sender<IndexedDocument>;
 
 /// Health check endpoint
@@ -46,6 +50,52 @@ pub(crate) async fn create_document(
     }))
 }
 
+/// Response for updating or deleting a document
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DocumentResponse {
+    /// Status of the operation
+    pub status: Status,
+    /// The id of the document that was updated or deleted
+    pub id: String,
+}
+
+/// Replaces the index of the document in each rolegraph
+///
+/// The id in the path takes precedence over the id in the body
+pub(crate) async fn update_document(
+    State(config): State<ConfigState>,
+    Path(id): Path<String>,
+    Json(mut document): Json<Document>,
+) -> Result<Json<DocumentResponse>> {
+    log::debug!("update_document {id}");
+    document.id = id;
+    let mut terraphim_service = TerraphimService::new(config.clone());
+    let document = terraphim_service.update_document(document).await?;
+    Ok(Json(DocumentResponse {
+        status: Status::Success,
+        id: document.id,
+    }))
+}
+
+/// Removes the document from each rolegraph
+pub(crate) async fn delete_document(
+    State(config): State<ConfigState>,
+    Path(id): Path<String>,
+) -> Result<Json<DocumentResponse>> {
+    log::debug!("delete_document {id}");
+    let mut terraphim_service = TerraphimService::new(config.clone());
+    if !terraphim_service.delete_document(&id).await? {
+        return Err(ApiError(
+            StatusCode::NOT_FOUND,
+            anyhow!("Document `{id}` not found"),
+        ));
+    }
+    Ok(Json(DocumentResponse {
+        status: Status::Success,
+        id,
+    }))
+}
+
 // TODO: Is this still needed now that we have search?
 pub(crate) async fn _list_documents(
     State(rolegraph): State<Arc<Mutex<RoleGraph>>>,
@@ -64,52 +114,274 @@ pub struct SearchResponse {
     pub results: Vec<Document>,
     /// The number of documents that match the search query
     pub total: usize,
+    /// Explanation of the rank of each result, in the same order as the
+    /// results. Only set if requested with `explain=true`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanations: Option<Vec<Explanation>>,
+    /// Misspelled words of the search term which were corrected before
+    /// searching, to show "did you mean" hints
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub corrections: Vec<Correction>,
+}
+
+/// Query parameters for the search endpoints
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ExplainParams {
+    /// Explain the ranking of the results
+    pub explain: Option<bool>,
+}
+
+/// Runs the search and optionally explains the ranking of the results
+async fn search(
+    config_state: ConfigState,
+    search_query: &SearchQuery,
+    explain: bool,
+) -> Result<SearchResponse> {
+    let mut terraphim_service = TerraphimService::new(config_state);
+    // Correct the query once for both the search and the explanations
+    let (search_query, corrections) = terraphim_service.correct_search_query(search_query).await?;
+    let (results, explanations) = if explain {
+        let (results, explanations) = terraphim_service
+            .explain_corrected(&search_query)
+            .await?
+            .into_iter()
+            .unzip();
+        (results, Some(explanations))
+    } else {
+        (
+            terraphim_service.search_corrected(&search_query).await?,
+            None,
+        )
+    };
+    let total = results.len();
+
+    if total == 0 {
+        log::debug!("No documents found");
+    } else {
+        log::debug!("Found {total} documents");
+    }
+
+    Ok(SearchResponse {
+        status: Status::Success,
+        results,
+        total,
+        explanations,
+        corrections,
+    })
 }
 
 /// Search for documents in all Terraphim graphs defined in the config via GET params
+///
+/// Pass `explain=true` to get an explanation of the rank of each result.
 pub(crate) async fn search_documents(
     Extension(_tx): Extension<SearchResultsStream>,
     State(config_state): State<ConfigState>,
     search_query: Query<SearchQuery>,
+    Query(params): Query<ExplainParams>,
 ) -> Result<Json<SearchResponse>> {
     log::debug!("search_document called with {:?}", search_query);
 
-    let mut terraphim_service = TerraphimService::new(config_state);
-    let results = terraphim_service.search(&search_query.0).await?;
-    let total = results.len();
-
-    Ok(Json(SearchResponse {
-        status: Status::Success,
-        results,
-        total,
-    }))
+    let response = search(
+        config_state,
+        &search_query.0,
+        params.explain.unwrap_or(false),
+    )
+    .await?;
+    Ok(Json(response))
 }
 
 /// Search for documents in all Terraphim graphs defined in the config via POST body
+///
+/// Pass `explain=true` as query parameter to get an explanation of the rank
+/// of each result.
 pub(crate) async fn search_documents_post(
     Extension(_tx): Extension<SearchResultsStream>,
     State(config_state): State<ConfigState>,
+    Query(params): Query<ExplainParams>,
     search_query: Json<SearchQuery>,
 ) -> Result<Json<SearchResponse>> {
     log::debug!("POST Searching documents with query: {search_query:?}");
 
-    let mut terraphim_service = TerraphimService::new(config_state);
-    let results = terraphim_service.search(&search_query).await?;
-    let total = results.len();
+    let response = search(config_state, &search_query, params.explain.unwrap_or(false)).await?;
+    Ok(Json(response))
+}
 
-    if total == 0 {
-        log::debug!("No documents found");
-    } else {
-        log::debug!("Found {total} documents");
+/// Maps errors for unknown roles and concepts to `404 Not Found`
+fn graph_error(error: ServiceError) -> ApiError {
+    match error {
+        ServiceError::RoleGraphNotFound(_) | ServiceError::ConceptNotFound(_) => {
+            ApiError(StatusCode::NOT_FOUND, error.into())
+        }
+        error => error.into(),
     }
+}
 
-    Ok(Json(SearchResponse {
+/// Response for listing the most connected concepts
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ConceptsResponse {
+    /// Status of the request
+    pub status: Status,
+    /// Concepts ordered by the number of connections, most connected first
+    pub concepts: Vec<ConnectedConcept>,
+}
+
+/// Query parameters for listing concepts
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ConceptsParams {
+    /// Maximum number of concepts, defaults to 10
+    pub limit: Option<usize>,
+}
+
+/// Lists the most connected concepts in the knowledge graph of a role
+pub(crate) async fn top_concepts(
+    State(config_state): State<ConfigState>,
+    Path(role): Path<String>,
+    Query(params): Query<ConceptsParams>,
+) -> Result<Json<ConceptsResponse>> {
+    log::debug!("top_concepts for role {role}");
+    let terraphim_service = TerraphimService::new(config_state);
+    let concepts = terraphim_service
+        .top_concepts(&role.into(), params.limit.unwrap_or(10))
+        .await
+        .map_err(graph_error)?;
+    Ok(Json(ConceptsResponse {
         status: Status::Success,
-        results,
-        total,
+        concepts,
+    }))
+}
+
+/// Response for listing the neighbours of a concept
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct NeighboursResponse {
+    /// Status of the request
+    pub status: Status,
+    /// Neighbours ordered by weight, heaviest first
+    pub neighbours: Vec<Neighbour>,
+}
+
+/// Lists the concepts co-occurring with a concept in the knowledge graph of
+/// a role
+///
+/// The concept can be given by its ID or by one of its synonyms.
+pub(crate) async fn concept_neighbours(
+    State(config_state): State<ConfigState>,
+    Path((role, concept)): Path<(String, String)>,
+) -> Result<Json<NeighboursResponse>> {
+    log::debug!("concept_neighbours for `{concept}` in role {role}");
+    let terraphim_service = TerraphimService::new(config_state);
+    let neighbours = terraphim_service
+        .neighbours(&role.into(), &concept)
+        .await
+        .map_err(graph_error)?;
+    Ok(Json(NeighboursResponse {
+        status: Status::Success,
+        neighbours,
+    }))
+}
+
+/// Query parameters for finding a path between two concepts
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PathParams {
+    /// ID or synonym of the first concept
+    pub from: String,
+    /// ID or synonym of the last concept
+    pub to: String,
+}
+
+/// Response for finding a path between two concepts
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PathResponse {
+    /// Status of the request
+    pub status: Status,
+    /// Concepts on the shortest path, including the first and the last one.
+    /// `None` if the concepts aren't connected.
+    pub path: Option<Vec<NormalizedTerm>>,
+}
+
+/// Finds the shortest path between two concepts in the knowledge graph of a
+/// role
+pub(crate) async fn shortest_path(
+    State(config_state): State<ConfigState>,
+    Path(role): Path<String>,
+    Query(params): Query<PathParams>,
+) -> Result<Json<PathResponse>> {
+    log::debug!("shortest_path {params:?} in role {role}");
+    let terraphim_service = TerraphimService::new(config_state);
+    let path = terraphim_service
+        .shortest_path(&role.into(), &params.from, &params.to)
+        .await
+        .map_err(graph_error)?;
+    Ok(Json(PathResponse {
+        status: Status::Success,
+        path,
+    }))
+}
+
+/// Query parameters for autocompletion
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AutocompleteParams {
+    /// Role whose knowledge graph is used
+    pub role: String,
+    /// What was typed so far
+    pub q: String,
+    /// Maximum number of suggestions, defaults to 10
+    pub limit: Option<usize>,
+}
+
+/// Response for autocompletion
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AutocompleteResponse {
+    /// Status of the request
+    pub status: Status,
+    /// Suggested synonyms, best first
+    pub suggestions: Vec<Suggestion>,
+}
+
+/// Suggests synonyms of the knowledge graph of a role completing what was
+/// typed
+pub(crate) async fn autocomplete(
+    State(config_state): State<ConfigState>,
+    Query(params): Query<AutocompleteParams>,
+) -> Result<Json<AutocompleteResponse>> {
+    log::debug!("autocomplete {params:?}");
+    let terraphim_service = TerraphimService::new(config_state);
+    let suggestions = terraphim_service
+        .autocomplete(&params.role.into(), &params.q, params.limit.unwrap_or(10))
+        .await
+        .map_err(graph_error)?;
+    Ok(Json(AutocompleteResponse {
+        status: Status::Success,
+        suggestions,
     }))
 }
 
+/// Query parameters for exporting a knowledge graph
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ExportParams {
+    /// Format of the export, defaults to GraphML
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Exports the knowledge graph of a role as GraphML, DOT or JSON Graph
+/// Format
+pub(crate) async fn export_rolegraph(
+    State(config_state): State<ConfigState>,
+    Path(role): Path<String>,
+    Query(params): Query<ExportParams>,
+) -> Result<impl IntoResponse> {
+    log::debug!("export_rolegraph for role {role} as {:?}", params.format);
+    let terraphim_service = TerraphimService::new(config_state);
+    let graph = terraphim_service
+        .export_rolegraph(&role.into(), params.format)
+        .await
+        .map_err(graph_error)?;
+    Ok((
+        [(header::CONTENT_TYPE, params.format.content_type())],
+        graph,
+    ))
+}
+
 /// Response type for showing the config
 ///
 /// This is also used when updating the config
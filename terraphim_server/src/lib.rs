@@ -3,11 +3,11 @@ use std::net::SocketAddr;
 use axum::{
     http::{header, Method, StatusCode, Uri},
     response::{Html, IntoResponse, Response},
-    routing::{get, post},
+    routing::{get, post, put},
     Extension, Router,
 };
 use rust_embed::RustEmbed;
-use terraphim_config::ConfigState;
+use terraphim_config::{ConfigState, SNAPSHOT_INTERVAL};
 use terraphim_types::IndexedDocument;
 use tokio::sync::broadcast::channel;
 use tower_http::cors::{Any, CorsLayer};
@@ -15,8 +15,15 @@ use tower_http::cors::{Any, CorsLayer};
 mod api;
 mod error;
 
-use api::{create_document, health, search_documents, search_documents_post};
-pub use api::{ConfigResponse, CreateDocumentResponse, SearchResponse};
+use api::{
+    autocomplete, concept_neighbours, create_document, delete_document, export_rolegraph, health,
+    search_documents, search_documents_post, shortest_path, top_concepts, update_document,
+};
+pub use api::{
+    AutocompleteParams, AutocompleteResponse, ConceptsParams, ConceptsResponse, ConfigResponse,
+    CreateDocumentResponse, DocumentResponse, ExplainParams, ExportParams, NeighboursResponse,
+    PathParams, PathResponse, SearchResponse,
+};
 pub use error::{Result, Status};
 
 // use axum_embed::ServeEmbed;
@@ -28,6 +35,8 @@ struct Assets;
 
 pub async fn axum_server(server_hostname: SocketAddr, config_state: ConfigState) -> Result<()> {
     log::info!("Starting axum server");
+    let snapshots = config_state.save_rolegraphs_periodically(SNAPSHOT_INTERVAL);
+    let rolegraphs = config_state.clone();
     // let assets = axum_embed::ServeEmbed::<Assets>::with_parameters(Some("index.html".to_owned()),axum_embed::FallbackBehavior::Ok, Some("index.html".to_owned()));
     let (tx, _rx) = channel::<IndexedDocument>(10);
 
@@ -38,6 +47,18 @@ pub async fn axum_server(server_hostname: SocketAddr, config_state: ConfigState)
         .route("/documents/", post(create_document))
         .route("/documents/search", get(search_documents))
         .route("/documents/search", post(search_documents_post))
+        .route(
+            "/documents/:id",
+            put(update_document).delete(delete_document),
+        )
+        .route("/rolegraph/:role/concepts", get(top_concepts))
+        .route(
+            "/rolegraph/:role/concepts/:concept/neighbours",
+            get(concept_neighbours),
+        )
+        .route("/rolegraph/:role/path", get(shortest_path))
+        .route("/rolegraph/:role/export", get(export_rolegraph))
+        .route("/autocomplete", get(autocomplete))
         .route("/config", get(api::get_config))
         .route("/config/", get(api::get_config))
         .route("/config", post(api::update_config))
@@ -69,11 +90,49 @@ pub async fn axum_server(server_hostname: SocketAddr, config_state: ConfigState)
 
     axum::Server::bind(&server_hostname)
         .serve(app.into_make_service())
+        .with_graceful_shutdown(shutdown_signal())
         .await?;
 
+    // Save the changes since the last periodic save
+    snapshots.abort();
+    if let Err(e) = rolegraphs.save_rolegraphs().await {
+        log::warn!("Failed to save rolegraph snapshots: {e:?}");
+    }
     Ok(())
 }
 
+/// Resolves when the server is asked to stop with Ctrl+C, or with SIGTERM
+/// on Unix, which is how service managers and container runtimes stop it
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            log::error!("Failed to listen for Ctrl+C: {e:?}");
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                log::error!("Failed to listen for SIGTERM: {e:?}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    log::info!("Shutting down axum server");
+}
+
 async fn static_handler(uri: Uri) -> impl IntoResponse {
     let path = uri.path().trim_start_matches('/');
 
@@ -17,16 +17,58 @@
 
 
 use anyhow::Context;
+use clap::{Parser, Subcommand};
 use std::net::SocketAddr;
+use std::path::PathBuf;
 use terraphim_config::{ConfigBuilder, ConfigId};
 use terraphim_persistence::Persistable;
 use terraphim_config::ConfigState;
+use terraphim_rolegraph::ExportFormat;
 use terraphim_server::{axum_server, Result};
+use terraphim_service::TerraphimService;
 use terraphim_settings::DeviceSettings;
 
+/// Terraphim AI server
+#[derive(Debug, Parser)]
+#[command(version, about)]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// Commands of the server, running the server is the default
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Run the server
+    Serve,
+    /// Export the knowledge graph of a role
+    Export {
+        /// Name of the role
+        #[arg(long)]
+        role: String,
+        /// Format of the export: graphml, dot or json
+        #[arg(long, default_value = "graphml")]
+        format: ExportFormat,
+        /// File to write the export to, defaults to stdout
+        #[arg(long)]
+        output: Option<PathBuf>,
+    },
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    match run_server().await {
+    // Set up logger for the server
+    env_logger::init();
+
+    let result = match Args::parse().command.unwrap_or(Command::Serve) {
+        Command::Serve => run_server().await,
+        Command::Export {
+            role,
+            format,
+            output,
+        } => export_rolegraph(role, format, output).await,
+    };
+    match result {
         Ok(()) => Ok(()),
         Err(e) => {
             log::error!("Error: {e:#?}");
@@ -36,9 +78,6 @@ async fn main() -> Result<()> {
 }
 
 async fn run_server() -> Result<()> {
-    // Set up logger for the server
-    env_logger::init();
-
     let server_settings =
         DeviceSettings::load_from_env_and_file(None).context("Failed to load settings")?;
     log::info!(
@@ -54,21 +93,16 @@ async fn run_server() -> Result<()> {
             SocketAddr::from(([127, 0, 0, 1], port))
         });
 
-    
-        let mut config = match ConfigBuilder::new_with_id(ConfigId::Server).build() {
-            Ok(mut local_config) => match local_config.load().await {
-                Ok(config) => config,
-                Err(e) => {
-                    log::info!("Failed to load config: {:?}", e);
-                    let config = ConfigBuilder::new().build_default_server().build().unwrap();
-                    config
-                },
-            },
-            Err(e) => panic!("Failed to build config: {:?}", e),
-        };
-    let config_state = ConfigState::new(&mut config)
-        .await
-        .context("Failed to load config")?;
+    let config_state = load_config_state().await?;
+
+    // Keep haystacks and local knowledge graphs up to date while running
+    let _watcher = match terraphim_middleware::watcher::watch(config_state.clone()).await {
+        Ok(watcher) => Some(watcher),
+        Err(e) => {
+            log::warn!("Failed to watch haystacks for changes: {e:?}");
+            None
+        }
+    };
 
     // Example of adding a role for testing
     // let role = "system operator2".to_string();
@@ -86,3 +120,39 @@ async fn run_server() -> Result<()> {
 
     Ok(())
 }
+
+/// Load the server config and build the rolegraphs of all roles
+async fn load_config_state() -> Result<ConfigState> {
+    let mut config = match ConfigBuilder::new_with_id(ConfigId::Server).build() {
+        Ok(mut local_config) => match local_config.load().await {
+            Ok(config) => config,
+            Err(e) => {
+                log::info!("Failed to load config: {:?}", e);
+                ConfigBuilder::new().build_default_server().build().unwrap()
+            }
+        },
+        Err(e) => panic!("Failed to build config: {:?}", e),
+    };
+    let config_state = ConfigState::new(&mut config)
+        .await
+        .context("Failed to load config")?;
+    Ok(config_state)
+}
+
+/// Export the knowledge graph of a role to a file or stdout
+async fn export_rolegraph(
+    role: String,
+    format: ExportFormat,
+    output: Option<PathBuf>,
+) -> Result<()> {
+    let config_state = load_config_state().await?;
+    let graph = TerraphimService::new(config_state)
+        .export_rolegraph(&role.into(), format)
+        .await?;
+    match output {
+        Some(path) => std::fs::write(&path, graph)
+            .with_context(|| format!("Failed to write export to {}", path.display()))?,
+        None => print!("{graph}"),
+    }
+    Ok(())
+}
@@ -6,14 +6,17 @@
 mod tests {
     use ahash::AHashMap;
     use terraphim_automata::AutomataPath;
-    use terraphim_server::{axum_server, CreateDocumentResponse, SearchResponse, Status};
+    use terraphim_server::{
+        axum_server, AutocompleteResponse, ConceptsResponse, CreateDocumentResponse,
+        DocumentResponse, NeighboursResponse, SearchResponse, Status,
+    };
     use terraphim_settings::DeviceSettings;
 
     use reqwest::{Client, StatusCode};
     use std::{net::SocketAddr, path::PathBuf, time::Duration};
     use terraphim_config::{
         Config, ConfigBuilder, ConfigState, Haystack, KnowledgeGraph, KnowledgeGraphLocal, Role,
-        ServiceType,
+        ServiceType, SynonymProperties,
     };
     use terraphim_types::{KnowledgeGraphInputType, RelevanceFunction, RoleName};
 
@@ -55,6 +58,7 @@ mod tests {
                         knowledge_graph_local: Some(KnowledgeGraphLocal {
                             input_type: KnowledgeGraphInputType::Markdown,
                             path: PathBuf::from("/tmp/system_operator/pages/"),
+                            synonyms: SynonymProperties::default(),
                         }),
                         public: true,
                         publish: true,
@@ -78,6 +82,7 @@ mod tests {
                         knowledge_graph_local: Some(KnowledgeGraphLocal {
                             input_type: KnowledgeGraphInputType::Markdown,
                             path: PathBuf::from("/tmp/system_operator/pages/"),
+                            synonyms: SynonymProperties::default(),
                         }),
                         public: true,
                         publish: true,
@@ -256,6 +261,134 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn test_concept_exploration() {
+        let server = ensure_server_started().await;
+
+        let response = reqwest::get(format!(
+            "http://{server}/rolegraph/System%20Operator/concepts?limit=5"
+        ))
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response: ConceptsResponse = response.json().await.unwrap();
+        assert!(response.concepts.len() <= 5);
+
+        for concept in response.concepts {
+            let response = reqwest::get(format!(
+                "http://{server}/rolegraph/System%20Operator/concepts/{}/neighbours",
+                concept.concept.id
+            ))
+            .await
+            .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let response: NeighboursResponse = response.json().await.unwrap();
+            assert_eq!(!response.neighbours.is_empty(), concept.degree > 0);
+        }
+
+        // Unknown concepts and roles without a knowledge graph
+        let response = reqwest::get(format!(
+            "http://{server}/rolegraph/System%20Operator/concepts/not%20a%20concept/neighbours"
+        ))
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = reqwest::get(format!("http://{server}/rolegraph/Default/concepts"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_autocomplete() {
+        let server = ensure_server_started().await;
+
+        let response = reqwest::get(format!(
+            "http://{server}/autocomplete?role=System%20Operator&q=life%20cy&limit=3"
+        ))
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response: AutocompleteResponse = response.json().await.unwrap();
+        assert_eq!(response.status, Status::Success);
+        assert!(!response.suggestions.is_empty());
+        assert!(response.suggestions.len() <= 3);
+        assert!(response.suggestions[0].term.as_str().starts_with("life cy"));
+
+        let response = reqwest::get(format!("http://{server}/autocomplete?role=Default&q=life"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_export_rolegraph() {
+        let server = ensure_server_started().await;
+
+        for (format, content_type) in [
+            ("graphml", "application/graphml+xml"),
+            ("dot", "text/vnd.graphviz"),
+            ("json", "application/json"),
+        ] {
+            let response = reqwest::get(format!(
+                "http://{server}/rolegraph/System%20Operator/export?format={format}"
+            ))
+            .await
+            .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            assert_eq!(response.headers()["content-type"], content_type);
+            assert!(!response.text().await.unwrap().is_empty());
+        }
+
+        let response = reqwest::get(format!(
+            "http://{server}/rolegraph/System%20Operator/export?format=csv"
+        ))
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_search_documents_explain() {
+        let server = ensure_server_started().await;
+
+        let response = reqwest::get(format!(
+            "http://{server}/documents/search?search_term=system&explain=true",
+        ))
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response: SearchResponse = response.json().await.unwrap();
+        assert!(matches!(response.status, Status::Success));
+        let explanations = response.explanations.unwrap();
+        assert_eq!(explanations.len(), response.results.len());
+        for (document, explanation) in response.results.iter().zip(&explanations) {
+            assert_eq!(document.id, explanation.document_id);
+            let total: u64 = explanation
+                .contributions
+                .iter()
+                .map(|contribution| contribution.total())
+                .sum();
+            assert_eq!(total, explanation.rank);
+        }
+
+        // Explanations are left out unless requested
+        let response: SearchResponse = reqwest::get(format!(
+            "http://{server}/documents/search?search_term=system"
+        ))
+        .await
+        .unwrap()
+        .json()
+        .await
+        .unwrap();
+        assert!(response.explanations.is_none());
+    }
+
     #[tokio::test]
     #[serial]
     async fn test_get_config() {
@@ -336,4 +469,41 @@ mod tests {
         assert!(matches!(response.status, Status::Success));
         assert_eq!(response.id, "Title of the document");
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn test_update_and_delete_document() {
+        let server = ensure_server_started().await;
+        let client = Client::new();
+        let url = format!("http://{server}/documents/document-to-delete");
+        let response = client
+            .put(&url)
+            .header("Content-Type", "application/json")
+            .body(
+                r#"
+            {
+                "id": "ignored, the id is taken from the path",
+                "title": "Document to delete",
+                "url": "url_of_the_document",
+                "body": "Life cycle concepts and Trained operators and maintainers, project direction"
+            }
+            "#,
+            )
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response: DocumentResponse = response.json().await.unwrap();
+        assert!(matches!(response.status, Status::Success));
+        assert_eq!(response.id, "document-to-delete");
+
+        let response = client.delete(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response: DocumentResponse = response.json().await.unwrap();
+        assert!(matches!(response.status, Status::Success));
+
+        // The document is gone now
+        let response = client.delete(&url).send().await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
 }